@@ -0,0 +1,138 @@
+use crate::{
+    count_documents, Bincode, Client, ClientBuilder, DatabaseError, Format, LoadError, RecordIter,
+    VerifyReport,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::Path;
+
+/// A client that can only add documents to a file, never remove or overwrite them, such as
+/// for audit logs.
+///
+/// Unlike a [`Client`], it has no method that truncates, rewrites or removes anything,
+/// and it does not give access to the client it wraps, so code that is handed one cannot
+/// delete history. The file is always opened in append mode.
+///
+/// Since documents are never removed, the client keeps count of them: the file is only
+/// walked once to count the documents it already holds, and every append returns the
+/// new total. Documents appended by other clients are not counted.
+pub struct AppendOnlyClient<T: Serialize + DeserializeOwned, F: Format = Bincode> {
+    client: Client<T, F>,
+    count: Option<usize>,
+}
+
+impl<T, F> AppendOnlyClient<T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+{
+    /// Opens the file at `path` in append mode, or creates it if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// See [`ClientBuilder::open`].
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, DatabaseError> {
+        Self::with_builder(path, ClientBuilder::new())
+    }
+
+    /// Opens the file at `path` with the options configured on `builder`, which is always
+    /// switched to append mode.
+    ///
+    /// # Errors
+    ///
+    /// See [`ClientBuilder::open`].
+    pub fn with_builder<P: AsRef<Path>>(
+        path: P,
+        mut builder: ClientBuilder,
+    ) -> Result<Self, DatabaseError> {
+        let client = builder.append(true).open(path)?;
+        Ok(Self {
+            client,
+            count: None,
+        })
+    }
+
+    /// Returns the number of documents in the file, walking the file on the first call
+    /// only.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn len(&mut self) -> Result<usize, DatabaseError> {
+        if let Some(count) = self.count {
+            return Ok(count);
+        }
+        let count = count_documents(&mut self.client.file, &self.client.layout)?;
+        self.count = Some(count);
+        Ok(count)
+    }
+
+    /// Returns `true` if the file holds no documents. See [`len`](AppendOnlyClient::len).
+    ///
+    /// # Errors
+    ///
+    /// See [`len`](AppendOnlyClient::len).
+    pub fn is_empty(&mut self) -> Result<bool, DatabaseError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Appends a document to the end of the file and returns the new number of
+    /// documents. See [`Client::write`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::write`] and [`len`](AppendOnlyClient::len).
+    pub fn append_one(&mut self, document: &T) -> Result<usize, DatabaseError> {
+        self.append_many(std::array::from_ref(document))
+    }
+
+    /// Appends documents to the end of the file and returns the new number of
+    /// documents. See [`Client::write_many`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::write_many`] and [`len`](AppendOnlyClient::len).
+    pub fn append_many(&mut self, documents: &[T]) -> Result<usize, DatabaseError> {
+        let count = self.len()?;
+        self.client.write_many(documents)?;
+        let count = count + documents.len();
+        self.count = Some(count);
+        Ok(count)
+    }
+
+    /// Appends a compressed document to the end of the file and returns the new number
+    /// of documents. See [`Client::write_compressed`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::write_compressed`] and [`len`](AppendOnlyClient::len).
+    pub fn append_compressed(&mut self, document: &T) -> Result<usize, DatabaseError> {
+        let count = self.len()?;
+        self.client.write_compressed(document)?;
+        self.count = Some(count + 1);
+        Ok(count + 1)
+    }
+
+    /// Returns every document. See [`Client::load`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::load`].
+    pub fn load(&mut self) -> Result<Option<Vec<T>>, LoadError<T>> {
+        self.client.load()
+    }
+
+    /// Returns an iterator over every document. See [`Client::iter`].
+    pub fn iter(&mut self) -> RecordIter<'_, T, F> {
+        self.client.iter()
+    }
+
+    /// Checks the checksum of every document. See [`Client::verify`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::verify`].
+    pub fn verify(&mut self) -> Result<VerifyReport, DatabaseError> {
+        self.client.verify()
+    }
+}
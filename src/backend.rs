@@ -0,0 +1,128 @@
+use std::{
+    fs::File,
+    io::{self, Cursor, Read, Seek, Write},
+};
+
+/// The storage a [`Client`](crate::Client) keeps its documents in. Clients opened by path
+/// store them in a [`File`], and [`Client::from_backend`](crate::Client::from_backend)
+/// creates a client on any other backend, e.g. a `Cursor<Vec<u8>>` in tests or a custom
+/// byte store.
+///
+/// The bytes stored are the same as in a file written by this crate, header included.
+/// Documents are appended by seeking to the end and writing, and overwritten in place by
+/// seeking back, so a backend has to support writing at any offset.
+///
+/// Operations that open, lock or replace the file by path, such as `reader()`, `lock()`
+/// or the backups of [`ClientBuilder::keep_backup`](crate::ClientBuilder::keep_backup),
+/// only exist for a `File`. The operations that rewrite every document, such as
+/// `vacuum()` or `replace_all()`, write the new documents into memory first and then
+/// replace the contents of the backend, which is not atomic unlike the rename used for
+/// a file.
+pub trait Backend: Read + Write + Seek {
+    /// Returns the number of bytes stored.
+    fn size(&self) -> io::Result<u64>;
+
+    /// Reads bytes starting at `offset` into `buf` like [`Read::read`], without going
+    /// through the cursor, so that a client can read through a shared reference.
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize>;
+
+    /// Truncates the stored bytes to `len`, or extends them with zeros.
+    fn set_len(&mut self, len: u64) -> io::Result<()>;
+
+    /// Makes every byte written so far durable.
+    fn sync(&mut self) -> io::Result<()>;
+
+    /// Returns the backend as a file, if it is one, for the operations that replace it by
+    /// path. Custom backends keep the default, which returns `None`.
+    fn as_file(&mut self) -> Option<&mut File> {
+        None
+    }
+}
+
+impl Backend for File {
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        read_at(self, buf, offset)
+    }
+
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        File::set_len(self, len)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.sync_all()
+    }
+
+    fn as_file(&mut self) -> Option<&mut File> {
+        Some(self)
+    }
+}
+
+impl Backend for Cursor<Vec<u8>> {
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.get_ref().len() as u64)
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let bytes = self.get_ref();
+        let start = usize::try_from(offset).map_or(bytes.len(), |offset| offset.min(bytes.len()));
+        (&bytes[start..]).read(buf)
+    }
+
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        let len = usize::try_from(len).map_err(|_| io::Error::from(io::ErrorKind::OutOfMemory))?;
+        self.get_mut().resize(len, 0);
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Lets a client borrow its backend, e.g. to inspect the bytes of a `Cursor<Vec<u8>>` once
+/// the client is dropped.
+impl<B: Backend + ?Sized> Backend for &mut B {
+    fn size(&self) -> io::Result<u64> {
+        (**self).size()
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        (**self).read_at(buf, offset)
+    }
+
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        (**self).set_len(len)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        (**self).sync()
+    }
+
+    fn as_file(&mut self) -> Option<&mut File> {
+        (**self).as_file()
+    }
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buf, offset)
+}
+
+// Unlike on Unix, a positioned read moves the cursor on Windows, which every other
+// method seeks past anyway.
+#[cfg(windows)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, buf, offset)
+}
+
+// Without positioned reads, the shared cursor is moved, so concurrent reads through a
+// shared reference may read from the wrong offset.
+#[cfg(not(any(unix, windows)))]
+fn read_at(mut file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    file.seek(io::SeekFrom::Start(offset))?;
+    file.read(buf)
+}
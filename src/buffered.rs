@@ -0,0 +1,127 @@
+use crate::{write_encoded, Bincode, Client, DatabaseError, Format, LoadError};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Wraps a [`Client`] to coalesce many small appends into few large writes, e.g. for an
+/// application that writes every incoming batch as soon as it arrives.
+///
+/// Documents are encoded right away into a buffer in memory, which is only appended to
+/// the file once it holds `capacity` bytes or more, or when the client is flushed or
+/// dropped. Until then, buffered documents are neither visible to other readers of the
+/// file nor safe from a crash of the process, and an error while writing them on drop is
+/// ignored. Call [`flush`](BufferedClient::flush) to write them on demand.
+pub struct BufferedClient<T: Serialize + DeserializeOwned, F: Format = Bincode> {
+    client: Client<T, F>,
+    capacity: usize,
+    buffer: Vec<u8>,
+    scratch: Vec<u8>,
+}
+
+impl<T, F> BufferedClient<T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+{
+    /// Wraps `client`, buffering up to `capacity` bytes of documents before writing them.
+    pub fn new(client: Client<T, F>, capacity: usize) -> Self {
+        Self {
+            client,
+            capacity,
+            buffer: Vec::with_capacity(capacity),
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Buffers a document, writing the buffer to the file if it is full. See
+    /// [`Client::write`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::write`] and [`flush`](BufferedClient::flush).
+    pub fn write(&mut self, document: &T) -> Result<(), DatabaseError> {
+        self.write_many(std::array::from_ref(document))
+    }
+
+    /// Buffers documents, writing the buffer to the file if it is full. See
+    /// [`Client::write_many`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::write_many`] and [`flush`](BufferedClient::flush).
+    pub fn write_many(&mut self, documents: &[T]) -> Result<(), DatabaseError> {
+        let len = self.buffer.len();
+        let layout = &self.client.layout;
+        if let Err(e) =
+            write_encoded::<F, _, T, _>(&mut self.buffer, documents, layout, &mut self.scratch)
+        {
+            self.buffer.truncate(len);
+            return Err(e);
+        }
+        if self.buffer.len() >= self.capacity {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Appends every buffered document to the file, returning whether there were any.
+    /// The documents stay buffered if the write fails.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn flush(&mut self) -> Result<bool, DatabaseError> {
+        if self.buffer.is_empty() {
+            return Ok(false);
+        }
+        self.client.append_raw_frames(&self.buffer, false)?;
+        self.buffer.clear();
+        Ok(true)
+    }
+
+    /// Appends every buffered document to the file like [`flush`](BufferedClient::flush),
+    /// and then makes every write durable with [`Client::fence`].
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn sync(&mut self) -> Result<(), DatabaseError> {
+        self.flush()?;
+        self.client.fence()
+    }
+
+    /// Writes every buffered document and returns the collection, which therefore holds
+    /// every document written so far. See [`Client::load`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::load`] and [`flush`](BufferedClient::flush).
+    pub fn load(&mut self) -> Result<Option<Vec<T>>, LoadError<T>> {
+        self.flush()?;
+        self.client.load()
+    }
+
+    /// Returns the number of bytes of documents that have not been written yet.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Writes every buffered document and returns the client it wraps, e.g. to read the
+    /// file, which then holds every document written so far.
+    ///
+    /// # Errors
+    ///
+    /// See [`flush`](BufferedClient::flush).
+    pub fn get_mut(&mut self) -> Result<&mut Client<T, F>, DatabaseError> {
+        self.flush()?;
+        Ok(&mut self.client)
+    }
+}
+
+impl<T, F> Drop for BufferedClient<T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+{
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
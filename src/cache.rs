@@ -0,0 +1,213 @@
+use crate::{Bincode, Client, DatabaseError, Format, WriteReport};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Hit and miss counters of a [`CachedClient`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of reads that were served from the cache.
+    pub hits: u64,
+    /// Number of reads that had to go to the file.
+    pub misses: u64,
+}
+
+/// Wraps a [`Client`] with a cache of recently read documents, keyed by their index, so
+/// that repeated reads of the same documents do not touch the file.
+///
+/// Once the cache holds `capacity` documents, the least recently read one is evicted.
+/// Every method that changes which document lives at an index, such as
+/// [`remove_at`](CachedClient::remove_at), invalidates the affected entries. Appending
+/// documents never moves existing ones, so it keeps the cache as is. Writes made to the
+/// file in any other way, e.g. by another client, are not noticed.
+pub struct CachedClient<T: Serialize + DeserializeOwned + Clone, F: Format = Bincode> {
+    client: Client<T, F>,
+    capacity: usize,
+    entries: HashMap<usize, (T, u64)>,
+    recency: BTreeMap<u64, usize>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+impl<T, F> CachedClient<T, F>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    F: Format,
+{
+    /// Wraps `client` with a cache holding up to `capacity` documents. A capacity of `0`
+    /// disables caching.
+    pub fn new(client: Client<T, F>, capacity: usize) -> Self {
+        Self {
+            client,
+            capacity,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Returns the document at `index`, or `None` if the collection has `index`
+    /// documents or fewer. Removed documents are not counted.
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::get_many`].
+    pub fn get(&mut self, index: usize) -> Result<Option<T>, DatabaseError> {
+        self.tick += 1;
+        if let Some((document, last_used)) = self.entries.get_mut(&index) {
+            self.recency.remove(last_used);
+            *last_used = self.tick;
+            self.recency.insert(self.tick, index);
+            self.stats.hits += 1;
+            return Ok(Some(document.clone()));
+        }
+        self.stats.misses += 1;
+        let document = self.client.get_many(&[index])?.pop().flatten();
+        if let Some(document) = &document {
+            self.insert(index, document.clone());
+        }
+        Ok(document)
+    }
+
+    fn insert(&mut self, index: usize, document: T) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.entries.len() >= self.capacity {
+            let Some((_, evicted)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&evicted);
+        }
+        self.entries.insert(index, (document, self.tick));
+        self.recency.insert(self.tick, index);
+    }
+
+    /// Returns the hit and miss counters since the client was created.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Returns the maximum number of documents the cache holds.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes the maximum number of documents the cache holds, evicting the least
+    /// recently read documents if it holds more than that.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > capacity {
+            let Some((_, evicted)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&evicted);
+        }
+    }
+
+    /// Empties the cache without resetting its counters.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    /// Drops every cached document whose index is `index` or later.
+    fn invalidate_from(&mut self, index: usize) {
+        let recency = &mut self.recency;
+        self.entries.retain(|&i, (_, last_used)| {
+            let keep = i < index;
+            if !keep {
+                recency.remove(last_used);
+            }
+            keep
+        });
+    }
+
+    /// Appends a document. See [`Client::write`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::write`].
+    pub fn write(&mut self, document: &T) -> Result<WriteReport, DatabaseError> {
+        self.client.write(document)
+    }
+
+    /// Appends documents. See [`Client::write_many`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::write_many`].
+    pub fn write_many(&mut self, documents: &[T]) -> Result<WriteReport, DatabaseError> {
+        self.client.write_many(documents)
+    }
+
+    /// Removes the document at `index`, which shifts every following document down by
+    /// one. See [`Client::remove_at`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::remove_at`].
+    pub fn remove_at(&mut self, index: usize) -> Result<bool, DatabaseError> {
+        let removed = self.client.remove_at(index)?;
+        if removed {
+            self.invalidate_from(index);
+        }
+        Ok(removed)
+    }
+
+    /// Inserts a document in front of the document at `index`, which shifts it and every
+    /// following document up by one. See [`Client::insert_at`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::insert_at`].
+    pub fn insert_at(&mut self, index: usize, document: &T) -> Result<(), DatabaseError> {
+        let result = self.client.insert_at(index, document);
+        self.invalidate_from(index);
+        result
+    }
+
+    /// Removes every document matching `predicate`. See [`Client::remove_where`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::remove_where`].
+    pub fn remove_where<P>(&mut self, predicate: P) -> Result<usize, DatabaseError>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let result = self.client.remove_where(predicate);
+        self.clear();
+        result
+    }
+
+    /// Removes the first `n` documents. See [`Client::trim_prefix`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::trim_prefix`].
+    pub fn trim_prefix(&mut self, n: usize) -> Result<usize, DatabaseError> {
+        let result = self.client.trim_prefix(n);
+        self.clear();
+        result
+    }
+
+    /// Replaces every document. See [`Client::replace_all_iter`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::replace_all_iter`].
+    pub fn replace_all_iter<I>(&mut self, documents: I) -> Result<usize, DatabaseError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let result = self.client.replace_all_iter(documents);
+        self.clear();
+        result
+    }
+
+    /// Returns the wrapped client, dropping the cache.
+    pub fn into_inner(self) -> Client<T, F> {
+        self.client
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{binary_to_vec, header::Layout, write_encoded, Bincode, DatabaseError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{Read, Write};
+
+/// Encodes `data` as frames and writes them to `w`, e.g. to send documents over a socket
+/// or through a compressor. The frames are those of a file without a header, i.e.
+/// bincode documents with a little-endian CRC32 checksum, so the bytes can be read by
+/// a [`Client`](crate::Client) once written to a file, or read back with [`read_frames`].
+///
+/// # Errors
+///
+/// - `bincode::Error` if a document fails to serialize.
+///
+/// - `std::io::Error`
+pub fn write_frames<W: Write, T: Serialize>(w: &mut W, data: &[T]) -> Result<(), DatabaseError> {
+    write_encoded::<Bincode, _, T, _>(w, data, &Layout::default(), &mut Vec::new())
+}
+
+/// Reads frames written by [`write_frames`] from `r` until it ends, and returns their
+/// documents. Since the whole stream is read before any document is decoded, `r` has to
+/// end, e.g. a socket has to be shut down by the other side.
+///
+/// # Errors
+///
+/// - If a checksum mismatch occurs, a `DatabaseError::CorruptedFrame` error is
+///   returned, at the offset of the corrupted frame in the stream.
+///
+/// - `bincode::Error` if a document fails to deserialize.
+///
+/// - `std::io::Error`
+pub fn read_frames<R: Read, T: DeserializeOwned>(r: &mut R) -> Result<Vec<T>, DatabaseError> {
+    let mut buf = Vec::new();
+    r.read_to_end(&mut buf)?;
+    Ok(binary_to_vec::<Bincode, T>(&buf, &Layout::default(), true)?)
+}
@@ -0,0 +1,147 @@
+use crate::{data_end, header::Layout, read_frame, Backend, DatabaseError, Format};
+use serde::de::DeserializeOwned;
+use std::{
+    fs::File,
+    io::{ErrorKind, Read, SeekFrom},
+    marker::PhantomData,
+};
+
+/// A cursor over the frames of a file, returned by [`Client::cursor`](crate::Client::cursor).
+///
+/// Unlike [`RecordIter`](crate::RecordIter), a cursor can be moved around: frames can be
+/// skipped without being decoded, and the cursor can be moved to any frame by its index,
+/// which counts every frame including removed documents. Reading with `next()` returns
+/// the document of the next frame that has not been removed.
+///
+/// An error does not stop the cursor: a frame that fails its checksum or fails to decode
+/// is still skipped over, so the next call moves on to the frame after it. Only a frame
+/// that ends past the end of the file moves the cursor to the end.
+pub struct Cursor<'a, T, F, B = File> {
+    file: &'a mut B,
+    layout: Layout,
+    end: u64,
+    offset: u64,
+    index: usize,
+    _phantom: PhantomData<(T, F)>,
+}
+
+impl<'a, T, F, B: Backend> Cursor<'a, T, F, B> {
+    pub(crate) fn new(file: &'a mut B, layout: Layout) -> Result<Self, DatabaseError> {
+        layout.require_frame_per_document()?;
+        let end = data_end(file, &layout)?;
+        Ok(Self {
+            file,
+            layout,
+            end,
+            offset: layout.data_start,
+            index: 0,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Returns the offset in the file of the frame the cursor is at, which is the end of
+    /// the documents once every frame has been read.
+    pub fn current_offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the index of the frame the cursor is at, counting removed documents.
+    pub fn frame_index(&self) -> usize {
+        self.index
+    }
+
+    /// Moves the cursor past the frame it is at by reading only its header, and returns
+    /// `false` if the cursor was already at the end.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the frame ends past the end of the
+    ///   file.
+    pub fn skip_frame(&mut self) -> Result<bool, DatabaseError> {
+        if self.offset >= self.end {
+            return Ok(false);
+        }
+        let next = self.frame_end().inspect_err(|_| self.offset = self.end)?;
+        self.advance(next);
+        Ok(true)
+    }
+
+    /// Moves the cursor to the frame at `index`, counting removed documents, and returns
+    /// `false` if the file has fewer frames, leaving the cursor at the end. Only frame
+    /// headers are read, starting over from the first frame when moving backwards.
+    ///
+    /// # Errors
+    ///
+    /// See [`skip_frame`](Cursor::skip_frame).
+    pub fn seek_to_frame(&mut self, index: usize) -> Result<bool, DatabaseError> {
+        if index < self.index {
+            self.offset = self.layout.data_start;
+            self.index = 0;
+        }
+        while self.index < index {
+            if !self.skip_frame()? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Returns the end of the frame the cursor is at, after checking that it is in the file.
+    fn frame_end(&mut self) -> Result<u64, DatabaseError> {
+        self.file.seek(SeekFrom::Start(self.offset + 4))?;
+        let len = self.layout.endianness.read_u32(self.file)?;
+        let next = self.offset + self.layout.frame_len(len);
+        if next > self.end {
+            return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+        }
+        Ok(next)
+    }
+
+    fn advance(&mut self, next: u64) {
+        self.offset = next;
+        self.index += 1;
+    }
+}
+
+impl<T, F, B> Cursor<'_, T, F, B>
+where
+    T: DeserializeOwned,
+    F: Format,
+    B: Backend,
+{
+    /// Reads the documents of the frames from the cursor on until one has not been
+    /// removed.
+    fn read_next(&mut self) -> Result<Option<T>, DatabaseError> {
+        while self.offset < self.end {
+            let next = self.frame_end().inspect_err(|_| self.offset = self.end)?;
+            self.file.seek(SeekFrom::Start(self.offset))?;
+            let frame = read_frame(
+                &mut (&mut *self.file).take(next - self.offset),
+                &self.layout,
+            );
+            self.advance(next);
+            let frame = frame?;
+            if frame.removed {
+                continue;
+            }
+            let body = frame.verify(&self.layout)?;
+            let (extras, data) = self.layout.split_body(&body)?;
+            let data = self.layout.frame_data(extras, data)?;
+            return self.layout.decode::<F, T>(&data).map(Some);
+        }
+        Ok(None)
+    }
+}
+
+impl<T, F, B> Iterator for Cursor<'_, T, F, B>
+where
+    T: DeserializeOwned,
+    F: Format,
+    B: Backend,
+{
+    type Item = Result<T, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next().transpose()
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{Client, DatabaseError, Format};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A difference between two collections, returned by [`diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diff<T> {
+    /// The first collection has a document at `index`, but the second one is shorter.
+    OnlyInA { index: usize, document: T },
+    /// The second collection has a document at `index`, but the first one is shorter.
+    OnlyInB { index: usize, document: T },
+    /// Both collections have a document at `index`, but they are not equal.
+    Changed { index: usize, a: T, b: T },
+}
+
+/// Compares the documents of two clients index by index, e.g. to check that a migration
+/// or a compaction preserved every document. Both files are read in lockstep, one
+/// document at a time, so only the differences are held in memory.
+///
+/// # Errors
+///
+/// - If a checksum mismatch occurs in either file, a
+///   `DatabaseError::MismatchedChecksum` error is returned.
+///
+/// - `bincode::Error` if a document fails to deserialize.
+///
+/// - `std::io::Error`
+pub fn diff<T, F>(a: &mut Client<T, F>, b: &mut Client<T, F>) -> Result<Vec<Diff<T>>, DatabaseError>
+where
+    T: Serialize + DeserializeOwned + PartialEq,
+    F: Format,
+{
+    let mut diffs = Vec::new();
+    let mut a = a.iter();
+    let mut b = b.iter();
+    for index in 0.. {
+        let diff = match (a.next().transpose()?, b.next().transpose()?) {
+            (None, None) => break,
+            (Some(a), Some(b)) if a == b => continue,
+            (Some(a), Some(b)) => Diff::Changed { index, a, b },
+            (Some(document), None) => Diff::OnlyInA { index, document },
+            (None, Some(document)) => Diff::OnlyInB { index, document },
+        };
+        diffs.push(diff);
+    }
+    Ok(diffs)
+}
@@ -0,0 +1,241 @@
+use crate::{header::Layout, Bincode, ClientBuilder, DatabaseError, Format};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::File,
+    io::{ErrorKind, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+/// Size of the checksum stored in front of every document.
+const CHECKSUM_LEN: u64 = 4;
+
+/// A client for documents that all have the same encoded size, such as structs made up
+/// of fixed-width integers.
+///
+/// Since every document has the same size, frames only store a checksum and no length,
+/// and a document can be read by its index without walking the rest of the file. The
+/// size is detected from the first document that is written and recorded in a header at
+/// the start of the file; every following document must have the same encoded size.
+///
+/// Note that `bincode` encodes collections and strings with their length, so types
+/// containing them usually do not have a fixed size.
+pub struct FixedClient<T: Serialize + DeserializeOwned, F: Format = Bincode> {
+    file: File,
+    layout: Layout,
+    _phantom: std::marker::PhantomData<(T, F)>,
+}
+
+impl<T, F> FixedClient<T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+{
+    /// Creates a new client. The `append` parameter behaves the same as in
+    /// [`Client::new`](crate::Client::new).
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Open` if it fails to open or create a new file.
+    ///
+    /// - `DatabaseError::Unsupported` if an existing file does not store fixed-size
+    ///   documents.
+    ///
+    /// - `DatabaseError::BadHeader`, `DatabaseError::UnsupportedVersion` or
+    ///   `DatabaseError::FormatMismatch` if the header of an existing file does not match.
+    pub fn new<P: AsRef<Path>>(path: P, append: bool) -> Result<Self, DatabaseError> {
+        let (mut file, _) = ClientBuilder::new()
+            .append(append)
+            .open_file(path.as_ref())?;
+        let layout = if file.metadata()?.len() == 0 {
+            Layout {
+                format: F::ID,
+                ..Layout::default()
+            }
+        } else {
+            let layout = Layout::read(&mut file, None)?;
+            if layout.format != F::ID {
+                return Err(DatabaseError::FormatMismatch {
+                    expected: F::ID,
+                    found: layout.format,
+                });
+            }
+            if layout.record_size.is_none() {
+                return Err(DatabaseError::Unsupported(
+                    "file does not store fixed-size documents",
+                ));
+            }
+            layout
+        };
+        Ok(Self {
+            file,
+            layout,
+            _phantom: std::marker::PhantomData,
+        })
+    }
+
+    /// Returns the encoded size of every document, or `None` if nothing has been written
+    /// to the file yet.
+    pub fn record_size(&self) -> Option<u32> {
+        self.layout.record_size
+    }
+
+    /// Writes the provided serializable document to disk.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::RecordSizeMismatch` if the encoded document does not have the
+    ///   same size as the documents already in the file.
+    ///
+    /// - `std::num::TryFromIntError` occurs when the document takes up more space
+    ///   than `u32::MAX` bytes.
+    ///
+    /// - `std::io::Error`
+    ///
+    /// - `bincode::Error` when the document provided fails to serialize for some reason.
+    pub fn write(&mut self, document: &T) -> Result<(), DatabaseError> {
+        self.write_many(std::array::from_ref(document))
+    }
+
+    /// Writes the provided serializable documents to disk. Nothing is written if any of
+    /// the documents has the wrong size.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::RecordSizeMismatch` if an encoded document does not have the
+    ///   same size as the documents already in the file.
+    ///
+    /// - `std::num::TryFromIntError` occurs when a document takes up more space
+    ///   than `u32::MAX` bytes.
+    ///
+    /// - `std::io::Error`
+    ///
+    /// - `bincode::Error` when the documents provided fails to serialize for some reason.
+    pub fn write_many(&mut self, documents: &[T]) -> Result<(), DatabaseError> {
+        let mut layout = self.layout;
+        let mut buf = Vec::new();
+        let mut scratch = Vec::new();
+        for document in documents {
+            scratch.clear();
+            F::serialize_into(&mut scratch, document)?;
+            let size = u32::try_from(scratch.len())?;
+            match layout.record_size {
+                None => layout.record_size = Some(size),
+                Some(expected) if expected != size => {
+                    return Err(DatabaseError::RecordSizeMismatch {
+                        expected,
+                        found: size,
+                    })
+                }
+                Some(_) => (),
+            }
+            layout
+                .endianness
+                .write_u32(&mut buf, layout.checksum(&scratch))?;
+            buf.write_all(&scratch)?;
+        }
+        self.file.seek(SeekFrom::End(0))?;
+        if self.layout.record_size.is_none() && layout.record_size.is_some() {
+            let mut header = layout.encode_header();
+            header.extend_from_slice(&buf);
+            buf = header;
+        }
+        self.file.write_all(&buf)?;
+        self.layout = layout;
+        Ok(())
+    }
+
+    /// Returns the number of documents in the file.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn len(&mut self) -> Result<usize, DatabaseError> {
+        let Some(frame_len) = self.frame_len() else {
+            return Ok(0);
+        };
+        let data_len = self.file.metadata()?.len() - self.layout.data_start;
+        Ok((data_len / frame_len) as usize)
+    }
+
+    /// Returns `true` if the file contains no documents.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn is_empty(&mut self) -> Result<bool, DatabaseError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Returns the document at `index`, or `Ok(None)` if `index` is out of range. The
+    /// position of the document is computed from its index, so only that document is
+    /// read from disk.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `bincode::Error` if the document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    pub fn get_at(&mut self, index: usize) -> Result<Option<T>, DatabaseError> {
+        let Some(frame_len) = self.frame_len() else {
+            return Ok(None);
+        };
+        if index >= self.len()? {
+            return Ok(None);
+        }
+        let offset = self.layout.data_start + index as u64 * frame_len;
+        self.file.seek(SeekFrom::Start(offset))?;
+        let mut frame = vec![0; frame_len as usize];
+        self.file.read_exact(&mut frame)?;
+        self.decode(&frame).map(Some)
+    }
+
+    /// Returns every document in the file, or `Ok(None)` if the file is empty.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `bincode::Error` if a document fails to deserialize.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn load(&mut self) -> Result<Option<Vec<T>>, DatabaseError> {
+        let Some(frame_len) = self.frame_len() else {
+            return Ok(None);
+        };
+        let mut buf = Vec::new();
+        self.file.seek(SeekFrom::Start(self.layout.data_start))?;
+        self.file.read_to_end(&mut buf)?;
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        let frames = buf.chunks_exact(frame_len as usize);
+        if !frames.remainder().is_empty() {
+            return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+        }
+        frames
+            .map(|frame| self.decode(frame))
+            .collect::<Result<_, _>>()
+            .map(Some)
+    }
+
+    fn frame_len(&self) -> Option<u64> {
+        self.layout
+            .record_size
+            .map(|size| CHECKSUM_LEN + u64::from(size))
+    }
+
+    fn decode(&self, frame: &[u8]) -> Result<T, DatabaseError> {
+        let (checksum, data) = frame.split_at(CHECKSUM_LEN as usize);
+        let saved = self.layout.endianness.u32_from_slice(checksum);
+        let expected = self.layout.checksum(data);
+        if saved != expected {
+            return Err(DatabaseError::MismatchedChecksum { saved, expected });
+        }
+        F::deserialize(data)
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{data_end, header::Layout, open_failed, read_frame, DatabaseError, Format};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::de::DeserializeOwned;
+use std::{
+    fs::File,
+    io::{Seek, SeekFrom},
+    path::Path,
+    sync::mpsc::{channel, Receiver},
+    time::Duration,
+};
+
+/// How long to wait for a notification before checking the file anyway, in case a
+/// notification was missed, e.g. on a network file system.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An iterator over the documents appended to a file after it was created, returned by
+/// [`Client::follow`](crate::Client::follow). Requires the `notify` feature.
+///
+/// Like `tail -f`, the iterator never ends: once it has read every complete document, it
+/// blocks until the file changes and then reads the bytes appended since. A document
+/// that is still being written is only read once it is complete. Errors, such as a
+/// checksum mismatch, are yielded without stopping the iterator.
+///
+/// The file is watched by its path, so documents written after the file is replaced,
+/// e.g. by [`Client::vacuum`](crate::Client::vacuum), are not seen.
+pub struct FollowIter<T, F> {
+    file: File,
+    layout: Layout,
+    position: u64,
+    events: Receiver<notify::Result<Event>>,
+    _watcher: RecommendedWatcher,
+    _phantom: std::marker::PhantomData<(T, F)>,
+}
+
+impl<T, F> FollowIter<T, F> {
+    pub(crate) fn new(path: &Path, layout: Layout) -> Result<Self, DatabaseError> {
+        layout.require_frame_per_document()?;
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(watch_error)?;
+        watcher
+            .watch(path, RecursiveMode::NonRecursive)
+            .map_err(watch_error)?;
+        let mut file = File::open(path).map_err(open_failed(path))?;
+        let position = complete_end(&mut file, &layout)?;
+        Ok(Self {
+            file,
+            layout,
+            position,
+            events,
+            _watcher: watcher,
+            _phantom: std::marker::PhantomData,
+        })
+    }
+
+    /// Returns the offset in the file up to which documents have been read.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+impl<T, F> FollowIter<T, F>
+where
+    T: DeserializeOwned,
+    F: Format,
+{
+    /// Reads the next complete document after the current position, if there is one.
+    fn read_next(&mut self) -> Result<Option<T>, DatabaseError> {
+        loop {
+            let Some(next) = next_complete(&mut self.file, &self.layout, self.position)? else {
+                return Ok(None);
+            };
+            self.file.seek(SeekFrom::Start(self.position))?;
+            let frame = read_frame(&mut self.file, &self.layout)?;
+            self.position = next;
+            if frame.removed {
+                continue;
+            }
+            let body = frame.verify(&self.layout)?;
+            let (extras, data) = self.layout.split_body(&body)?;
+            let data = self.layout.frame_data(extras, data)?;
+            return self.layout.decode::<F, T>(&data).map(Some);
+        }
+    }
+}
+
+impl<T, F> Iterator for FollowIter<T, F>
+where
+    T: DeserializeOwned,
+    F: Format,
+{
+    type Item = Result<T, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.read_next() {
+                Ok(Some(document)) => return Some(Ok(document)),
+                Ok(None) => (),
+                Err(e) => return Some(Err(e)),
+            }
+            if let Ok(Err(e)) = self.events.recv_timeout(POLL_INTERVAL) {
+                return Some(Err(watch_error(e)));
+            }
+            while self.events.try_recv().is_ok() {}
+        }
+    }
+}
+
+/// Returns the end of the frame starting at `offset` if the whole frame is in the file.
+fn next_complete(
+    file: &mut File,
+    layout: &Layout,
+    offset: u64,
+) -> Result<Option<u64>, DatabaseError> {
+    let end = data_end(file, layout)?;
+    if end < offset + layout.frame_header_len() {
+        return Ok(None);
+    }
+    file.seek(SeekFrom::Start(offset + 4))?;
+    let len = layout.endianness.read_u32(file)?;
+    let next = offset + layout.frame_len(len);
+    Ok((next <= end).then_some(next))
+}
+
+/// Returns the end of the last complete frame in the file.
+fn complete_end(file: &mut File, layout: &Layout) -> Result<u64, DatabaseError> {
+    let mut offset = layout.data_start;
+    while let Some(next) = next_complete(file, layout, offset)? {
+        offset = next;
+    }
+    Ok(offset)
+}
+
+fn watch_error(e: notify::Error) -> DatabaseError {
+    std::io::Error::other(e).into()
+}
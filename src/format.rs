@@ -0,0 +1,258 @@
+use crate::DatabaseError;
+use bincode::Options;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The encoding used for the payload of every document.
+///
+/// A format only decides how a single document is turned into bytes and back. The
+/// checksum and length framing around every document is the same for all formats.
+///
+/// Other formats can be implemented outside of this crate, returning their errors as a
+/// `DatabaseError::FormatError`, e.g. JSON with `serde_json`:
+///
+/// ```ignore
+/// struct Json;
+///
+/// impl Format for Json {
+///     const ID: u8 = 0x80;
+///
+///     fn serialize_into<T: Serialize>(buf: &mut Vec<u8>, value: &T) -> Result<(), DatabaseError> {
+///         serde_json::to_writer(buf, value).map_err(|e| DatabaseError::FormatError(Box::new(e)))
+///     }
+///
+///     fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DatabaseError> {
+///         serde_json::from_slice(bytes).map_err(|e| DatabaseError::FormatError(Box::new(e)))
+///     }
+/// }
+///
+/// let client: Client<Record, Json> = Client::new("records.crio", true)?;
+/// ```
+///
+/// Identifiers from `0x80` up are never used by the formats of this crate.
+///
+/// A format can also encrypt documents at rest, e.g. with ChaCha20-Poly1305 from the
+/// `chacha20poly1305` crate and a random nonce stored in front of every ciphertext. Since
+/// the checksum of a frame covers the bytes returned by the format, corruption is still
+/// detected before a document is decrypted, and a file written with the format has its
+/// identifier in the header, so a client without the key fails with
+/// `DatabaseError::FormatMismatch` instead of decoding ciphertext:
+///
+/// ```ignore
+/// static KEY: OnceLock<Key> = OnceLock::new();
+///
+/// struct Encrypted;
+///
+/// impl Format for Encrypted {
+///     const ID: u8 = 0x81;
+///
+///     fn serialize_into<T: Serialize>(buf: &mut Vec<u8>, value: &T) -> Result<(), DatabaseError> {
+///         let cipher = ChaCha20Poly1305::new(KEY.get().unwrap());
+///         let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+///         let plaintext = bincode::serialize(value)?;
+///         let ciphertext = cipher.encrypt(&nonce, plaintext.as_slice()).unwrap();
+///         buf.extend_from_slice(&nonce);
+///         buf.extend_from_slice(&ciphertext);
+///         Ok(())
+///     }
+///
+///     fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DatabaseError> {
+///         let cipher = ChaCha20Poly1305::new(KEY.get().unwrap());
+///         let (nonce, ciphertext) = bytes.split_at(12);
+///         let plaintext = cipher
+///             .decrypt(Nonce::from_slice(nonce), ciphertext)
+///             .map_err(|e| DatabaseError::FormatError(e.to_string().into()))?;
+///         Ok(bincode::deserialize(&plaintext)?)
+///     }
+/// }
+/// ```
+pub trait Format {
+    /// Identifier recorded in the file header so that a file is never decoded with a
+    /// different format than the one it was written with. Files without a header are
+    /// always encoded with [`Bincode`], whose identifier is `0`.
+    const ID: u8;
+
+    /// Appends the encoded `value` to `buf`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `value` fails to serialize.
+    fn serialize_into<T: Serialize>(buf: &mut Vec<u8>, value: &T) -> Result<(), DatabaseError>;
+
+    /// Decodes a value from `bytes`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` fails to deserialize into `T`.
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DatabaseError>;
+
+    /// Decodes a value from `bytes` like `deserialize()`, but fails instead of allocating
+    /// more than `limit` bytes. The default implementation ignores the limit, so formats
+    /// that can enforce one should override it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DatabaseError::LimitExceeded` if decoding would exceed `limit`, or another
+    /// error if `bytes` fails to deserialize into `T`.
+    fn deserialize_limited<T: DeserializeOwned>(
+        bytes: &[u8],
+        limit: u64,
+    ) -> Result<T, DatabaseError> {
+        let _ = limit;
+        Self::deserialize(bytes)
+    }
+
+    /// Decodes a value from `bytes` like `deserialize()`, or like `deserialize_limited()`
+    /// if a `limit` is given, but fails if any bytes are left over after the value. The
+    /// default implementation does not check for leftover bytes, so formats that can
+    /// detect them should override it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DatabaseError::TrailingBytes` if bytes are left over, or any error of
+    /// `deserialize_limited()`.
+    fn deserialize_exact<T: DeserializeOwned>(
+        bytes: &[u8],
+        limit: Option<u64>,
+    ) -> Result<T, DatabaseError> {
+        match limit {
+            Some(limit) => Self::deserialize_limited(bytes, limit),
+            None => Self::deserialize(bytes),
+        }
+    }
+}
+
+/// Encodes documents with `bincode`. This is the default format.
+///
+/// Documents are encoded with the configuration of `bincode::serialize`, since that is
+/// what every file without a header contains. A different configuration changes the
+/// encoding and is a [`Format`] of its own with another [`ID`](Format::ID), such as
+/// [`BincodeVarint`] for variable length integers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bincode;
+
+impl Format for Bincode {
+    const ID: u8 = 0;
+
+    fn serialize_into<T: Serialize>(buf: &mut Vec<u8>, value: &T) -> Result<(), DatabaseError> {
+        Ok(bincode::serialize_into(buf, value)?)
+    }
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DatabaseError> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+
+    fn deserialize_limited<T: DeserializeOwned>(
+        bytes: &[u8],
+        limit: u64,
+    ) -> Result<T, DatabaseError> {
+        let mut reader = bytes;
+        deserialize_from_limited(fixint(), &mut reader, limit)
+    }
+
+    fn deserialize_exact<T: DeserializeOwned>(
+        bytes: &[u8],
+        limit: Option<u64>,
+    ) -> Result<T, DatabaseError> {
+        deserialize_exact(fixint(), bytes, limit)
+    }
+}
+
+/// Encodes documents with `bincode` using variable length integers, which take a single
+/// byte for values below 251, e.g. to shrink documents holding many small numbers. The
+/// encoding differs from [`Bincode`], so files written with either format can only be
+/// read with the same format.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BincodeVarint;
+
+impl Format for BincodeVarint {
+    const ID: u8 = 2;
+
+    fn serialize_into<T: Serialize>(buf: &mut Vec<u8>, value: &T) -> Result<(), DatabaseError> {
+        Ok(varint().serialize_into(buf, value)?)
+    }
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DatabaseError> {
+        Ok(varint().deserialize(bytes)?)
+    }
+
+    fn deserialize_limited<T: DeserializeOwned>(
+        bytes: &[u8],
+        limit: u64,
+    ) -> Result<T, DatabaseError> {
+        let mut reader = bytes;
+        deserialize_from_limited(varint(), &mut reader, limit)
+    }
+
+    fn deserialize_exact<T: DeserializeOwned>(
+        bytes: &[u8],
+        limit: Option<u64>,
+    ) -> Result<T, DatabaseError> {
+        deserialize_exact(varint(), bytes, limit)
+    }
+}
+
+/// The configuration of `bincode::serialize` and `bincode::deserialize`.
+fn fixint() -> impl Options + Copy {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+}
+
+/// The configuration of [`BincodeVarint`].
+fn varint() -> impl Options + Copy {
+    bincode::DefaultOptions::new()
+        .with_varint_encoding()
+        .allow_trailing_bytes()
+}
+
+/// Decodes a value with `options`, with a limit on top, leaving `reader` at the end of
+/// the value. The limit is only enforced when decoding from a reader, not from a slice.
+fn deserialize_from_limited<T: DeserializeOwned>(
+    options: impl Options,
+    reader: &mut &[u8],
+    limit: u64,
+) -> Result<T, DatabaseError> {
+    options
+        .with_limit(limit)
+        .deserialize_from(reader)
+        .map_err(|e| match *e {
+            bincode::ErrorKind::SizeLimit => DatabaseError::LimitExceeded(limit),
+            _ => DatabaseError::SerdeError(e),
+        })
+}
+
+/// Decodes a value with `options` like [`Format::deserialize_exact`].
+fn deserialize_exact<T: DeserializeOwned>(
+    options: impl Options,
+    bytes: &[u8],
+    limit: Option<u64>,
+) -> Result<T, DatabaseError> {
+    let mut reader = bytes;
+    let value = match limit {
+        Some(limit) => deserialize_from_limited(options, &mut reader, limit)?,
+        None => options.deserialize_from(&mut reader)?,
+    };
+    if !reader.is_empty() {
+        return Err(DatabaseError::TrailingBytes(reader.len()));
+    }
+    Ok(value)
+}
+
+/// Encodes documents with CBOR, a self-describing format that is widely supported by
+/// embedded devices. Requires the `cbor` feature.
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl Format for Cbor {
+    const ID: u8 = 1;
+
+    fn serialize_into<T: Serialize>(buf: &mut Vec<u8>, value: &T) -> Result<(), DatabaseError> {
+        ciborium::into_writer(value, buf).map_err(|e| DatabaseError::FormatError(Box::new(e)))
+    }
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DatabaseError> {
+        ciborium::from_reader(bytes).map_err(|e| DatabaseError::FormatError(Box::new(e)))
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{
+    binary_to_vec, header::Layout, open_failed, rename_over, write_encoded, Bincode, DatabaseError,
+    Format,
+};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::File,
+    io::{ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// A client for files that are gzip-compressed as a whole, such as archived files.
+/// Requires the `gzip` feature.
+///
+/// The whole file is decompressed into memory when the client is created, and read from
+/// memory afterwards. Writes are buffered in memory as well and only reach the disk when
+/// the client is flushed or dropped, at which point the whole file is compressed again
+/// and atomically replaces the original. This makes the client best suited for files
+/// that are mostly read.
+pub struct GzClient<T: Serialize + DeserializeOwned, F: Format = Bincode> {
+    path: PathBuf,
+    data: Vec<u8>,
+    layout: Layout,
+    dirty: bool,
+    _phantom: std::marker::PhantomData<(T, F)>,
+}
+
+impl<T, F> GzClient<T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+{
+    /// Creates a new client. The `append` parameter behaves the same as in
+    /// [`Client::new`](crate::Client::new), except that nothing is written to disk until
+    /// the client is flushed.
+    ///
+    /// # Errors
+    ///
+    /// - Returns `std::io::Error` if it fails to read or decompress an existing file.
+    ///
+    /// - `DatabaseError::BadHeader`, `DatabaseError::UnsupportedVersion` or
+    ///   `DatabaseError::FormatMismatch` if the header of an existing file does not match.
+    pub fn new<P: AsRef<Path>>(path: P, append: bool) -> Result<Self, DatabaseError> {
+        let path = path.as_ref().to_path_buf();
+        let mut data = Vec::new();
+        if append {
+            match File::open(&path) {
+                Ok(file) => {
+                    GzDecoder::new(file).read_to_end(&mut data)?;
+                }
+                Err(e) if e.kind() == ErrorKind::NotFound => (),
+                Err(e) => return Err(open_failed(&path)(e)),
+            }
+        }
+        let layout = if data.is_empty() {
+            let mut layout = Layout {
+                format: F::ID,
+                ..Layout::default()
+            };
+            if layout.needs_header() {
+                data = layout.encode_header();
+            }
+            layout
+        } else {
+            let layout = Layout::read(&mut data.as_slice(), None)?;
+            if layout.format != F::ID {
+                return Err(DatabaseError::FormatMismatch {
+                    expected: F::ID,
+                    found: layout.format,
+                });
+            }
+            if layout.record_size.is_some() {
+                return Err(DatabaseError::Unsupported(
+                    "file stores fixed-size documents, open it with a FixedClient",
+                ));
+            }
+            layout
+        };
+        Ok(Self {
+            path,
+            data,
+            layout,
+            dirty: !append,
+            _phantom: std::marker::PhantomData,
+        })
+    }
+
+    /// Returns a collection. If the file is empty, this method returns `Ok(None)`. See
+    /// [`Client::load`](crate::Client::load).
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::CorruptedFrame` error is
+    ///   returned.
+    ///
+    /// - `bincode::Error` if the deserializer fails to deserialize bytes from
+    ///   the file to your requested document type.
+    pub fn load(&self) -> Result<Option<Vec<T>>, DatabaseError> {
+        let raw_data = &self.data[self.layout.data_start as usize..];
+        if raw_data.is_empty() {
+            return Ok(None);
+        }
+        let result = binary_to_vec::<F, _>(raw_data, &self.layout, true)?;
+        Ok(Some(result))
+    }
+
+    /// Buffers the provided documents until the client is flushed.
+    ///
+    /// # Errors
+    ///
+    /// - `std::num::TryFromIntError` occurs when a document you are inserting
+    ///   takes up more space than `u32::MAX` bytes.
+    ///
+    /// - `bincode::Error` when the documents provided fails to serialize for some reason.
+    pub fn write_many(&mut self, documents: &[T]) -> Result<(), DatabaseError> {
+        let len = self.data.len();
+        if let Err(e) =
+            write_encoded::<F, _, T, _>(&mut self.data, documents, &self.layout, &mut Vec::new())
+        {
+            self.data.truncate(len);
+            return Err(e);
+        }
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Buffers the provided document until the client is flushed.
+    ///
+    /// # Errors
+    ///
+    /// - `std::num::TryFromIntError` occurs when the document takes up more space than
+    ///   `u32::MAX` bytes.
+    ///
+    /// - `bincode::Error` when the document provided fails to serialize for some reason.
+    pub fn write(&mut self, document: &T) -> Result<(), DatabaseError> {
+        self.write_many(std::array::from_ref(document))
+    }
+
+    /// Compresses the whole file and atomically replaces the file on disk with it, if
+    /// anything changed since the last flush. This is also done when the client is
+    /// dropped, but errors are ignored there.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn flush(&mut self) -> Result<(), DatabaseError> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        let temp = File::create(&temp_path).map_err(open_failed(Path::new(&temp_path)))?;
+        let mut encoder = GzEncoder::new(temp, Compression::default());
+        encoder.write_all(&self.data)?;
+        encoder.finish()?.sync_all()?;
+        rename_over(temp_path.as_ref(), &self.path)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl<T, F> Drop for GzClient<T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+{
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
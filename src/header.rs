@@ -0,0 +1,881 @@
+use crate::{open_failed, Bincode, DatabaseError, Format, CRC};
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
+use crc::{Algorithm, Crc, NoTable, CRC_32_ISO_HDLC, CRC_64_XZ};
+use serde::de::DeserializeOwned;
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, BufRead, Read, Write},
+    path::Path,
+};
+
+/// Magic bytes at the start of every file with a header.
+pub const MAGIC: [u8; 4] = *b"CRIO";
+
+/// Major version of the file format written by this crate, stored after the magic bytes
+/// of a header. Files with another major version cannot be read.
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Minor version of the file format written by this crate. Files with a newer minor
+/// version can still be read, ignoring the flags this crate does not know.
+const MINOR_VERSION: u8 = 0;
+
+/// The top byte of the flags holds the minor version rather than flags.
+const MINOR_VERSION_SHIFT: u32 = 24;
+
+/// Size of a file header without any optional fields: the magic bytes, the format
+/// version, the format identifier, the size of the header and the flags.
+pub const FILE_HEADER_LEN: usize = 12;
+
+/// Size of the header of every frame without a status byte: the checksum of the frame
+/// body followed by its length, both as `u32` in the byte order of the file.
+pub const FRAME_HEADER_LEN: usize = 8;
+
+/// Magic bytes at the start of the footer written by
+/// [`Client::finalize`](crate::Client::finalize).
+pub const FOOTER_MAGIC: [u8; 4] = *b"CRFT";
+
+/// Size of the footer written by [`Client::finalize`](crate::Client::finalize): the magic
+/// bytes, the checksum of the documents and their size in bytes.
+pub const FOOTER_LEN: usize = 16;
+
+/// The checksum and length of every frame is stored in big-endian order.
+const FLAG_BIG_ENDIAN: u32 = 1 << 0;
+
+/// Every frame stores the time at which it was written.
+const FLAG_TIMESTAMPS: u32 = 1 << 1;
+
+/// Every document has the same size, which is stored in the header, and frames do not
+/// store a length. Such files can only be opened with a [`FixedClient`](crate::FixedClient).
+const FLAG_FIXED_SIZE: u32 = 1 << 2;
+
+/// Every frame has a status byte after its length, which marks removed documents.
+const FLAG_TOMBSTONES: u32 = 1 << 3;
+
+/// Checksums are computed with a CRC32 algorithm other than `CRC_32_ISO_HDLC`, whose
+/// check value is stored in the header.
+const FLAG_CUSTOM_CHECKSUM: u32 = 1 << 4;
+
+/// Every frame is padded so that the body of every frame starts at a multiple of the
+/// alignment stored in the header.
+const FLAG_ALIGNED: u32 = 1 << 5;
+
+/// The file was created by a client in append mode, and is protected from being
+/// truncated by a client in overwrite mode.
+const FLAG_APPEND_LOG: u32 = 1 << 6;
+
+/// Every frame holds a block of up to the number of documents stored in the header, each
+/// prefixed with its length, so that a single checksum covers the whole block.
+const FLAG_BLOCKS: u32 = 1 << 7;
+
+/// Every frame stores the version of the schema its document was written with, as a single
+/// byte after the timestamp, if any.
+const FLAG_VERSIONS: u32 = 1 << 8;
+
+/// Every frame stores a byte of flags after the schema version, if any, telling whether
+/// its document is compressed, so that compression can be chosen per document.
+const FLAG_COMPRESSION: u32 = 1 << 9;
+
+/// The header stores an identifier chosen by the application that created the file.
+const FLAG_APP_ID: u32 = 1 << 10;
+
+/// Every frame stores a CRC-64 of its encoded document after the byte of flags, if any.
+const FLAG_CRC64: u32 = 1 << 11;
+
+/// The checksum of every frame is left as `0` and never checked.
+const FLAG_NO_CHECKSUM: u32 = 1 << 12;
+
+/// Every frame stores a type tag chosen by the application, as a `u16` in the byte order
+/// of the frames after the schema version, if any.
+const FLAG_TAGS: u32 = 1 << 13;
+
+/// The algorithm of the 64-bit checksum stored in every frame of a file with
+/// [`Checksum::Crc64`].
+const CRC64: Crc<u64> = Crc::<u64>::new(&CRC_64_XZ);
+
+/// Set in the per-frame flags of a frame whose document is deflate-compressed.
+const FRAME_COMPRESSED: u8 = 1 << 0;
+
+const KNOWN_FLAGS: u32 = FLAG_BIG_ENDIAN
+    | FLAG_TIMESTAMPS
+    | FLAG_FIXED_SIZE
+    | FLAG_TOMBSTONES
+    | FLAG_CUSTOM_CHECKSUM
+    | FLAG_ALIGNED
+    | FLAG_APPEND_LOG
+    | FLAG_BLOCKS
+    | FLAG_VERSIONS
+    | FLAG_COMPRESSION
+    | FLAG_APP_ID
+    | FLAG_CRC64
+    | FLAG_NO_CHECKSUM
+    | FLAG_TAGS;
+
+/// The largest alignment of frame bodies that can be recorded in a header.
+pub(crate) const MAX_ALIGNMENT: u32 = 4096;
+
+/// Bit of the status byte of a frame that is set once its document has been removed.
+pub(crate) const STATUS_REMOVED: u8 = 1 << 0;
+
+/// The byte order used to store the checksum and length of every document.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endianness {
+    /// Little-endian order, which is the default.
+    #[default]
+    Little,
+    /// Big-endian order, also known as network byte order.
+    Big,
+}
+
+/// The width of the checksum that protects every document, set with
+/// [`ClientBuilder::checksum`](crate::ClientBuilder::checksum).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Checksum {
+    /// The 32-bit checksum of the frame header only, which is the default.
+    #[default]
+    Crc32,
+    /// A `CRC_64_XZ` checksum of every document in addition to the 32-bit checksum, for
+    /// stores large enough that a corruption matching a 32-bit checksum by chance is a
+    /// concern. This adds 8 bytes to every frame.
+    Crc64,
+    /// No checksum at all, for scratch files where corruption does not matter and
+    /// writing as fast as possible does. Frames keep the field of their checksum, which
+    /// is left as `0`, so corruption goes undetected: it is either deserialized into a
+    /// wrong value or rejected by the deserializer.
+    None,
+}
+
+impl Endianness {
+    pub(crate) fn read_u32<R: Read>(self, r: &mut R) -> io::Result<u32> {
+        match self {
+            Self::Little => r.read_u32::<LittleEndian>(),
+            Self::Big => r.read_u32::<BigEndian>(),
+        }
+    }
+
+    pub(crate) fn write_u32<W: Write>(self, w: &mut W, n: u32) -> io::Result<()> {
+        match self {
+            Self::Little => w.write_u32::<LittleEndian>(n),
+            Self::Big => w.write_u32::<BigEndian>(n),
+        }
+    }
+
+    pub(crate) fn u16_from_slice(self, buf: &[u8]) -> u16 {
+        match self {
+            Self::Little => LittleEndian::read_u16(buf),
+            Self::Big => BigEndian::read_u16(buf),
+        }
+    }
+
+    pub(crate) fn write_u16<W: Write>(self, w: &mut W, n: u16) -> io::Result<()> {
+        match self {
+            Self::Little => w.write_u16::<LittleEndian>(n),
+            Self::Big => w.write_u16::<BigEndian>(n),
+        }
+    }
+
+    pub(crate) fn u32_from_slice(self, buf: &[u8]) -> u32 {
+        match self {
+            Self::Little => LittleEndian::read_u32(buf),
+            Self::Big => BigEndian::read_u32(buf),
+        }
+    }
+
+    pub(crate) fn read_u64<R: Read>(self, r: &mut R) -> io::Result<u64> {
+        match self {
+            Self::Little => r.read_u64::<LittleEndian>(),
+            Self::Big => r.read_u64::<BigEndian>(),
+        }
+    }
+
+    pub(crate) fn write_u64<W: Write>(self, w: &mut W, n: u64) -> io::Result<()> {
+        match self {
+            Self::Little => w.write_u64::<LittleEndian>(n),
+            Self::Big => w.write_u64::<BigEndian>(n),
+        }
+    }
+
+    pub(crate) fn u64_from_slice(self, buf: &[u8]) -> u64 {
+        match self {
+            Self::Little => LittleEndian::read_u64(buf),
+            Self::Big => BigEndian::read_u64(buf),
+        }
+    }
+}
+
+/// Describes where the documents of a file start and how their frames are encoded.
+///
+/// Every frame consists of a checksum and a length, followed by a body of that length
+/// which the checksum is computed over. The body starts with the per-frame metadata
+/// enabled by the layout, a timestamp followed by a schema version, a type tag, a byte of
+/// flags and a CRC-64 of the rest of the body, followed by the encoded document, which is
+/// compressed if the flags say so.
+///
+/// If tombstones are enabled, the length is followed by a status byte which is not
+/// covered by the checksum, so that a document can be marked as removed by overwriting
+/// a single byte in place.
+///
+/// If frames are aligned, every frame is followed by zeroed padding which rounds the size
+/// of the frame up to a multiple of the alignment, and the header is padded so that the
+/// body of the first frame starts at a multiple of the alignment. Since the padding only
+/// depends on the size of a frame, frames can be copied between aligned files.
+///
+/// Files using the default layout are written without a header so that they stay
+/// readable by older versions of this crate. Every other layout is recorded in a header
+/// at the start of the file:
+///
+/// | Offset | Size | Description                              |
+/// |--------|------|------------------------------------------|
+/// | 0      | 4    | Magic bytes `CRIO`                       |
+/// | 4      | 1    | Header version                           |
+/// | 5      | 1    | Identifier of the payload [`Format`]     |
+/// | 6      | 2    | Size of the header in bytes (LE)         |
+/// | 8      | 3    | Flags (LE)                               |
+/// | 11     | 1    | Minor version                            |
+///
+/// Some flags are followed by an optional field, which are appended after the flags in
+/// the order of their flag bits:
+///
+/// | Flag              | Size | Description                                  |
+/// |-------------------|------|----------------------------------------------|
+/// | `FIXED_SIZE`      | 4    | Size of every encoded document (LE)          |
+/// | `CUSTOM_CHECKSUM` | 4    | Check value of the checksum algorithm (LE)   |
+/// | `ALIGNED`         | 4    | Alignment of every frame body in bytes (LE)  |
+/// | `BLOCKS`          | 4    | Maximum number of documents per block (LE)   |
+/// | `APP_ID`          | 8    | Identifier of the application                |
+///
+/// In a file with blocks, the body of every frame is a block holding one or more
+/// documents, each prefixed with its length as a `u32` in the byte order of the frames.
+///
+/// The check value of a CRC algorithm is its checksum of the ASCII string `123456789`,
+/// which tells different algorithms apart.
+///
+/// Any file may end with a footer, written by [`Client::finalize`](crate::Client::finalize),
+/// whose checksum covers every byte between the header and the footer:
+///
+/// | Offset | Size | Description                                  |
+/// |--------|------|----------------------------------------------|
+/// | 0      | 4    | Magic bytes `CRFT`                           |
+/// | 4      | 4    | Checksum of the documents                    |
+/// | 8      | 8    | Size of the documents in bytes               |
+///
+/// Both numbers are stored in the byte order of the frames. The last 16 bytes of a file
+/// are only treated as a footer if they start with the magic bytes and the recorded size
+/// matches, so a document that happens to end with the magic bytes is not mistaken for one.
+///
+/// The header version is the major version of the file format. It is bumped by changes
+/// that older versions of this crate would misread, such as changing how frames are
+/// encoded or adding a flag that changes them. Such files fail to open with
+/// `DatabaseError::UnsupportedVersion`. The minor version is bumped by changes that older
+/// versions can safely ignore, such as a flag whose field only records extra
+/// information about the file. Since the fields of a new flag come after the fields of
+/// every existing flag and the size of the header is recorded, older versions open files
+/// with a newer minor version by ignoring the flags they do not know. Unknown flags in a
+/// file with the same or an older minor version are rejected as `BadHeader`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Layout {
+    pub(crate) endianness: Endianness,
+    pub(crate) format: u8,
+    pub(crate) timestamps: bool,
+    pub(crate) record_size: Option<u32>,
+    pub(crate) tombstones: bool,
+    /// `None` for the default `CRC_32_ISO_HDLC` algorithm.
+    pub(crate) checksum_algorithm: Option<&'static Algorithm<u32>>,
+    /// Frames are not padded if this is `0` or `1`.
+    pub(crate) alignment: u32,
+    pub(crate) append_log: bool,
+    /// Maximum number of documents per frame, or `None` for one document per frame.
+    pub(crate) block_size: Option<u32>,
+    pub(crate) versions: bool,
+    pub(crate) compression: bool,
+    pub(crate) app_id: Option<[u8; 8]>,
+    pub(crate) crc64: bool,
+    /// Whether frames have no checksum, see [`Checksum::None`].
+    pub(crate) no_checksum: bool,
+    pub(crate) tags: bool,
+    /// Whether the file starts with a header even though its layout would not need one.
+    pub(crate) header: bool,
+    pub(crate) data_start: u64,
+    /// Maximum number of bytes a single document may allocate while it is decoded. This
+    /// is a read option of the client and is not stored in the file.
+    pub(crate) deserialize_limit: Option<u64>,
+    /// Largest body a frame may declare before it is read on its own. This is a read
+    /// option of the client and is not stored in the file.
+    pub(crate) max_record_size: Option<u32>,
+    /// Whether decoding fails if a frame holds more bytes than its document. This is a
+    /// read option of the client and is not stored in the file.
+    pub(crate) reject_trailing_bytes: bool,
+    /// Schema version written into every new frame of a file with versions. This is a
+    /// write option of the client and is not stored in the file.
+    pub(crate) version: u8,
+    /// Type tag written into the next frames of a file with tags. This is a write option
+    /// of the client and is not stored in the file.
+    pub(crate) tag: u16,
+    /// Whether new frames of a file with per-frame compression are compressed. This is a
+    /// write option of the client and is not stored in the file.
+    pub(crate) compress: bool,
+    /// Deflate level of compressed frames, or `None` for the default level. This is a
+    /// write option of the client and is not stored in the file.
+    pub(crate) compression_level: Option<u32>,
+    /// Whether frames are read back and checked after they are written. This is a write
+    /// option of the client and is not stored in the file.
+    pub(crate) verify_after_write: bool,
+    /// Offset past which nothing belongs to the file, for a client opened on a region of
+    /// a larger file. This is not stored in the file.
+    pub(crate) region_end: Option<u64>,
+}
+
+impl Layout {
+    /// Returns `true` if this layout has to be recorded in a header.
+    pub(crate) fn needs_header(&self) -> bool {
+        self.header
+            || self.endianness != Endianness::Little
+            || self.format != Bincode::ID
+            || self.timestamps
+            || self.record_size.is_some()
+            || self.tombstones
+            || self.checksum_algorithm.is_some()
+            || self.alignment > 1
+            || self.append_log
+            || self.block_size.is_some()
+            || self.versions
+            || self.compression
+            || self.app_id.is_some()
+            || self.crc64
+            || self.no_checksum
+            || self.tags
+    }
+
+    /// Fails for files with blocks, for operations that address documents by the frame
+    /// they are stored in, such as indexed reads.
+    pub(crate) fn require_frame_per_document(&self) -> Result<(), DatabaseError> {
+        if self.block_size.is_some() {
+            return Err(DatabaseError::Unsupported(
+                "operation is not supported for files with blocks",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fails for clients opened on a region of a larger file, for operations that would
+    /// write past the region or replace the whole file, or that reopen the file by path.
+    pub(crate) fn require_whole_file(&self) -> Result<(), DatabaseError> {
+        if self.region_end.is_some() {
+            return Err(DatabaseError::Unsupported(
+                "operation is not supported for a file region",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if frames of this layout can be copied as is into a file with the
+    /// `other` layout, i.e. if everything that affects how frames are encoded matches.
+    pub(crate) fn same_frames(&self, other: &Self) -> bool {
+        self.endianness == other.endianness
+            && self.format == other.format
+            && self.timestamps == other.timestamps
+            && self.record_size == other.record_size
+            && self.tombstones == other.tombstones
+            && self.checksum_algorithm == other.checksum_algorithm
+            && self.alignment.max(1) == other.alignment.max(1)
+            && self.block_size == other.block_size
+            && self.versions == other.versions
+            && self.compression == other.compression
+            && self.crc64 == other.crc64
+            && self.no_checksum == other.no_checksum
+            && self.tags == other.tags
+    }
+
+    /// Returns `algorithm`, or `None` if it is the default algorithm.
+    pub(crate) fn custom_algorithm(
+        algorithm: Option<&'static Algorithm<u32>>,
+    ) -> Option<&'static Algorithm<u32>> {
+        algorithm.filter(|algorithm| **algorithm != CRC_32_ISO_HDLC)
+    }
+
+    /// Computes the checksum of a frame body. Custom algorithms are computed without a
+    /// lookup table, which is slower than the default algorithm. Without checksums, this
+    /// is always `0`, which is also what every frame stores, so every check passes.
+    pub(crate) fn checksum(&self, data: &[u8]) -> u32 {
+        if self.no_checksum {
+            return 0;
+        }
+        match self.checksum_algorithm {
+            None => CRC.checksum(data),
+            Some(algorithm) => Crc::<u32, NoTable>::new(algorithm).checksum(data),
+        }
+    }
+
+    /// Computes the 64-bit checksum stored in the metadata of a frame of a file with
+    /// [`Checksum::Crc64`], which covers the encoded document as it is stored.
+    pub(crate) fn checksum64(&self, data: &[u8]) -> u64 {
+        CRC64.checksum(data)
+    }
+
+    fn check_value(&self) -> u32 {
+        self.checksum_algorithm.unwrap_or(&CRC_32_ISO_HDLC).check
+    }
+
+    /// Returns the size of the part of every frame that precedes its body.
+    pub(crate) fn frame_header_len(&self) -> u64 {
+        FRAME_HEADER_LEN as u64 + u64::from(self.tombstones)
+    }
+
+    /// Returns the size of the per-frame metadata at the start of every frame body.
+    pub(crate) fn extras_len(&self) -> usize {
+        8 * usize::from(self.timestamps)
+            + usize::from(self.versions)
+            + 2 * usize::from(self.tags)
+            + usize::from(self.compression)
+            + 8 * usize::from(self.crc64)
+    }
+
+    /// Returns the number of padding bytes that follow a frame with a body of `body_len`
+    /// bytes.
+    pub(crate) fn padding(&self, body_len: u32) -> u64 {
+        let alignment = u64::from(self.alignment.max(1));
+        let len = self.frame_header_len() + u64::from(body_len);
+        (alignment - len % alignment) % alignment
+    }
+
+    /// Returns the size of a frame with a body of `body_len` bytes, including padding.
+    pub(crate) fn frame_len(&self, body_len: u32) -> u64 {
+        self.frame_header_len() + u64::from(body_len) + self.padding(body_len)
+    }
+
+    /// Splits a frame body into its metadata and its encoded document.
+    pub(crate) fn split_body<'a>(&self, body: &'a [u8]) -> io::Result<(&'a [u8], &'a [u8])> {
+        if body.len() < self.extras_len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "frame is shorter than its metadata",
+            ));
+        }
+        Ok(body.split_at(self.extras_len()))
+    }
+
+    /// Splits the encoded data of a frame into the documents it holds, which is the whole
+    /// data unless the file has blocks.
+    pub(crate) fn split_documents<'a>(
+        &self,
+        data: &'a [u8],
+    ) -> impl Iterator<Item = io::Result<&'a [u8]>> + 'a {
+        let blocks = self.block_size.is_some();
+        let endianness = self.endianness;
+        let mut rest = Some(data);
+        std::iter::from_fn(move || {
+            let data = rest.take()?;
+            if !blocks {
+                return Some(Ok(data));
+            }
+            if data.is_empty() {
+                return None;
+            }
+            let document = data.get(4..).and_then(|tail| {
+                let len = endianness.u32_from_slice(&data[..4]) as usize;
+                tail.get(..len).map(|document| (document, &tail[len..]))
+            });
+            let Some((document, tail)) = document else {
+                return Some(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "block ends in the middle of a document",
+                )));
+            };
+            rest = Some(tail);
+            Some(Ok(document))
+        })
+    }
+
+    /// Decodes a document with `F`, enforcing the deserialization limit if one is set.
+    pub(crate) fn decode<F: Format, T: DeserializeOwned>(
+        &self,
+        data: &[u8],
+    ) -> Result<T, DatabaseError> {
+        if self.reject_trailing_bytes {
+            return F::deserialize_exact(data, self.deserialize_limit);
+        }
+        match self.deserialize_limit {
+            Some(limit) => F::deserialize_limited(data, limit),
+            None => F::deserialize(data),
+        }
+    }
+
+    /// Returns the timestamp stored in the metadata of a frame, if any.
+    pub(crate) fn timestamp(&self, extras: &[u8]) -> Option<u64> {
+        self.timestamps
+            .then(|| self.endianness.u64_from_slice(&extras[..8]))
+    }
+
+    /// Returns the schema version stored in the metadata of a frame, if any.
+    pub(crate) fn version(&self, extras: &[u8]) -> Option<u8> {
+        self.versions
+            .then(|| extras[8 * usize::from(self.timestamps)])
+    }
+
+    /// Returns the type tag stored in the metadata of a frame, if any.
+    pub(crate) fn frame_tag(&self, extras: &[u8]) -> Option<u16> {
+        let start = 8 * usize::from(self.timestamps) + usize::from(self.versions);
+        self.tags
+            .then(|| self.endianness.u16_from_slice(&extras[start..start + 2]))
+    }
+
+    /// Returns the encoded document of a frame, decompressing it if its flags say so.
+    pub(crate) fn frame_data<'a>(
+        &self,
+        extras: &[u8],
+        data: &'a [u8],
+    ) -> Result<Cow<'a, [u8]>, DatabaseError> {
+        let flags = 8 * usize::from(self.timestamps)
+            + usize::from(self.versions)
+            + 2 * usize::from(self.tags);
+        if self.compression && extras[flags] & FRAME_COMPRESSED != 0 {
+            return inflate(data).map(Cow::Owned);
+        }
+        Ok(Cow::Borrowed(data))
+    }
+
+    /// Returns the per-frame flags written into every new frame of a file with per-frame
+    /// compression.
+    pub(crate) fn frame_flags(&self) -> u8 {
+        if self.compress {
+            FRAME_COMPRESSED
+        } else {
+            0
+        }
+    }
+
+    /// Returns the encoded header for this layout, and sets `data_start` accordingly.
+    pub(crate) fn encode_header(&mut self) -> Vec<u8> {
+        let mut flags = 0;
+        if self.endianness == Endianness::Big {
+            flags |= FLAG_BIG_ENDIAN;
+        }
+        if self.timestamps {
+            flags |= FLAG_TIMESTAMPS;
+        }
+        if self.tombstones {
+            flags |= FLAG_TOMBSTONES;
+        }
+        if self.append_log {
+            flags |= FLAG_APPEND_LOG;
+        }
+        if self.versions {
+            flags |= FLAG_VERSIONS;
+        }
+        if self.compression {
+            flags |= FLAG_COMPRESSION;
+        }
+        if self.crc64 {
+            flags |= FLAG_CRC64;
+        }
+        if self.no_checksum {
+            flags |= FLAG_NO_CHECKSUM;
+        }
+        if self.tags {
+            flags |= FLAG_TAGS;
+        }
+        let mut fields = Vec::new();
+        if let Some(record_size) = self.record_size {
+            flags |= FLAG_FIXED_SIZE;
+            fields.extend_from_slice(&record_size.to_le_bytes());
+        }
+        if self.checksum_algorithm.is_some() {
+            flags |= FLAG_CUSTOM_CHECKSUM;
+            fields.extend_from_slice(&self.check_value().to_le_bytes());
+        }
+        if self.alignment > 1 {
+            flags |= FLAG_ALIGNED;
+            fields.extend_from_slice(&self.alignment.to_le_bytes());
+        }
+        if let Some(block_size) = self.block_size {
+            flags |= FLAG_BLOCKS;
+            fields.extend_from_slice(&block_size.to_le_bytes());
+        }
+        if let Some(app_id) = self.app_id {
+            flags |= FLAG_APP_ID;
+            fields.extend_from_slice(&app_id);
+        }
+        let mut header_len = (FILE_HEADER_LEN + fields.len()) as u64;
+        if self.alignment > 1 {
+            let alignment = u64::from(self.alignment);
+            let body_start = header_len + self.frame_header_len();
+            header_len += (alignment - body_start % alignment) % alignment;
+            fields.resize(header_len as usize - FILE_HEADER_LEN, 0);
+        }
+        let header_len =
+            u16::try_from(header_len).expect("alignment is too large for the header size");
+        let mut buf = Vec::with_capacity(usize::from(header_len));
+        buf.extend_from_slice(&MAGIC);
+        buf.push(FORMAT_VERSION);
+        buf.push(self.format);
+        buf.extend_from_slice(&header_len.to_le_bytes());
+        flags |= u32::from(MINOR_VERSION) << MINOR_VERSION_SHIFT;
+        buf.extend_from_slice(&flags.to_le_bytes());
+        buf.extend_from_slice(&fields);
+        self.data_start = u64::from(header_len);
+        buf
+    }
+
+    /// Reads the layout of a file from its first bytes. Files that do not start with the
+    /// magic bytes are assumed to be headerless files using the default layout.
+    ///
+    /// Since a header only records the check value of the checksum algorithm, the caller
+    /// passes the algorithm it expects the file to use, or `None` for the default one.
+    pub(crate) fn read<R: Read>(
+        r: &mut R,
+        checksum_algorithm: Option<&'static Algorithm<u32>>,
+    ) -> Result<Self, DatabaseError> {
+        let mut layout = Self {
+            checksum_algorithm: Self::custom_algorithm(checksum_algorithm),
+            ..Self::default()
+        };
+        let header = layout.read_header(r)?;
+        layout.check_algorithm(header.map_or(CRC_32_ISO_HDLC.check, |header| header.check))?;
+        Ok(layout)
+    }
+
+    /// Reads the header of a file into this layout, without checking the checksum
+    /// algorithm it records, and returns what else the header records, or `None` if the
+    /// file has no header.
+    fn read_header<R: Read>(&mut self, r: &mut R) -> Result<Option<HeaderFields>, DatabaseError> {
+        let layout = self;
+        let mut prefix = Vec::with_capacity(8);
+        r.take(8).read_to_end(&mut prefix)?;
+        if prefix.len() < MAGIC.len() || prefix[..4] != MAGIC {
+            return Ok(None);
+        }
+        if prefix.len() < 8 {
+            return Err(DatabaseError::BadHeader);
+        }
+        let version = prefix[4];
+        if version != FORMAT_VERSION {
+            return Err(DatabaseError::UnsupportedVersion(version));
+        }
+        let header_len = LittleEndian::read_u16(&prefix[6..8]);
+        if usize::from(header_len) < FILE_HEADER_LEN {
+            return Err(DatabaseError::BadHeader);
+        }
+        let mut rest = Vec::with_capacity(usize::from(header_len) - 8);
+        r.take(u64::from(header_len) - 8).read_to_end(&mut rest)?;
+        if rest.len() != usize::from(header_len) - 8 {
+            return Err(DatabaseError::BadHeader);
+        }
+        let flags = LittleEndian::read_u32(&rest[0..4]);
+        let minor_version = (flags >> MINOR_VERSION_SHIFT) as u8;
+        let newer = minor_version > MINOR_VERSION;
+        if !newer && flags & !KNOWN_FLAGS & 0x00ff_ffff != 0 {
+            return Err(DatabaseError::BadHeader);
+        }
+        let flags = flags & KNOWN_FLAGS;
+        let mut fields = &rest[4..];
+        let mut read_field = |flag: u32| -> Result<Option<u32>, DatabaseError> {
+            if flags & flag == 0 {
+                return Ok(None);
+            }
+            let field = fields
+                .read_u32::<LittleEndian>()
+                .map_err(|_| DatabaseError::BadHeader)?;
+            Ok(Some(field))
+        };
+        layout.record_size = read_field(FLAG_FIXED_SIZE)?;
+        let check = read_field(FLAG_CUSTOM_CHECKSUM)?;
+        layout.alignment = read_field(FLAG_ALIGNED)?.unwrap_or(0);
+        layout.block_size = read_field(FLAG_BLOCKS)?;
+        if layout.block_size == Some(0) {
+            return Err(DatabaseError::BadHeader);
+        }
+        if flags & FLAG_APP_ID != 0 {
+            let mut app_id = [0; 8];
+            fields
+                .read_exact(&mut app_id)
+                .map_err(|_| DatabaseError::BadHeader)?;
+            layout.app_id = Some(app_id);
+        }
+        if layout.alignment > MAX_ALIGNMENT {
+            return Err(DatabaseError::BadHeader);
+        }
+        if flags & FLAG_BIG_ENDIAN != 0 {
+            layout.endianness = Endianness::Big;
+        }
+        layout.format = prefix[5];
+        layout.timestamps = flags & FLAG_TIMESTAMPS != 0;
+        layout.tombstones = flags & FLAG_TOMBSTONES != 0;
+        layout.append_log = flags & FLAG_APPEND_LOG != 0;
+        layout.versions = flags & FLAG_VERSIONS != 0;
+        layout.compression = flags & FLAG_COMPRESSION != 0;
+        layout.crc64 = flags & FLAG_CRC64 != 0;
+        layout.no_checksum = flags & FLAG_NO_CHECKSUM != 0;
+        layout.tags = flags & FLAG_TAGS != 0;
+        layout.header = true;
+        layout.data_start = u64::from(header_len);
+        Ok(Some(HeaderFields {
+            check: check.unwrap_or(CRC_32_ISO_HDLC.check),
+            minor_version,
+        }))
+    }
+
+    /// Fails unless `found`, the check value recorded in a file, belongs to the checksum
+    /// algorithm of this layout.
+    fn check_algorithm(&self, found: u32) -> Result<(), DatabaseError> {
+        let expected = self.check_value();
+        if found != expected {
+            return Err(DatabaseError::ChecksumAlgorithmMismatch { expected, found });
+        }
+        Ok(())
+    }
+}
+
+/// Compresses the encoded document of a frame. Requires the `gzip` feature.
+#[cfg(feature = "gzip")]
+pub(crate) fn deflate(data: &[u8], level: Option<u32>) -> Result<Vec<u8>, DatabaseError> {
+    let level = level.map_or_else(flate2::Compression::default, flate2::Compression::new);
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), level);
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(not(feature = "gzip"))]
+pub(crate) fn deflate(_: &[u8], _: Option<u32>) -> Result<Vec<u8>, DatabaseError> {
+    Err(DatabaseError::Unsupported(
+        "compressing documents requires the `gzip` feature",
+    ))
+}
+
+/// Decompresses the encoded document of a frame. Requires the `gzip` feature.
+#[cfg(feature = "gzip")]
+fn inflate(data: &[u8]) -> Result<Vec<u8>, DatabaseError> {
+    let mut decoded = Vec::new();
+    flate2::read::DeflateDecoder::new(data).read_to_end(&mut decoded)?;
+    Ok(decoded)
+}
+
+#[cfg(not(feature = "gzip"))]
+fn inflate(_: &[u8]) -> Result<Vec<u8>, DatabaseError> {
+    Err(DatabaseError::Unsupported(
+        "reading compressed documents requires the `gzip` feature",
+    ))
+}
+
+/// What a header records besides the layout of a file.
+struct HeaderFields {
+    check: u32,
+    minor_version: u8,
+}
+
+/// The layout of a file as recorded in its header, returned by [`inspect_header`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderInfo {
+    /// The major and minor version of the file format, or `None` if the file has no
+    /// header, in which case every other field describes the default layout.
+    pub version: Option<(u8, u8)>,
+    /// Whether the file is compressed as a whole, as written by a `GzClient`. Every other
+    /// field then describes the file inside.
+    pub gzip: bool,
+    /// Identifier of the [`Format`] the documents are encoded with, i.e. its
+    /// [`Format::ID`].
+    pub format: u8,
+    pub endianness: Endianness,
+    /// Check value of the checksum algorithm, i.e. its checksum of the ASCII string
+    /// `123456789`, which is `CRC_32_ISO_HDLC.check` for the default algorithm.
+    pub checksum_check: u32,
+    /// Whether the file was created in append mode and is protected from being
+    /// overwritten. See [`ClientBuilder::append`](crate::ClientBuilder::append).
+    pub append_log: bool,
+    pub timestamps: bool,
+    pub tombstones: bool,
+    /// Whether every document stores a schema version.
+    pub versions: bool,
+    /// Whether every document stores a type tag. See
+    /// [`ClientBuilder::frame_tags`](crate::ClientBuilder::frame_tags).
+    pub tags: bool,
+    /// Whether every document can be compressed on its own. See
+    /// [`ClientBuilder::frame_compression`](crate::ClientBuilder::frame_compression).
+    pub frame_compression: bool,
+    /// The width of the checksum of every document. See
+    /// [`ClientBuilder::checksum`](crate::ClientBuilder::checksum).
+    pub checksum: Checksum,
+    /// The size of every encoded document of a file written by a
+    /// [`FixedClient`](crate::FixedClient).
+    pub record_size: Option<u32>,
+    /// Alignment of every frame body in bytes, `0` or `1` if frames are not aligned.
+    pub alignment: u32,
+    /// Maximum number of documents per frame, or `None` for one document per frame.
+    pub block_size: Option<u32>,
+    /// The identifier of the application that created the file. See
+    /// [`ClientBuilder::app_id`](crate::ClientBuilder::app_id).
+    pub app_id: Option<[u8; 8]>,
+    /// Offset of the first frame, which is the size of the header.
+    pub data_start: u64,
+}
+
+/// First bytes of every gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Reads the header of the file at `path` without opening it for writing, creating it or
+/// truncating it, e.g. to pick the options to open it with. Files without a header, such
+/// as empty files, are reported with the default layout.
+///
+/// # Errors
+///
+/// - `DatabaseError::Open` if the file cannot be opened.
+///
+/// - `DatabaseError::BadHeader` or `DatabaseError::UnsupportedVersion` if the file has a
+///   header this version of the crate cannot read.
+///
+/// - `DatabaseError::Unsupported` for files compressed as a whole, without the `gzip`
+///   feature.
+///
+/// - `std::io::Error`
+pub fn inspect_header<P: AsRef<Path>>(path: P) -> Result<HeaderInfo, DatabaseError> {
+    let path = path.as_ref();
+    let mut file = io::BufReader::new(File::open(path).map_err(open_failed(path))?);
+    let gzip = file.fill_buf()?.starts_with(&GZIP_MAGIC);
+    let mut layout = Layout::default();
+    let header = if gzip {
+        read_gzip_header(&mut layout, file)?
+    } else {
+        layout.read_header(&mut file)?
+    };
+    Ok(HeaderInfo {
+        version: header
+            .as_ref()
+            .map(|header| (FORMAT_VERSION, header.minor_version)),
+        gzip,
+        format: layout.format,
+        endianness: layout.endianness,
+        checksum_check: header.map_or(CRC_32_ISO_HDLC.check, |header| header.check),
+        append_log: layout.append_log,
+        timestamps: layout.timestamps,
+        tombstones: layout.tombstones,
+        versions: layout.versions,
+        tags: layout.tags,
+        frame_compression: layout.compression,
+        checksum: if layout.crc64 {
+            Checksum::Crc64
+        } else if layout.no_checksum {
+            Checksum::None
+        } else {
+            Checksum::Crc32
+        },
+        record_size: layout.record_size,
+        alignment: layout.alignment,
+        block_size: layout.block_size,
+        app_id: layout.app_id,
+        data_start: layout.data_start,
+    })
+}
+
+#[cfg(feature = "gzip")]
+fn read_gzip_header<R: Read>(
+    layout: &mut Layout,
+    r: R,
+) -> Result<Option<HeaderFields>, DatabaseError> {
+    layout.read_header(&mut flate2::read::GzDecoder::new(r))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn read_gzip_header<R: Read>(_: &mut Layout, _: R) -> Result<Option<HeaderFields>, DatabaseError> {
+    Err(DatabaseError::Unsupported(
+        "reading gzip-compressed files requires the `gzip` feature",
+    ))
+}
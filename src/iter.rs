@@ -0,0 +1,293 @@
+use crate::{
+    file_end, header::Layout, in_block, read_frame, valid_footer, Backend, DatabaseError, Format,
+};
+use serde::de::DeserializeOwned;
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    fs::File,
+    io::{BufReader, ErrorKind, Read, Seek, SeekFrom},
+};
+
+/// Reads the payloads of a file one frame at a time and keeps track of how far it got.
+/// This is shared by every iterator over a file.
+///
+/// An error confined to a single frame, such as a checksum mismatch or a document that
+/// fails to decode, is yielded without stopping, since the next frame can still be found.
+/// Any other error, such as an IO error, stops the iteration.
+struct Frames<R> {
+    reader: BufReader<R>,
+    layout: Layout,
+    end: Option<u64>,
+    /// Offset of the footer or of the end of the region of the file, at which reading
+    /// stops.
+    stop: Option<u64>,
+    position: u64,
+    frames_seen: usize,
+    /// Documents of the last block read that have not been yielded yet.
+    pending: VecDeque<Vec<u8>>,
+    started: bool,
+    done: bool,
+}
+
+impl<R: Read + Seek> Frames<R> {
+    fn new(file: R, layout: Layout, (end, stop): (Option<u64>, Option<u64>)) -> Self {
+        Self {
+            reader: BufReader::new(file),
+            layout,
+            end: stop.or(end),
+            stop,
+            position: layout.data_start,
+            frames_seen: 0,
+            pending: VecDeque::new(),
+            started: false,
+            done: false,
+        }
+    }
+
+    fn next_payload(&mut self) -> Result<Option<Vec<u8>>, DatabaseError> {
+        if !self.started {
+            self.started = true;
+            if let Err(e) = self.reader.seek(SeekFrom::Start(self.layout.data_start)) {
+                self.done = true;
+                return Err(e.into());
+            }
+        }
+        loop {
+            if let Some(document) = self.pending.pop_front() {
+                return Ok(Some(document));
+            }
+            let limit = self
+                .stop
+                .map_or(u64::MAX, |stop| stop.saturating_sub(self.position));
+            if limit == 0 {
+                return Ok(None);
+            }
+            let frame = match read_frame(&mut (&mut self.reader).take(limit), &self.layout) {
+                Ok(frame) => frame,
+                Err(DatabaseError::Io(e)) if e.kind() == ErrorKind::UnexpectedEof => {
+                    self.done = true;
+                    let len = self.end.map_or(0, |end| end - self.layout.data_start);
+                    if self.frames_seen == 0 && len > 0 {
+                        return Err(DatabaseError::TruncatedHeader(len as usize));
+                    }
+                    return Ok(None);
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Err(e);
+                }
+            };
+            self.frames_seen += 1;
+            self.position += self.layout.frame_len(frame.data_len);
+            if frame.removed {
+                continue;
+            }
+            // The metadata is cut off the front of the body in place, so a payload costs a
+            // single allocation however it is read.
+            let mut body = frame
+                .verify(&self.layout)
+                .map_err(in_block(&self.layout, self.frames_seen - 1))?;
+            if self.layout.block_size.is_some() {
+                for document in self.layout.split_documents(&body) {
+                    self.pending.push_back(document?.to_vec());
+                }
+                continue;
+            }
+            let (extras, data) = self.layout.split_body(&body)?;
+            if let Cow::Owned(data) = self.layout.frame_data(extras, data)? {
+                return Ok(Some(data));
+            }
+            let extras_len = extras.len();
+            body.drain(..extras_len);
+            return Ok(Some(body));
+        }
+    }
+
+    fn next(&mut self) -> Option<Result<Vec<u8>, DatabaseError>> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_payload().transpose();
+        if result.is_none() {
+            self.done = true;
+        }
+        result
+    }
+
+    fn next_document<T, F>(&mut self) -> Option<Result<T, DatabaseError>>
+    where
+        T: DeserializeOwned,
+        F: Format,
+    {
+        Some(
+            self.next()?
+                .and_then(|payload| self.layout.decode::<F, T>(&payload)),
+        )
+    }
+
+    /// Estimates the number of documents left from the average size of the frames read
+    /// so far and the number of bytes left in the file. The upper bound assumes that every
+    /// remaining frame is empty.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            return (0, Some(0));
+        }
+        let Some(end) = self.end else {
+            return (0, None);
+        };
+        let remaining = end.saturating_sub(self.position);
+        let upper = remaining / self.layout.frame_header_len();
+        let estimate = match self.frames_seen {
+            0 => 0,
+            seen => {
+                let average = (self.position - self.layout.data_start) / seen as u64;
+                remaining / average.max(1)
+            }
+        };
+        let clamp = |n: u64| usize::try_from(n).unwrap_or(usize::MAX);
+        (clamp(estimate), Some(clamp(upper)))
+    }
+}
+
+/// Returns the length of the file and the offset at which reading stops, if any, which
+/// is the offset of its footer or the end of the region of the file.
+fn file_len<B: Backend>(file: &mut B, layout: &Layout) -> (Option<u64>, Option<u64>) {
+    let footer = valid_footer(file, layout).ok().flatten();
+    let len = file_end(file, layout).ok();
+    let stop = footer.map(|footer| footer.offset).or(layout.region_end);
+    (len, stop)
+}
+
+/// An iterator over the encoded bytes of every document in a file, returned by
+/// [`Client::raw_payloads`](crate::Client::raw_payloads).
+///
+/// Frames are read and checked one at a time, so the iterator also reports how far it
+/// got, e.g. to render the progress of a scan against the length of the file.
+pub struct RawPayloads<'a, B = File> {
+    frames: Frames<&'a mut B>,
+}
+
+impl<'a, B: Backend> RawPayloads<'a, B> {
+    pub(crate) fn new(file: &'a mut B, layout: Layout) -> Self {
+        let end = file_len(file, &layout);
+        Self {
+            frames: Frames::new(file, layout, end),
+        }
+    }
+
+    /// Returns the offset in the file up to which frames have been read, including the
+    /// header of the file.
+    pub fn bytes_consumed(&self) -> u64 {
+        self.frames.position
+    }
+
+    /// Returns the number of frames read so far, including removed documents that were
+    /// skipped.
+    pub fn frames_seen(&self) -> usize {
+        self.frames.frames_seen
+    }
+}
+
+impl<B: Backend> Iterator for RawPayloads<'_, B> {
+    type Item = Result<Vec<u8>, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.frames.next()
+    }
+
+    /// Estimates the number of documents left from the average size of the frames read
+    /// so far and the number of bytes left in the file. This is only an estimate: frames
+    /// can have very different sizes, removed documents are counted, and documents
+    /// appended while iterating are not. The upper bound assumes that every remaining
+    /// frame is empty.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.frames.size_hint()
+    }
+}
+
+/// An iterator over every document in a file, returned by
+/// [`Client::iter`](crate::Client::iter).
+///
+/// Documents are read, checked and deserialized one at a time, so only a single document
+/// is held in memory. A document that fails its checksum or fails to deserialize is
+/// yielded as an error and the iterator moves on to the next one, so the caller decides
+/// whether to stop. Any other error, such as an IO error, ends the iterator.
+pub struct RecordIter<'a, T, F, B = File> {
+    frames: Frames<&'a mut B>,
+    _phantom: std::marker::PhantomData<(T, F)>,
+}
+
+impl<'a, T, F, B: Backend> RecordIter<'a, T, F, B> {
+    pub(crate) fn new(file: &'a mut B, layout: Layout) -> Self {
+        let end = file_len(file, &layout);
+        Self {
+            frames: Frames::new(file, layout, end),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Returns the offset in the file up to which documents have been read. See
+    /// [`RawPayloads::bytes_consumed`].
+    pub fn bytes_consumed(&self) -> u64 {
+        self.frames.position
+    }
+
+    /// Returns the number of frames read so far. See [`RawPayloads::frames_seen`].
+    pub fn frames_seen(&self) -> usize {
+        self.frames.frames_seen
+    }
+}
+
+impl<T, F, B> Iterator for RecordIter<'_, T, F, B>
+where
+    B: Backend,
+    T: DeserializeOwned,
+    F: Format,
+{
+    type Item = Result<T, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.frames.next_document::<T, F>()
+    }
+
+    /// Estimates the number of documents left. See [`RawPayloads`].
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.frames.size_hint()
+    }
+}
+
+/// An iterator over every document in a file which owns the file handle, returned by
+/// calling `into_iter()` on a [`Client`](crate::Client). See [`RecordIter`].
+pub struct IntoIter<T, F, B = File> {
+    frames: Frames<B>,
+    _phantom: std::marker::PhantomData<(T, F)>,
+}
+
+impl<T, F, B: Backend> IntoIter<T, F, B> {
+    pub(crate) fn new(mut file: B, layout: Layout) -> Self {
+        let end = file_len(&mut file, &layout);
+        Self {
+            frames: Frames::new(file, layout, end),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T, F, B> Iterator for IntoIter<T, F, B>
+where
+    B: Backend,
+    T: DeserializeOwned,
+    F: Format,
+{
+    type Item = Result<T, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.frames.next_document::<T, F>()
+    }
+
+    /// Estimates the number of documents left. See [`RawPayloads`].
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.frames.size_hint()
+    }
+}
@@ -3,7 +3,8 @@
 //! Any type that is able to be deserialized or serialized using `serde` can be stored on disk.
 //! Data is stored on disk with a CRC32 checksum associated with every document to ensure
 //! data integrity. Each document is deserialized into bytes
-//! via `bincode` when writing to file. All data is stored in little-endian order.
+//! via `bincode` when writing to file. All data is stored in little-endian order unless
+//! configured otherwise through [`ClientBuilder::endianness`].
 //!
 //! This crate is meant for storing small serializable data that stores the state of an application
 //! after exit. Since all the data is loaded
@@ -53,29 +54,155 @@
 //!     panic!("File is empty");
 //! }
 //! ```
+//!
+//! # Async runtimes
+//!
+//! Every method of a `Client` blocks on file IO, so calling one from an async task stalls
+//! the executor. Either run the client on a blocking thread, e.g. with Tokio's
+//! `spawn_blocking`, or do the IO asynchronously and only encode and decode the frames
+//! with [`write_frames`] and [`read_frames`], which work on byte buffers:
+//!
+//! ```ignore
+//! let mut buf = Vec::new();
+//! crio::write_frames(&mut buf, &messages)?;
+//! tokio::fs::OpenOptions::new()
+//!     .append(true)
+//!     .create(true)
+//!     .open("messages")
+//!     .await?
+//!     .write_all(&buf)
+//!     .await?;
+//! let buf = tokio::fs::read("messages").await?;
+//! let messages: Vec<Message> = crio::read_frames(&mut buf.as_slice())?;
+//! ```
 
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
-use crc::{Crc, CRC_32_ISO_HDLC};
+use crc::{Algorithm, Crc, CRC_32_ISO_HDLC};
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
+    borrow::Borrow,
+    collections::HashSet,
+    fmt,
     fs::{File, OpenOptions},
-    io::{ErrorKind, Read, Seek, SeekFrom, Write},
+    hash::Hash,
+    io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write},
     num::TryFromIntError,
-    path::Path,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use thiserror::Error;
 
+mod append_only;
+mod backend;
+mod buffered;
+mod cache;
+mod codec;
+mod cursor;
+mod diff;
+mod fixed;
+#[cfg(feature = "notify")]
+mod follow;
+mod format;
+#[cfg(feature = "gzip")]
+mod gzip;
+mod header;
+mod iter;
+mod lock;
+mod migrate;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod multi;
+mod queue;
+mod throttle;
+mod transaction;
+
+use header::{deflate, Layout, MAX_ALIGNMENT, STATUS_REMOVED};
+
+pub use append_only::AppendOnlyClient;
+pub use backend::Backend;
+pub use buffered::BufferedClient;
+pub use cache::{CacheStats, CachedClient};
+pub use codec::{read_frames, write_frames};
+pub use cursor::Cursor;
+pub use diff::{diff, Diff};
+pub use fixed::FixedClient;
+#[cfg(feature = "notify")]
+pub use follow::FollowIter;
+#[cfg(feature = "cbor")]
+pub use format::Cbor;
+pub use format::{Bincode, BincodeVarint, Format};
+#[cfg(feature = "gzip")]
+pub use gzip::GzClient;
+pub use header::{
+    inspect_header, Checksum, Endianness, HeaderInfo, FILE_HEADER_LEN, FOOTER_LEN, FOOTER_MAGIC,
+    FORMAT_VERSION, FRAME_HEADER_LEN, MAGIC,
+};
+pub use iter::{IntoIter, RawPayloads, RecordIter};
+pub use lock::LockGuard;
+pub use migrate::{migrate, MigrationOutcome};
+pub use multi::{MultiClient, MultiIter};
+pub use queue::CrioQueue;
+pub use throttle::ThrottledClient;
+pub use transaction::Transaction;
+
 /// This is the main error type of this crate.
 #[derive(Error, Debug)]
 pub enum DatabaseError {
     /// This returns `std::io::Error`
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    /// The file at `path` could not be opened or created. IO errors that happen after the
+    /// file was opened are returned as `Io`.
+    #[error("failed to open {}: {source}", path.display())]
+    Open {
+        path: PathBuf,
+        source: std::io::Error,
+    },
     /// This error occurs if the saved checksum does not match the expected checksum of the saved document.
     /// This is likely due to data corruption. Data backup is outside the scope of this crate,
     /// thus an external backup solution is strongly recommended.
     #[error("data corruption encountered ({expected:08x} != {saved:08x})")]
     MismatchedChecksum { saved: u32, expected: u32 },
+    /// Like `MismatchedChecksum`, but for the 64-bit checksum of a document in a file with
+    /// [`Checksum::Crc64`], whose frame checksum matched.
+    #[error("data corruption encountered ({expected:016x} != {saved:016x})")]
+    MismatchedChecksum64 { saved: u64, expected: u64 },
+    /// Like `MismatchedChecksum`, but for a check of the whole file that knows where the
+    /// corrupted frame starts, such as [`ClientBuilder::verify_on_open`].
+    #[error("data corruption encountered at offset {offset} ({expected:08x} != {saved:08x})")]
+    CorruptedFrame {
+        offset: u64,
+        saved: u32,
+        expected: u32,
+    },
+    /// Like `MismatchedChecksum`, but for a file with [`ClientBuilder::block_size`], in which
+    /// a checksum covers a whole block of documents. `index` is the zero-based position
+    /// of the corrupted block in the file.
+    #[error("data corruption encountered in block {index} ({expected:08x} != {saved:08x})")]
+    CorruptedBlock {
+        index: usize,
+        saved: u32,
+        expected: u32,
+    },
+    /// A frame appended by a client with [`ClientBuilder::verify_after_write`] did not
+    /// match its checksum when it was read back. `offset` is the start of the frame, or
+    /// the start of the write if it could not be read back whole. The write is undone.
+    #[error("write verification failed at offset {offset}")]
+    WriteVerifyFailed { offset: u64 },
+    /// The file holds this many bytes after its header, which is too few for even the
+    /// header of a single frame, e.g. because the first write was interrupted.
+    #[error("file holds {0} bytes, which is shorter than a frame header")]
+    TruncatedHeader(usize),
+    /// The frame at `offset` declares a body of `expected_len` bytes, but the file ends
+    /// after `available` bytes of it, e.g. because the last write was interrupted.
+    #[error(
+        "frame at offset {offset} declares {expected_len} bytes, but only {available} are left"
+    )]
+    TruncatedFrame {
+        offset: u64,
+        expected_len: u32,
+        available: u64,
+    },
     /// This crate can only store a document that takes up `u32::MAX` bytes of space. If you run
     /// into this error you should consider some other crate.
     #[error("inserted data too large (document > u32::MAX)")]
@@ -83,19 +210,289 @@ pub enum DatabaseError {
     /// Serialization/deserialization error for a document.
     #[error(transparent)]
     SerdeError(#[from] bincode::Error),
+    /// Serialization/deserialization error for a document encoded with a [`Format`] other
+    /// than [`Bincode`].
+    #[error(transparent)]
+    FormatError(Box<dyn std::error::Error + Send + Sync>),
+    /// The file starts with the magic bytes of a header, but the header is incomplete or
+    /// contains flags that this version of the crate does not understand.
+    #[error("malformed file header")]
+    BadHeader,
+    /// The file has a header with a version that this version of the crate cannot read.
+    #[error("unsupported file format version {0}")]
+    UnsupportedVersion(u8),
+    /// The file was written with a different [`Format`] than the one the client uses.
+    #[error("file is encoded with format {found}, expected format {expected}")]
+    FormatMismatch { expected: u8, found: u8 },
+    /// The path points to a directory or some other special file instead of a regular file.
+    #[error("{0} is not a regular file")]
+    NotAFile(PathBuf),
+    /// The directory that should contain the file does not exist. Enable
+    /// [`ClientBuilder::create_dirs`] to create it automatically.
+    #[error("parent directory {0} does not exist")]
+    ParentMissing(PathBuf),
+    /// A [`FixedClient`] can only store documents that all have the same encoded size.
+    #[error("document has an encoded size of {found} bytes, expected {expected} bytes")]
+    RecordSizeMismatch { expected: u32, found: u32 },
+    /// A document would allocate more memory while it is decoded than allowed by
+    /// [`ClientBuilder::deserialize_limit`].
+    #[error("document exceeds the deserialization limit of {0} bytes")]
+    LimitExceeded(u64),
+    /// A frame declares a body larger than allowed by [`ClientBuilder::max_record_size`],
+    /// which likely means that its length is corrupted.
+    #[error("frame declares {declared} bytes, more than the maximum of {max} bytes")]
+    RecordTooLarge { declared: u32, max: u32 },
+    /// A document was decoded, but this many bytes of its frame were left over, which
+    /// means the frame is corrupted or was written with another type. Only detected if
+    /// [`ClientBuilder::reject_trailing_bytes`] is enabled.
+    #[error("{0} bytes left over after decoding a document")]
+    TrailingBytes(usize),
+    /// The file was created with a different checksum algorithm than the one configured
+    /// with [`ClientBuilder::checksum_algorithm`]. Algorithms are identified by their
+    /// check value.
+    #[error("file uses checksum algorithm {found:08x}, expected {expected:08x}")]
+    ChecksumAlgorithmMismatch { expected: u32, found: u32 },
+    /// The file was created by another application than the one configured with
+    /// [`ClientBuilder::app_id`], or without an application identifier if `found` is
+    /// `None`.
+    #[error("file belongs to application {found:?}, expected {expected:?}")]
+    WrongAppId {
+        expected: [u8; 8],
+        found: Option<[u8; 8]>,
+    },
+    /// The file was created by a client in append mode and still contains documents, so
+    /// opening it in overwrite mode would wipe them. Enable [`ClientBuilder::force`] to
+    /// overwrite it anyway.
+    #[error("{0} is an append log, refusing to overwrite it")]
+    ModeConflict(PathBuf),
+    /// The document at `index` was requested, but the collection only has `len`
+    /// documents.
+    #[error("index {index} is out of range for {len} documents")]
+    IndexOutOfRange { index: usize, len: usize },
+    /// The operation requires a feature that the file was not created with.
+    #[error("unsupported operation: {0}")]
+    Unsupported(&'static str),
+    /// The file is locked by another client, and the client was opened with
+    /// [`ClientBuilder::exclusive`] without waiting for the lock.
+    #[error("{} is locked by another client", .0.display())]
+    Locked(PathBuf),
+    /// The file system of the file does not support `capability`, which this crate
+    /// relies on. Only checked if [`ClientBuilder::probe_filesystem`] is enabled.
+    #[error("file system does not support {capability}")]
+    UnsupportedFilesystem { capability: &'static str },
+}
+
+impl DatabaseError {
+    /// Returns the category of this error, which can be inspected without moving or
+    /// cloning the error, e.g. to decide whether an operation should be retried.
+    pub fn kind(&self) -> DatabaseErrorKind {
+        match self {
+            Self::Io(e) if e.kind() == ErrorKind::UnexpectedEof => DatabaseErrorKind::Truncated,
+            Self::TruncatedHeader(_) | Self::TruncatedFrame { .. } => DatabaseErrorKind::Truncated,
+            Self::Io(_)
+            | Self::Open { .. }
+            | Self::NotAFile(_)
+            | Self::ParentMissing(_)
+            | Self::Locked(_) => DatabaseErrorKind::Io,
+            Self::MismatchedChecksum { .. }
+            | Self::MismatchedChecksum64 { .. }
+            | Self::CorruptedFrame { .. }
+            | Self::CorruptedBlock { .. }
+            | Self::WriteVerifyFailed { .. } => DatabaseErrorKind::Checksum,
+            Self::DataTooLarge(_)
+            | Self::RecordSizeMismatch { .. }
+            | Self::LimitExceeded(_)
+            | Self::RecordTooLarge { .. } => DatabaseErrorKind::TooLarge,
+            Self::SerdeError(_) | Self::FormatError(_) | Self::TrailingBytes(_) => {
+                DatabaseErrorKind::Serde
+            }
+            Self::BadHeader
+            | Self::UnsupportedVersion(_)
+            | Self::FormatMismatch { .. }
+            | Self::ChecksumAlgorithmMismatch { .. }
+            | Self::WrongAppId { .. } => DatabaseErrorKind::Header,
+            Self::Unsupported(_)
+            | Self::UnsupportedFilesystem { .. }
+            | Self::ModeConflict(_)
+            | Self::IndexOutOfRange { .. } => DatabaseErrorKind::Unsupported,
+        }
+    }
+}
+
+/// The error returned by [`Client::append_try_iter`], which is either an error yielded by
+/// the iterator or an error of this crate.
+#[derive(Error, Debug)]
+pub enum MixedError<E> {
+    /// The iterator yielded an error after `written` documents were appended.
+    #[error("source failed after {written} documents were written")]
+    Source {
+        written: usize,
+        #[source]
+        error: E,
+    },
+    /// Writing to the file failed. Nothing was appended.
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+}
+
+/// The error returned by [`Client::load`], which keeps the documents that were read
+/// before a checksum mismatch.
+#[derive(Error)]
+pub enum LoadError<T> {
+    /// A document failed its checksum. The documents before it can be recovered with
+    /// [`DataPoisonError::into_inner`].
+    #[error(transparent)]
+    Poisoned(DataPoisonError<T>),
+    /// Loading failed with any other error.
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+}
+
+impl<T> LoadError<T> {
+    /// Returns the category of this error. See [`DatabaseError::kind`].
+    pub fn kind(&self) -> DatabaseErrorKind {
+        match self {
+            Self::Poisoned(_) => DatabaseErrorKind::Checksum,
+            Self::Database(e) => e.kind(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for LoadError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Poisoned(e) => f.debug_tuple("Poisoned").field(e).finish(),
+            Self::Database(e) => f.debug_tuple("Database").field(e).finish(),
+        }
+    }
+}
+
+impl<T> From<LoadError<T>> for DatabaseError {
+    /// Discards the documents of a [`DataPoisonError`], turning it into a
+    /// `DatabaseError::CorruptedFrame`.
+    fn from(e: LoadError<T>) -> Self {
+        match e {
+            LoadError::Poisoned(e) => Self::CorruptedFrame {
+                offset: e.offset,
+                saved: e.saved,
+                expected: e.expected,
+            },
+            LoadError::Database(e) => e,
+        }
+    }
+}
+
+/// A checksum mismatch that stopped a load, along with every document that was read
+/// before the corrupted one, in order.
+#[derive(Error)]
+#[error("data corruption encountered at offset {offset} ({expected:08x} != {saved:08x})")]
+pub struct DataPoisonError<T> {
+    documents: Vec<T>,
+    offset: u64,
+    saved: u32,
+    expected: u32,
+}
+
+impl<T> DataPoisonError<T> {
+    /// Returns the offset in the file at which the corrupted frame starts.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the checksum saved in the corrupted frame.
+    pub fn saved(&self) -> u32 {
+        self.saved
+    }
+
+    /// Returns the checksum computed from the corrupted frame.
+    pub fn expected(&self) -> u32 {
+        self.expected
+    }
+
+    /// Returns the documents that were read before the corrupted one.
+    pub fn into_inner(self) -> Vec<T> {
+        self.documents
+    }
+}
+
+impl<T> fmt::Debug for DataPoisonError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataPoisonError")
+            .field("documents", &self.documents.len())
+            .field("offset", &self.offset)
+            .field("saved", &self.saved)
+            .field("expected", &self.expected)
+            .finish()
+    }
+}
+
+/// The category of a [`DatabaseError`], returned by [`DatabaseError::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DatabaseErrorKind {
+    /// An IO error other than the file ending unexpectedly. These are usually worth
+    /// retrying.
+    Io,
+    /// The file ends in the middle of a document.
+    Truncated,
+    /// A saved checksum does not match its document.
+    Checksum,
+    /// A document is too large, exceeds the deserialization limit, or does not have the
+    /// size required by a [`FixedClient`].
+    TooLarge,
+    /// A document failed to serialize or deserialize.
+    Serde,
+    /// The file header is malformed or has an unsupported version.
+    Header,
+    /// The operation is not supported by the file or its file system, refers to a
+    /// document that does not exist, or would destroy data of a file that is used
+    /// differently.
+    Unsupported,
 }
 
 const CRC: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
 
 /// Responsible for handling IO operations as well as serialization and deserialization.
-pub struct Client<T: Serialize + DeserializeOwned> {
-    file: File,
-    _phantom: std::marker::PhantomData<T>,
+///
+/// Documents are encoded with [`Bincode`] unless another [`Format`] is specified, e.g.
+/// `Client<T, Cbor>`. Methods that write documents only require `T: Serialize` and
+/// methods that read them only require `T: DeserializeOwned`, so a client that only
+/// writes or only reads works with types that implement a single half of `serde`.
+///
+/// A client owns a single file handle, whose cursor is shared by every method. Each
+/// method seeks to the position it needs before touching the file, e.g. the start of the
+/// first document for reads and the end of the file for writes, and makes no promise
+/// about where it leaves the cursor. Typed reads, raw reads through `as_bytes_reader()`
+/// and writes can therefore be freely interleaved without affecting each other.
+///
+/// The file can be swapped for another [`Backend`], such as a `Cursor<Vec<u8>>`, with
+/// [`from_backend`](Client::from_backend), which creates a `Client<T, F, B>`.
+pub struct Client<T, F: Format = Bincode, B = File> {
+    file: B,
+    path: PathBuf,
+    created: bool,
+    layout: Layout,
+    flush_every: Option<Duration>,
+    last_flush: Instant,
+    scratch: Vec<u8>,
+    write_through: bool,
+    keep_backup: bool,
+    /// The length of the file and the checksums of its documents, as of the last call to
+    /// `append_unique()`.
+    unique: Option<(u64, HashSet<u32>)>,
+    /// The length of the file and the number of its documents, as of the last call to
+    /// `append()`.
+    appended: Option<(u64, usize)>,
+    /// The temporary file that the handle points to instead of the file at `path`, if the
+    /// client was opened in overwrite mode on a file holding data, until it replaces it.
+    pending: Option<Replacement>,
+    /// The lock file held for the lifetime of the client, with [`ClientBuilder::exclusive`].
+    lock: Option<File>,
+    _phantom: std::marker::PhantomData<(T, F)>,
 }
 
-impl<T> Client<T>
+impl<T, F> Client<T, F>
 where
-    T: Serialize + DeserializeOwned,
+    F: Format,
 {
     /// Creates a new client. It opens the file if a file with the same name exists or
     /// creates a new file if it doesn't exist. Set the `append` parameter to `false` if you want to
@@ -104,196 +501,8046 @@ where
     ///
     /// # Errors
     ///
-    /// - Returns `std::io::Error` if it fails to open or create a new file.
+    /// - `DatabaseError::Open` if it fails to open or create a new file.
     pub fn new<P: AsRef<Path>>(path: P, append: bool) -> Result<Self, DatabaseError> {
-        let file = if append {
-            OpenOptions::new()
-                .read(true)
-                .create(true)
-                .append(true)
-                .open(path.as_ref())?
-        } else {
-            OpenOptions::new()
-                .read(true)
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .open(path.as_ref())?
-        };
+        ClientBuilder::new().append(append).open(path)
+    }
+
+    /// Opens a client like [`new`](Client::new), checking the checksum of every document
+    /// in append mode first, so that nothing is appended to a file that is already
+    /// corrupted. In overwrite mode, nothing is checked since the documents are replaced
+    /// anyway. See [`ClientBuilder::verify_on_open`].
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::CorruptedFrame` with the offset of the first document that fails
+    ///   its checksum.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of a
+    ///   document.
+    ///
+    /// - See [`new`](Client::new).
+    pub fn new_verified<P: AsRef<Path>>(path: P, append: bool) -> Result<Self, DatabaseError> {
+        ClientBuilder::new()
+            .append(append)
+            .verify_on_open(append)
+            .open(path)
+    }
+
+    /// Opens a client like [`new`](Client::new) that compresses every document it writes
+    /// at `level`. See [`ClientBuilder::compression_level`].
+    ///
+    /// # Errors
+    ///
+    /// See [`ClientBuilder::open`].
+    pub fn with_compression<P: AsRef<Path>>(
+        path: P,
+        append: bool,
+        level: u32,
+    ) -> Result<Self, DatabaseError> {
+        ClientBuilder::new()
+            .append(append)
+            .compression_level(level)
+            .open(path)
+    }
+
+    /// Creates a read-only client for the bytes `start..start + len` of `file`, e.g. for a
+    /// section of a container format that embeds documents written by this crate. The
+    /// region is read like a whole file, starting with a header if it has one, and
+    /// nothing outside of it is ever read, even if the length of a frame points past it.
+    /// The region has to use the default checksum algorithm.
+    ///
+    /// Since the region cannot grow or be replaced on its own, every method that writes
+    /// to the file, replaces it, or opens it again by path, such as `write()`,
+    /// `vacuum()`, `lock()` or `reader()`, fails with `DatabaseError::Unsupported`.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::BadHeader`, `DatabaseError::UnsupportedVersion` or
+    ///   `DatabaseError::ChecksumAlgorithmMismatch` if the region has a header this
+    ///   version of the crate cannot read.
+    ///
+    /// - `DatabaseError::FormatMismatch` if the region was written with another format
+    ///   than `F`.
+    ///
+    /// - `std::io::Error`
+    pub fn from_file_region(mut file: File, start: u64, len: u64) -> Result<Self, DatabaseError> {
+        file.seek(SeekFrom::Start(start))?;
+        let mut layout = Layout::read(&mut (&file).take(len), None)?;
+        if layout.format != F::ID {
+            return Err(DatabaseError::FormatMismatch {
+                expected: F::ID,
+                found: layout.format,
+            });
+        }
+        if layout.record_size.is_some() {
+            return Err(DatabaseError::Unsupported(
+                "file stores fixed-size documents, open it with a FixedClient",
+            ));
+        }
+        let end = start + len;
+        layout.data_start = (start + layout.data_start).min(end);
+        layout.region_end = Some(end);
         Ok(Self {
             file,
-            _phantom: std::marker::PhantomData::default(),
+            path: PathBuf::new(),
+            created: false,
+            layout,
+            flush_every: None,
+            last_flush: Instant::now(),
+            scratch: Vec::new(),
+            write_through: false,
+            keep_backup: false,
+            unique: None,
+            appended: None,
+            pending: None,
+            lock: None,
+            _phantom: std::marker::PhantomData,
         })
     }
 
-    /// Returns a collection. If the file is empty, this method
-    /// returns `Ok(None)`.
+    /// Consumes the client and returns its file handle, positioned at the end of the
+    /// documents, i.e. in front of the footer if the file has one, or at the end of the
+    /// region for a client created with [`from_file_region`](Client::from_file_region).
+    /// This is useful to keep writing to a file that embeds documents written by this
+    /// crate, e.g. to append a footer of another format.
     ///
-    /// The collection is returned in the order in which the documents
-    /// were inserted into the file previously.
+    /// The client does not buffer anything, so every write made through it is already in
+    /// the file, but not necessarily on disk: syncing and closing the file is up to the
+    /// caller from then on.
     ///
     /// # Errors
     ///
-    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
-    /// is returned.
+    /// - `std::io::Error`
+    pub fn into_file(self) -> Result<File, DatabaseError> {
+        self.into_backend()
+    }
+
+    /// Creates a new client in overwrite mode and writes `documents` to the file, one at
+    /// a time, before returning it. Any previous contents of the file are discarded.
     ///
-    /// - `bincode::Error` occurs if the deserializer fails to deserialize bytes from
-    /// the file to your requested document type. In that case, the most probable reason
-    /// is that the data in that file stores data of some other data type and you are
-    /// attempting to deserialize it to the wrong data type.
+    /// # Errors
     ///
-    /// - `std::io::Error`
-    pub fn load(&mut self) -> Result<Option<Vec<T>>, DatabaseError> {
-        let mut buf = Vec::new();
-        self.file.seek(SeekFrom::Start(0))?;
-        self.file.read_to_end(&mut buf)?;
-        if buf.is_empty() {
+    /// - `DatabaseError::Open` if it fails to open or create a new file, or
+    ///   `std::io::Error` if it fails to write to it.
+    ///
+    /// - `std::num::TryFromIntError` occurs when a document takes up more space than
+    ///   `u32::MAX` bytes.
+    ///
+    /// - `bincode::Error` when a document fails to serialize for some reason.
+    pub fn create_with<P, I>(path: P, documents: I) -> Result<Self, DatabaseError>
+    where
+        T: Serialize,
+        P: AsRef<Path>,
+        I: IntoIterator<Item = T>,
+    {
+        let mut client = Self::new(path, false)?;
+        client.append_documents(documents)?;
+        Ok(client)
+    }
+
+    /// Converts every document of the file at `path` from `T` to `U` with `f`, e.g. after
+    /// a field was added to a type, and returns the number of documents converted. To
+    /// upgrade the file format instead, see [`migrate`](crate::migrate()).
+    ///
+    /// Every document is checked and deserialized as `T` first, so a file that is
+    /// corrupted or holds other documents is left untouched. The converted documents are
+    /// written with the same layout into a temporary file which is then renamed over the
+    /// original, so the file holds either the old or the new documents, even if the
+    /// process crashes. Removed documents are dropped.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Open` if the file does not exist or fails to open.
+    ///
+    /// - See [`load`](Client::load) and [`write_many`](Client::write_many).
+    pub fn migrate<U, P, G>(path: P, f: G) -> Result<usize, DatabaseError>
+    where
+        T: DeserializeOwned,
+        U: Serialize,
+        P: AsRef<Path>,
+        G: FnMut(T) -> U,
+    {
+        let path = path.as_ref();
+        std::fs::metadata(path).map_err(open_failed(path))?;
+        let mut client = Self::new(path, true)?;
+        let documents = client.load()?.unwrap_or_default();
+        let count = documents.len();
+        let mut layout = client.layout;
+        replace_file(&mut client.file, path, |temp| {
+            let mut writer = BufWriter::new(temp);
+            if layout.needs_header() {
+                writer.write_all(&layout.encode_header())?;
+            }
+            let documents = documents.into_iter().map(f);
+            write_encoded::<F, _, U, _>(&mut writer, documents, &layout, &mut Vec::new())?;
+            writer.flush()?;
+            Ok(())
+        })?;
+        Ok(count)
+    }
+
+    /// Opens an independent read-only handle to the file backing this client. Every
+    /// reader has its own cursor, so multiple readers can be moved to different threads
+    /// and read concurrently without contending over the client. Writes still have to go
+    /// through the client.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Open` if it fails to open the file.
+    pub fn reader(&self) -> Result<Reader<T, F>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        self.layout.require_whole_file()?;
+        let path = self.current_path();
+        let file = File::open(path).map_err(open_failed(path))?;
+        Ok(Reader {
+            file,
+            layout: self.layout,
+            _phantom: std::marker::PhantomData,
+        })
+    }
+
+    /// Returns an iterator that yields every document appended to the file from now on,
+    /// by this client or by another process, blocking until the next one arrives. The
+    /// file is watched with the notifications of the file system, so nothing is read
+    /// until it changes. Requires the `notify` feature.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Open` if it fails to open the file.
+    ///
+    /// - `std::io::Error` if the file cannot be watched, or of kind `UnexpectedEof` if
+    ///   the file is truncated while its end is located.
+    #[cfg(feature = "notify")]
+    pub fn follow(&self) -> Result<FollowIter<T, F>, DatabaseError> {
+        self.layout.require_whole_file()?;
+        FollowIter::new(self.current_path(), self.layout)
+    }
+
+    /// Opens a read-only handle like `reader()`, which only sees the documents that are
+    /// in the file right now. Documents appended afterwards, by this client or by another
+    /// process, are ignored by the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Open` if it fails to open the file.
+    pub fn snapshot_reader(&self) -> Result<SnapshotReader<T, F>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        self.layout.require_whole_file()?;
+        let path = self.current_path();
+        let file = File::open(path).map_err(open_failed(path))?;
+        let len = data_end(&file, &self.layout)?;
+        Ok(SnapshotReader {
+            file,
+            layout: self.layout,
+            len,
+            _phantom: std::marker::PhantomData,
+        })
+    }
+
+    /// Returns a collection like `load()`, but maps the file into memory and decodes the
+    /// documents straight from the mapping, instead of reading the whole file into a
+    /// buffer first. Requires the `mmap` feature. On platforms other than Unix, the file
+    /// is read into a buffer like in `load()`.
+    ///
+    /// The file must not be truncated by another process while it is loaded, which
+    /// makes this process crash with `SIGBUS`, and documents that are overwritten in the
+    /// meantime may be read half-written, in which case their checksum is likely to fail.
+    /// Writes through this client cannot happen during the load.
+    ///
+    /// # Errors
+    ///
+    /// The same errors as `load()`.
+    #[cfg(feature = "mmap")]
+    pub fn load_mmap(&self) -> Result<Option<Vec<T>>, LoadError<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let end = data_end(&self.file, &self.layout)?;
+        if end <= self.layout.data_start {
             return Ok(None);
         }
-        let result = binary_to_vec(&buf)?;
-        Ok(Some(result))
+        let map = mmap::Mmap::map(&self.file, end)?;
+        let raw_data = &map[self.layout.data_start as usize..];
+        binary_to_vec::<F, _>(raw_data, &self.layout, true).map(Some)
     }
 
-    /// Validates if the checksum of the documents in the collection matches its
-    /// corresponding stored checksum value. This is an efficient way to determine
-    /// if a file has possibly been corrupted. Returns `Ok(true)` if the file has
-    /// no checksum mismatches and `Ok(false)` if the opposite is true.
+    /// Splits the documents into `parts` new files in `dir` with the same layout, e.g. to
+    /// process each part on its own, and returns their paths in order. The documents are
+    /// split contiguously: the first part holds the first documents and so on, and parts
+    /// differ by at most one document in size. Parts are named after this file, e.g.
+    /// `data.0.crio`, `data.1.crio` and so on for `data.crio`, and existing files with
+    /// these names are overwritten. If there are fewer documents than parts, the last
+    /// parts are empty.
+    ///
+    /// The file is read twice, once to count the documents by their frame headers and
+    /// once to copy their frames as is into the parts, so memory use stays bounded.
     ///
     /// # Errors
     ///
-    /// - `std::io::Error`
-
-    pub fn validate(&mut self) -> Result<bool, DatabaseError> {
-        let mut buf = Vec::new();
-        self.file.seek(SeekFrom::Start(0))?;
-        self.file.read_to_end(&mut buf)?;
-        loop {
-            match process_document(&mut buf.as_slice()) {
-                Ok(_) => (),
-                Err(DatabaseError::MismatchedChecksum { .. }) => return Ok(false),
-                Err(DatabaseError::Io(e)) if e.kind() == ErrorKind::UnexpectedEof => break,
-                Err(e) => return Err(e),
+    /// - `DatabaseError::Unsupported` if `parts` is 0, or for files with blocks.
+    ///
+    /// - `DatabaseError::Open` with the path of the part that cannot be created.
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned. The parts written so far are left in `dir`.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn split_into<P: AsRef<Path>>(
+        &mut self,
+        dir: P,
+        parts: usize,
+    ) -> Result<Vec<PathBuf>, DatabaseError> {
+        self.layout.require_frame_per_document()?;
+        if parts == 0 {
+            return Err(DatabaseError::Unsupported("cannot split into zero parts"));
+        }
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        let count = frame_offsets(&mut self.file, &self.layout)?.len();
+        let end = data_end(&self.file, &self.layout)?;
+        let stem = self.path.file_stem().unwrap_or("part".as_ref());
+        let extension = self.path.extension();
+        let mut layout = self.layout;
+        let header = layout.needs_header().then(|| layout.encode_header());
+        let header_len = layout.frame_header_len() as usize;
+        let mut scratch = Vec::new();
+        self.file.seek(SeekFrom::Start(layout.data_start))?;
+        let mut reader = BufReader::new((&mut self.file).take(end - layout.data_start));
+        let mut paths = Vec::with_capacity(parts);
+        for part in 0..parts {
+            let mut name = stem.to_owned();
+            name.push(format!(".{part}"));
+            if let Some(extension) = extension {
+                name.push(".");
+                name.push(extension);
+            }
+            let path = dir.join(name);
+            let mut writer = BufWriter::new(File::create(&path).map_err(open_failed(&path))?);
+            if let Some(header) = &header {
+                writer.write_all(header)?;
             }
+            let mut left = count / parts + usize::from(part < count % parts);
+            while left > 0 {
+                let frame = read_frame(&mut reader, &layout)?;
+                if frame.removed {
+                    continue;
+                }
+                if frame.data.len() != frame.data_len as usize {
+                    return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+                }
+                scratch.clear();
+                scratch.resize(header_len, 0);
+                scratch.extend_from_slice(&frame.verify(&layout)?);
+                finish_frame(&mut scratch, &layout)?;
+                writer.write_all(&scratch)?;
+                left -= 1;
+            }
+            writer.flush()?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// Replaces the file with the backup kept by [`ClientBuilder::keep_backup`], undoing
+    /// the last overwrite. Returns `false`, leaving the file unchanged, if there is no
+    /// backup. The backup is renamed over the file, so the file holds either its current
+    /// or its previous contents, even if the process crashes, and the backup is gone
+    /// afterwards.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn restore_backup(&mut self) -> Result<bool, DatabaseError> {
+        self.layout.require_whole_file()?;
+        self.commit_overwrite()?;
+        let backup = backup_path(&self.path);
+        let restored = match OpenOptions::new().read(true).write(true).open(&backup) {
+            Ok(restored) => restored,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        // As in `replace_file`, the handle to the current file is closed before the
+        // rename for the sake of Windows.
+        drop(std::mem::replace(&mut self.file, restored));
+        if let Err(e) = rename_over(&backup, &self.path) {
+            self.reopen()?;
+            return Err(e.into());
         }
+        self.after_replace()?;
         Ok(true)
     }
 
-    /// Writes the provided serializable documents to disk. If no file is found,
-    /// a new file will be created and written to.
+    /// Blocks until this client holds an exclusive lock on its file, and returns a guard
+    /// which releases it when dropped. Other clients calling this method on the same file,
+    /// in this process or another one, wait until then, so a compound operation such as
+    /// loading, modifying and replacing the documents is not interleaved with theirs. A
+    /// client opened with [`ClientBuilder::exclusive`] already holds the lock, so the guard
+    /// is returned right away.
     ///
     /// # Errors
     ///
-    /// - `std::num::TryFromIntError` occurs when a document you are inserting
-    /// takes up more space than `u32::MAX` bytes.
+    /// - `DatabaseError::Open` if it fails to open or create the lock file.
     ///
-    /// - `std::io::Error`
+    /// - `std::io::Error` if the lock cannot be taken.
+    pub fn lock(&mut self) -> Result<LockGuard<'_, T, F>, DatabaseError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        self.layout.require_whole_file()?;
+        LockGuard::new(self)
+    }
+
+    /// Returns every document and then removes them like [`clear`](Client::clear), e.g.
+    /// to take every pending job out of a work queue, or an empty collection if the file
+    /// is empty. Removed documents are not returned.
     ///
-    /// - `bincode::Error` when the documents provided fails to serialize for some reason.
-    pub fn write_many(&mut self, documents: &[T]) -> Result<(), DatabaseError> {
-        let buf = vec_to_binary(documents)?;
-        self.file.seek(SeekFrom::End(0))?;
-        self.file.write_all(&buf)?;
-        Ok(())
+    /// Both happen while holding the lock of the file, see [`lock`](Client::lock), so a
+    /// document written by another client that also locks the file is either returned or
+    /// kept in the file, but never lost. Clients that write without locking the file are
+    /// not excluded.
+    ///
+    /// # Errors
+    ///
+    /// - See [`lock`](Client::lock) and [`load`](Client::load). If the documents fail to
+    ///   load, nothing is removed.
+    pub fn drain(&mut self) -> Result<Vec<T>, DatabaseError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let mut guard = self.lock()?;
+        let documents = guard.load()?.unwrap_or_default();
+        guard.clear()?;
+        Ok(documents)
     }
+}
 
-    /// Writes the provided serializable document to disk. If no file is found,
-    /// a new file will be created and written to.
+impl<T, F, B> Client<T, F, B>
+where
+    F: Format,
+    B: Backend,
+{
+    /// Creates a client that stores its documents in `backend` instead of a file, e.g. a
+    /// `Cursor<Vec<u8>>` in tests. `backend` holds the same bytes as a file written by
+    /// this crate, so it is read like one if it holds any, and a new header is written to
+    /// it otherwise. As with [`new`](Client::new), set `append` to `false` to overwrite
+    /// every document, which truncates `backend` right away, or `true` to append to it.
+    ///
+    /// The client has no path, so the methods that open the file again by path do not
+    /// exist on it. See [`Backend`].
     ///
     /// # Errors
     ///
-    /// - `std::num::TryFromIntError` occurs when a document you are inserting
-    /// takes up more space than `u32::MAX` bytes.
+    /// - `DatabaseError::BadHeader` or `DatabaseError::UnsupportedVersion` if `backend`
+    ///   has a header this version of the crate cannot read.
+    ///
+    /// - `DatabaseError::FormatMismatch` if `backend` was written with another format
+    ///   than `F`.
     ///
     /// - `std::io::Error`
+    pub fn from_backend(mut backend: B, append: bool) -> Result<Self, DatabaseError> {
+        if !append {
+            backend.set_len(0)?;
+        }
+        backend.seek(SeekFrom::Start(0))?;
+        let layout = ClientBuilder::new()
+            .append(append)
+            .layout::<F, _>(&mut backend)?;
+        Ok(Self {
+            file: backend,
+            path: PathBuf::new(),
+            created: false,
+            layout,
+            flush_every: None,
+            last_flush: Instant::now(),
+            scratch: Vec::new(),
+            write_through: false,
+            keep_backup: false,
+            unique: None,
+            appended: None,
+            pending: None,
+            lock: None,
+            _phantom: std::marker::PhantomData,
+        })
+    }
+
+    /// Consumes the client and returns its backend, positioned at the end of the
+    /// documents like [`into_file`](Client::into_file).
     ///
-    /// - `bincode::Error` when the document provided fails to serialize for some reason.
-    pub fn write(&mut self, document: &T) -> Result<(), DatabaseError> {
-        let buf = vec_to_binary(std::array::from_ref(document))?;
-        self.file.seek(SeekFrom::End(0))?;
-        self.file.write_all(&buf)?;
-        Ok(())
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn into_backend(mut self) -> Result<B, DatabaseError> {
+        self.commit_overwrite()?;
+        let end = data_end(&self.file, &self.layout)?;
+        self.file.seek(SeekFrom::Start(end))?;
+        Ok(self.file)
     }
-}
 
-fn binary_to_vec<T: DeserializeOwned>(mut raw_data: &[u8]) -> Result<Vec<T>, DatabaseError> {
-    let mut result = Vec::new();
-    loop {
-        let raw_doc = process_document(&mut raw_data);
-        let raw_doc = match raw_doc {
-            Ok(d) => d,
-            Err(e) => match e {
-                DatabaseError::Io(e) if e.kind() == ErrorKind::UnexpectedEof => {
-                    break;
-                }
-                _ => return Err(e),
-            },
-        };
-        let data = bincode::deserialize(&raw_doc)?;
-        result.push(data);
+    /// Returns `true` if the file did not exist and was created when this client was
+    /// constructed, or `false` if an existing file was opened.
+    pub fn was_created(&self) -> bool {
+        self.created
     }
-    Ok(result)
-}
 
-fn process_document<R: Read>(f: &mut R) -> Result<Vec<u8>, DatabaseError> {
-    let saved_checksum = f.read_u32::<LittleEndian>()?;
-    let data_len = f.read_u32::<LittleEndian>()?;
-    let mut data = Vec::with_capacity(data_len as usize);
-    f.take(u64::from(data_len)).read_to_end(&mut data)?;
-    let expected_checksum = CRC.checksum(&data);
-    if expected_checksum != saved_checksum {
-        return Err(DatabaseError::MismatchedChecksum {
-            saved: saved_checksum,
-            expected: expected_checksum,
-        });
+    /// Returns the path of the file, as given when this client was constructed. In
+    /// overwrite mode, this is the file that is replaced, not the temporary file that
+    /// is written to until then.
+    pub fn path(&self) -> &Path {
+        &self.path
     }
-    Ok(data)
-}
 
-fn vec_to_binary<T: Serialize>(data: &[T]) -> Result<Vec<u8>, DatabaseError> {
-    let mut buf = Vec::new();
-    for document in data {
-        let raw_data = bincode::serialize(&document)?;
-        let data_len = raw_data.len();
-        let checksum = CRC.checksum(&raw_data);
-        buf.write_u32::<LittleEndian>(checksum)?;
-        buf.write_u32::<LittleEndian>(u32::try_from(data_len)?)?;
-        buf.write_all(&raw_data)?;
+    /// Returns a collection. If the file is empty, this method
+    /// returns `Ok(None)`.
+    ///
+    /// The collection is returned in the order in which the documents
+    /// were inserted into the file previously.
+    ///
+    /// The file is read with positioned reads, which leave its cursor untouched, so this
+    /// method only needs a shared reference and can run on several threads at once.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `LoadError::Poisoned` error is returned, which
+    ///   records the offset of the corrupted frame and from which the documents before
+    ///   the corrupted one can be recovered with [`DataPoisonError::into_inner`]. In a
+    ///   file with blocks, a corrupted block is returned as a
+    ///   `DatabaseError::CorruptedBlock` instead.
+    ///
+    /// - `bincode::Error` occurs if the deserializer fails to deserialize bytes from
+    ///   the file to your requested document type. In that case, the most probable reason
+    ///   is that the data in that file stores data of some other data type and you are
+    ///   attempting to deserialize it to the wrong data type.
+    ///
+    /// - `std::io::Error`
+    ///
+    /// Every error other than `LoadError::Poisoned` is a `LoadError::Database`. A
+    /// `LoadError` converts into a `DatabaseError`, e.g. with `?`, by discarding the
+    /// recovered documents.
+    pub fn load(&self) -> Result<Option<Vec<T>>, LoadError<T>>
+    where
+        T: DeserializeOwned,
+    {
+        load_file::<F, _, _>(&self.file, &self.layout, true)
     }
-    Ok(buf)
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::{binary_to_vec, vec_to_binary};
-    use serde_derive::{Deserialize, Serialize};
+    /// Returns a collection like `load()`, but checks and deserializes the documents on
+    /// every available thread, which speeds up loading a large file of documents that
+    /// are expensive to deserialize. The whole file is read into memory first and split
+    /// into frames, which are then decoded in contiguous runs, one per thread, and
+    /// collected in order.
+    ///
+    /// # Errors
+    ///
+    /// The same errors as `load()`. If several documents fail to decode, the error of
+    /// the first one in the file is returned, regardless of which thread finishes first.
+    pub fn load_parallel(&mut self) -> Result<Option<Vec<T>>, DatabaseError>
+    where
+        T: DeserializeOwned + Send,
+    {
+        let buf = read_file(&self.file, &self.layout)?;
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        binary_to_vec_parallel::<F, T>(&buf, &self.layout).map(Some)
+    }
 
-    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
-    struct Test {
-        id: usize,
-        message: String,
+    /// Returns a collection like `load()` as a boxed slice, which has no spare capacity,
+    /// for a collection that is loaded once and never grown.
+    ///
+    /// # Errors
+    ///
+    /// The same errors as `load()`.
+    pub fn load_boxed(&mut self) -> Result<Option<Box<[T]>>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(self.load()?.map(Vec::into_boxed_slice))
     }
 
-    fn generate_test_data() -> Vec<Test> {
-        let test1 = Test {
-            id: 1,
-            message: "Hello there, you suck".to_string(),
-        };
-        let test2 = Test {
-            id: 2,
-            message: "No you".to_string(),
-        };
-        let test3 = Test {
-            id: 3,
-            message: "You both suck".to_string(),
-        };
-        vec![test1, test2, test3]
+    /// Loads the collection like `load()` into `out`, which is cleared first, and returns
+    /// the number of documents loaded. Reusing the same vector across loads, e.g. to
+    /// reload a file whenever it changes, reuses its allocation.
+    ///
+    /// # Errors
+    ///
+    /// The same errors as `load()`. `out` is left empty when an error occurs.
+    pub fn load_into(&mut self, out: &mut Vec<T>) -> Result<usize, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        out.clear();
+        let buf = read_file(&self.file, &self.layout)?;
+        let result = decode_binary::<F, T, _>(&buf, &self.layout, true, |_, document| {
+            out.push(document);
+        });
+        if let Err(e) = result {
+            out.clear();
+            return Err(e);
+        }
+        Ok(out.len())
     }
 
-    #[test]
-    fn binary_vec_conversion() {
-        let test_messages = generate_test_data();
-        let binary = vec_to_binary(&test_messages).unwrap();
-        let vec: Vec<Test> = binary_to_vec(&binary).unwrap();
-        assert_eq!(test_messages, vec);
+    /// Returns a collection like `load()`, but without checking the checksum of any
+    /// document. This is faster, but corrupted data goes undetected: it is either
+    /// deserialized into a wrong value or rejected by the deserializer. Only use this
+    /// method if the file is trusted, e.g. because it was written by the same process.
+    ///
+    /// # Errors
+    ///
+    /// - `bincode::Error` if the deserializer fails to deserialize bytes from
+    ///   the file to your requested document type.
+    ///
+    /// - `DatabaseError::TruncatedFrame` if the last document is incomplete.
+    ///
+    /// - `std::io::Error`
+    pub fn load_unchecked(&mut self) -> Result<Option<Vec<T>>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(load_file::<F, _, _>(&self.file, &self.layout, false)?)
+    }
+
+    /// Recovers what it can from a damaged file. Returns the documents of every frame
+    /// that is intact, in order, along with the number of bytes that were skipped
+    /// because they do not belong to one.
+    ///
+    /// Wherever a frame is incomplete, fails its checksum or does not decode into `T`,
+    /// the scan moves on by a single byte and tries again, until it finds a frame that is
+    /// intact. This is expensive on badly damaged files, and it is a heuristic: a frame
+    /// that is written with another type is skipped, and garbage that happens to look
+    /// like an intact frame is returned as a document. Use `load()` for files that are
+    /// not known to be damaged.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn load_resync(&mut self) -> Result<(Vec<T>, u64), DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        let (documents, corrupt) = self.load_lenient()?;
+        Ok((documents, corrupt.iter().map(|region| region.len).sum()))
+    }
+
+    /// Recovers what it can from a damaged file like `load_resync()`, but returns every
+    /// region of the file that was skipped as a [`CorruptFrame`], with the checksums of
+    /// the frame that failed at its start, e.g. to report how much of the file was
+    /// salvaged and where it is damaged.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn load_lenient(&mut self) -> Result<(Vec<T>, Vec<CorruptFrame>), DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        let buf = read_file(&self.file, &self.layout)?;
+        Ok(resync_binary::<F, T>(
+            &buf,
+            self.layout.data_start,
+            &self.layout,
+        ))
+    }
+
+    /// Validates if the checksum of the documents in the collection matches its
+    /// corresponding stored checksum value. This is an efficient way to determine
+    /// if a file has possibly been corrupted. Returns `Ok(true)` if the file has
+    /// no checksum mismatches and `Ok(false)` if the opposite is true.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn validate(&mut self) -> Result<bool, DatabaseError> {
+        validate_file(&mut self.file, &self.layout)
+    }
+
+    /// Walks every frame in the file and checks its checksum without deserializing
+    /// anything, returning a [`VerifyReport`] that lists the offset as well as the saved
+    /// and expected checksums of every corrupted frame.
+    ///
+    /// Unlike `validate()`, the walk does not stop at the first corrupted frame. The
+    /// length field of a corrupted frame is trusted in order to skip to the next one,
+    /// and the walk stops if that length points past the end of the file, in which case
+    /// the file is reported as truncated.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn verify(&mut self) -> Result<VerifyReport, DatabaseError> {
+        verify_file(&mut self.file, &self.layout)
+    }
+
+    /// Checks that every document matches its checksum and deserializes into `T`,
+    /// returning the number of documents checked, e.g. to catch a file written with an
+    /// incompatible version of `T` at startup. Unlike `load()`, every document is dropped
+    /// as soon as it has been deserialized, so memory use stays bounded.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `bincode::Error` if a document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    pub fn validate_decodable(&mut self) -> Result<usize, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        let mut count = 0;
+        for document in self.iter() {
+            document?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Returns a human-readable summary of the file, e.g. to print for an `--inspect`
+    /// flag, such as `3 documents, 1.2 KiB, 60 bytes overhead, all checksums valid`. The
+    /// overhead is every byte that is not part of an encoded document, such as the
+    /// header and the frame headers. Nothing is deserialized.
+    ///
+    /// If `frames` is `true`, the summary is followed by one line per frame with its
+    /// offset, the size of its body, its saved checksum and whether it is intact.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn describe(&mut self, frames: bool) -> Result<String, DatabaseError> {
+        use std::fmt::Write as _;
+
+        let size = self.file.size()?;
+        let buf = read_file(&self.file, &self.layout)?;
+        let mut raw_data = buf.as_slice();
+        let mut lines = String::new();
+        let (mut documents, mut payload, mut corrupted, mut truncated) = (0, 0, 0, false);
+        while !raw_data.is_empty() {
+            let offset = self.layout.data_start + (buf.len() - raw_data.len()) as u64;
+            let Some(frame) = FrameRef::split(&mut raw_data, &self.layout) else {
+                truncated = true;
+                break;
+            };
+            let status = if !frame.is_complete() {
+                truncated = true;
+                "truncated".to_owned()
+            } else if let Err(DatabaseError::MismatchedChecksum { expected, .. }) =
+                frame.verify(&self.layout)
+            {
+                corrupted += 1;
+                format!("corrupted, expected {expected:08x}")
+            } else if frame.removed {
+                "removed".to_owned()
+            } else {
+                let data = &frame.data[self.layout.extras_len().min(frame.data.len())..];
+                for document in self.layout.split_documents(data).flatten() {
+                    documents += 1;
+                    payload += document.len() as u64;
+                }
+                "ok".to_owned()
+            };
+            if frames {
+                let _ = writeln!(
+                    lines,
+                    "{offset:>10}  {:>8} bytes  crc {:08x}  {status}",
+                    frame.data_len, frame.saved
+                );
+            }
+        }
+        let checksums = match (corrupted, truncated) {
+            (0, false) => "all checksums valid".to_owned(),
+            (0, true) => "all checksums valid, truncated".to_owned(),
+            (n, false) => format!("{n} corrupted frames"),
+            (n, true) => format!("{n} corrupted frames, truncated"),
+        };
+        Ok(format!(
+            "{documents} documents, {}, {} bytes overhead, {checksums}\n{lines}",
+            format_size(size),
+            size - payload
+        ))
+    }
+
+    /// Appends a footer with a checksum of every byte of every document, replacing the
+    /// previous footer if there is one. Unlike the checksum of every frame, which only
+    /// covers its own document, the footer detects a frame that was dropped, duplicated
+    /// or reordered as a whole, e.g. by a faulty copy. Check it with `verify_footer()`.
+    ///
+    /// The footer is skipped by every read, and removed by the next write or in-place
+    /// removal, since it no longer matches the file; call this again once the file is
+    /// complete. Rewriting the file, e.g. with `vacuum()`, drops it as well. Versions of
+    /// this crate without footers read a finalized file as a truncated last document.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn finalize(&mut self) -> Result<(), DatabaseError> {
+        strip_footer(&mut self.file, &self.layout)?;
+        let data = read_file(&self.file, &self.layout)?;
+        let mut footer = Vec::with_capacity(FOOTER_LEN);
+        footer.extend_from_slice(&FOOTER_MAGIC);
+        let endianness = self.layout.endianness;
+        endianness.write_u32(&mut footer, self.layout.checksum(&data))?;
+        endianness.write_u64(&mut footer, data.len() as u64)?;
+        let len = self.file.seek(SeekFrom::End(0))?;
+        append_or_rollback(&mut self.file, len, |file| Ok(file.write_all(&footer)?))?;
+        self.commit_overwrite()
+    }
+
+    /// Recomputes the checksum of every byte of every document and compares it with the
+    /// footer written by `finalize()`, returning `Ok(true)` if they match. If the size
+    /// of the documents no longer matches the footer, `Ok(false)` is returned without
+    /// reading them.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` if the file does not end with a footer.
+    ///
+    /// - `std::io::Error`
+    pub fn verify_footer(&mut self) -> Result<bool, DatabaseError> {
+        let Some(footer) = read_footer(&self.file, &self.layout)? else {
+            return Err(DatabaseError::Unsupported(
+                "file does not end with a footer",
+            ));
+        };
+        if !footer.matches(&self.layout) {
+            return Ok(false);
+        }
+        let data = read_file_until(&self.file, &self.layout, footer.offset)?;
+        Ok(self.layout.checksum(&data) == footer.checksum)
+    }
+
+    /// Returns a reader over the raw frames of every document currently in the file, e.g.
+    /// to copy them to another file with [`Client::append_raw_frames`]. The reader starts
+    /// at the first document and stops at what is currently the end of the file.
+    ///
+    /// The reader borrows the file handle of the client, so the client cannot be used
+    /// until the reader is dropped. The next call to any other method seeks back to the
+    /// position it needs.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn as_bytes_reader(&mut self) -> Result<std::io::Take<&mut B>, DatabaseError> {
+        let end = data_end(&self.file, &self.layout)?;
+        self.file.seek(SeekFrom::Start(self.layout.data_start))?;
+        Ok((&mut self.file).take(end.saturating_sub(self.layout.data_start)))
+    }
+
+    /// Returns the current position of the cursor of the file handle, without moving it.
+    /// Every write leaves the cursor at the end of the file, but other methods make no
+    /// promise about where they leave it, see [`Client`].
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn position(&mut self) -> Result<u64, DatabaseError> {
+        Ok(self.file.stream_position()?)
+    }
+
+    /// Returns the metadata of every frame in the file, including removed ones, by reading
+    /// only the frame headers. This is useful to build an external index of the file or
+    /// to inspect it.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn scan_frames(&mut self) -> Result<Vec<FrameMeta>, DatabaseError> {
+        scan_file(&mut self.file, &self.layout)
+    }
+
+    /// Returns the saved checksum of every document that has not been removed, in order,
+    /// by reading only the frame headers. Comparing them with the checksums of a copy of
+    /// the file tells which documents differ without transferring or decoding any of them.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn checksums(&mut self) -> Result<Vec<u32>, DatabaseError> {
+        Ok(scan_file(&mut self.file, &self.layout)?
+            .into_iter()
+            .filter(|frame| !frame.removed)
+            .map(|frame| frame.checksum)
+            .collect())
+    }
+
+    /// Returns an iterator over the encoded bytes of every document, without deserializing
+    /// them into `T`. The checksum of every document is checked before it is yielded.
+    /// This allows documents encoded with something other than `serde`, such as protobuf,
+    /// to be stored in the file; the type parameter of the client is then irrelevant.
+    ///
+    /// A document that fails its checksum is yielded as an error without stopping the
+    /// iterator, which stops after any other error. It reports how many bytes and frames
+    /// it has read so far, which can be used to show the progress of a scan over a large
+    /// file.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is yielded.
+    ///
+    /// - `std::io::Error`
+    pub fn raw_payloads(&mut self) -> RawPayloads<'_, B> {
+        RawPayloads::new(&mut self.file, self.layout)
+    }
+
+    /// Returns an iterator over every document in the file, in the order in which they
+    /// were inserted. Unlike `load()`, documents are read and deserialized one at a time,
+    /// so the whole collection never has to fit in memory.
+    ///
+    /// A document that fails its checksum or fails to deserialize is yielded as an error
+    /// and the iterator moves on to the next document, so the caller decides whether to
+    /// stop; any other error, such as an IO error, ends the iterator. Its `size_hint()` is
+    /// an estimate based on the documents read so far, not a guarantee.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is yielded.
+    ///
+    /// - `bincode::Error` if a document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    pub fn iter(&mut self) -> RecordIter<'_, T, F, B> {
+        RecordIter::new(&mut self.file, self.layout)
+    }
+
+    /// Returns a cursor over the frames of the file, starting at the first one, which can
+    /// skip frames without decoding them and move to a frame by its index, e.g. to page
+    /// through a file in an inspector. See [`Cursor`].
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` for files with blocks.
+    ///
+    /// - `std::io::Error`
+    pub fn cursor(&mut self) -> Result<Cursor<'_, T, F, B>, DatabaseError> {
+        Cursor::new(&mut self.file, self.layout)
+    }
+
+    /// Returns an iterator like `iter()` that pairs every document with its zero-based
+    /// index, which is the index that [`get_many`](Client::get_many) and
+    /// [`remove_at`](Client::remove_at) take. Removed documents are not counted.
+    ///
+    /// # Errors
+    ///
+    /// The same errors as `iter()`.
+    pub fn enumerate(&mut self) -> impl Iterator<Item = Result<(usize, T), DatabaseError>> + '_
+    where
+        T: DeserializeOwned,
+    {
+        self.iter()
+            .enumerate()
+            .map(|(index, document)| document.map(|document| (index, document)))
+    }
+
+    /// Returns an iterator over the documents in the file in chunks of `chunk_size`
+    /// documents, e.g. to process a large file in batches. The last chunk has fewer
+    /// documents if the number of documents is not a multiple of `chunk_size`. Only one
+    /// chunk is held in memory at a time.
+    ///
+    /// The iterator stops after the first error, discarding the documents already read
+    /// into the current chunk.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is yielded.
+    ///
+    /// - `bincode::Error` if a document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    pub fn load_chunks(
+        &mut self,
+        chunk_size: usize,
+    ) -> impl Iterator<Item = Result<Vec<T>, DatabaseError>> + '_
+    where
+        T: DeserializeOwned,
+    {
+        assert!(chunk_size != 0, "chunk size must be non-zero");
+        let mut documents = self.iter();
+        std::iter::from_fn(move || {
+            let mut chunk = Vec::with_capacity(chunk_size);
+            for document in documents.by_ref() {
+                match document {
+                    Ok(document) => chunk.push(document),
+                    Err(e) => return Some(Err(e)),
+                }
+                if chunk.len() == chunk_size {
+                    break;
+                }
+            }
+            (!chunk.is_empty()).then_some(Ok(chunk))
+        })
+    }
+
+    /// Calls `f` on every document in the file, in the order in which they were
+    /// inserted, until it returns `ControlFlow::Break`. Documents are read one at a time
+    /// like with `iter()`, so memory use stays bounded.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `bincode::Error` if a document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    pub fn for_each<G>(&mut self, mut f: G) -> Result<(), DatabaseError>
+    where
+        T: DeserializeOwned,
+        G: FnMut(T) -> ControlFlow<()>,
+    {
+        for document in self.iter() {
+            if f(document?).is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the documents matching `predicate` into a new file at `dest` with the same
+    /// layout, returning the number of documents copied, e.g. to split a collection into
+    /// several files. Documents are read one at a time and their frames are copied as is,
+    /// keeping their timestamps. An existing file at `dest` is overwritten, and this file
+    /// is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` if `dest` is this file.
+    ///
+    /// - `DatabaseError::Open` if `dest` cannot be created.
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned. The documents copied so far are left in `dest`.
+    ///
+    /// - `bincode::Error` if a document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    pub fn filter_into<P, Q>(&mut self, dest: Q, mut predicate: P) -> Result<usize, DatabaseError>
+    where
+        T: DeserializeOwned,
+        P: FnMut(&T) -> bool,
+        Q: AsRef<Path>,
+    {
+        self.layout.require_frame_per_document()?;
+        let dest = dest.as_ref();
+        if !self.path.as_os_str().is_empty()
+            && std::fs::canonicalize(dest).ok() == Some(std::fs::canonicalize(&self.path)?)
+        {
+            return Err(DatabaseError::Unsupported(
+                "cannot filter a file into itself",
+            ));
+        }
+        let mut layout = self.layout;
+        let mut writer = BufWriter::new(File::create(dest).map_err(open_failed(dest))?);
+        if layout.needs_header() {
+            writer.write_all(&layout.encode_header())?;
+        }
+        let header_len = layout.frame_header_len() as usize;
+        let mut scratch = Vec::new();
+        let mut count = 0;
+        let start = self.layout.data_start;
+        let end = data_end(&self.file, &self.layout)?;
+        let mut reader = BufReader::new(
+            ReadAt {
+                file: &self.file,
+                offset: start,
+            }
+            .take(end - start),
+        );
+        loop {
+            let frame = match read_frame(&mut reader, &layout) {
+                Ok(frame) => frame,
+                Err(DatabaseError::Io(e)) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            };
+            if frame.removed {
+                continue;
+            }
+            let body = frame.verify(&layout)?;
+            let (extras, data) = layout.split_body(&body)?;
+            let data = layout.frame_data(extras, data)?;
+            if predicate(&layout.decode::<F, T>(&data)?) {
+                scratch.clear();
+                scratch.resize(header_len, 0);
+                scratch.extend_from_slice(&body);
+                finish_frame(&mut scratch, &layout)?;
+                writer.write_all(&scratch)?;
+                count += 1;
+            }
+        }
+        writer.flush()?;
+        Ok(count)
+    }
+
+    /// Returns the zero-based indices of the documents matching `predicate`, e.g. to pass
+    /// them to [`get_many`](Client::get_many) or [`remove_at`](Client::remove_at) later.
+    /// Documents are read one at a time, so only the indices are held in memory. Removed
+    /// documents are not counted.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `bincode::Error` if a document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    pub fn positions<P>(&mut self, mut predicate: P) -> Result<Vec<usize>, DatabaseError>
+    where
+        T: DeserializeOwned,
+        P: FnMut(&T) -> bool,
+    {
+        let mut positions = Vec::new();
+        for document in self.enumerate() {
+            let (index, document) = document?;
+            if predicate(&document) {
+                positions.push(index);
+            }
+        }
+        Ok(positions)
+    }
+
+    /// Returns the fraction of documents that have been superseded, i.e. that are not the
+    /// last document with their key, e.g. to decide when to compact a file that full
+    /// snapshots are appended to. Documents are read one at a time, so only their keys
+    /// are held in memory. Returns 0 if the file is empty.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `bincode::Error` if a document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    pub fn dead_ratio<K, G>(&mut self, key: G) -> Result<f64, DatabaseError>
+    where
+        T: DeserializeOwned,
+        K: Eq + Hash,
+        G: Fn(&T) -> K,
+    {
+        let mut keys = HashSet::new();
+        let mut total = 0usize;
+        for document in self.iter() {
+            keys.insert(key(&document?));
+            total += 1;
+        }
+        if total == 0 {
+            return Ok(0.0);
+        }
+        Ok((total - keys.len()) as f64 / total as f64)
+    }
+
+    /// Returns the number of documents in the file without deserializing them. Unless the
+    /// file has blocks, only the frame headers are read, seeking past every payload.
+    /// Removed documents are not counted.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of a
+    ///   document.
+    pub fn len(&mut self) -> Result<usize, DatabaseError> {
+        count_documents(&mut self.file, &self.layout)
+    }
+
+    /// Returns `true` if the file holds no documents. Like [`len`](Client::len), but stops
+    /// at the first document that has not been removed.
+    ///
+    /// # Errors
+    ///
+    /// See [`len`](Client::len).
+    pub fn is_empty(&mut self) -> Result<bool, DatabaseError> {
+        if self.layout.block_size.is_some() {
+            return Ok(self.len()? == 0);
+        }
+        let mut empty = true;
+        walk_frames(&self.file, &self.layout, |frame| {
+            empty = frame.removed;
+            if empty {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        })?;
+        Ok(empty)
+    }
+
+    /// Returns the number of documents matching `predicate`. Documents are read one at a
+    /// time and dropped once tested, so nothing is collected. Removed documents are not
+    /// counted.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `bincode::Error` if a document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    pub fn count_where<P>(&mut self, mut predicate: P) -> Result<usize, DatabaseError>
+    where
+        T: DeserializeOwned,
+        P: FnMut(&T) -> bool,
+    {
+        let mut count = 0;
+        for document in self.iter() {
+            if predicate(&document?) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Returns the documents matching `predicate`, in order. Documents are read one at a
+    /// time and dropped unless they match, so only the matching documents are held in
+    /// memory, whereas filtering the result of `load()` holds every document at once.
+    /// Every document is still checked against its checksum.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `bincode::Error` if a document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    pub fn load_filter<P>(&mut self, mut predicate: P) -> Result<Vec<T>, DatabaseError>
+    where
+        T: DeserializeOwned,
+        P: FnMut(&T) -> bool,
+    {
+        let mut documents = Vec::new();
+        for document in self.iter() {
+            let document = document?;
+            if predicate(&document) {
+                documents.push(document);
+            }
+        }
+        Ok(documents)
+    }
+
+    /// Checks whether the file can be read as a collection of `T` by checking and
+    /// deserializing only its first document. Returns `Ok(true)` if that succeeds or if
+    /// the file is empty, and `Ok(false)` if the first document is corrupted, incomplete
+    /// or not a `T`. This is a cheap way to catch a file of the wrong type at startup.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn probe(&mut self) -> Result<bool, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        match self.iter().next() {
+            None | Some(Ok(_)) => Ok(true),
+            Some(Err(e)) => match e.kind() {
+                DatabaseErrorKind::Serde
+                | DatabaseErrorKind::Checksum
+                | DatabaseErrorKind::Truncated => Ok(false),
+                _ => Err(e),
+            },
+        }
+    }
+
+    /// Returns a collection like `load()`, with every document paired with the time at
+    /// which it was written, in milliseconds since the Unix epoch.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` if the file was not created with
+    ///   [`ClientBuilder::timestamps`] enabled.
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::CorruptedFrame` error is
+    ///   returned.
+    ///
+    /// - `bincode::Error` if a document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    pub fn load_with_timestamps(&mut self) -> Result<Option<Vec<(u64, T)>>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        if !self.layout.timestamps {
+            return Err(DatabaseError::Unsupported("file does not store timestamps"));
+        }
+        let buf = read_file(&self.file, &self.layout)?;
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        let mut result = Vec::new();
+        decode_binary::<F, T, _>(&buf, &self.layout, true, |extras, document| {
+            result.push((self.layout.timestamp(extras).unwrap_or_default(), document));
+        })?;
+        Ok(Some(result))
+    }
+
+    /// Returns a collection like `load()`, but decodes every document with `decode`, which
+    /// is passed the schema version the document was written with and its encoded bytes.
+    /// This lets documents written with an older version of `T` be decoded explicitly,
+    /// e.g. by deserializing them into the old type and converting them, since formats
+    /// like bincode cannot skip fields that a document does not have.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` if the file was not created with
+    ///   [`ClientBuilder::frame_version`] set.
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - Any error returned by `decode`.
+    ///
+    /// - `std::io::Error`
+    pub fn load_versioned<G>(&mut self, mut decode: G) -> Result<Option<Vec<T>>, DatabaseError>
+    where
+        G: FnMut(u8, &[u8]) -> Result<T, DatabaseError>,
+    {
+        if !self.layout.versions {
+            return Err(DatabaseError::Unsupported(
+                "file does not store schema versions",
+            ));
+        }
+        let buf = read_file(&self.file, &self.layout)?;
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        let mut result = Vec::new();
+        let mut raw_data = buf.as_slice();
+        let mut index = 0;
+        while let Some(frame) = FrameRef::split(&mut raw_data, &self.layout) {
+            index += 1;
+            if frame.removed {
+                continue;
+            }
+            let body = frame
+                .verify(&self.layout)
+                .map_err(in_block(&self.layout, index - 1))?;
+            let (extras, data) = self.layout.split_body(body)?;
+            let version = self.layout.version(extras).unwrap_or_default();
+            let data = self.layout.frame_data(extras, data)?;
+            for document in self.layout.split_documents(&data) {
+                result.push(decode(version, document?)?);
+            }
+        }
+        Ok(Some(result))
+    }
+
+    /// Returns every document paired with the type tag it was written with, in the order
+    /// in which they were written, or an empty collection if the file is empty. See
+    /// [`write_tagged`](Client::write_tagged).
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` if the file was not created with
+    ///   [`ClientBuilder::frame_tags`] enabled.
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::CorruptedFrame` error is
+    ///   returned.
+    ///
+    /// - `bincode::Error` if a document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    pub fn load_tagged(&mut self) -> Result<Vec<(u16, T)>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        if !self.layout.tags {
+            return Err(DatabaseError::Unsupported("file does not store type tags"));
+        }
+        let buf = read_file(&self.file, &self.layout)?;
+        let mut result = Vec::new();
+        decode_binary::<F, T, _>(&buf, &self.layout, true, |extras, document| {
+            result.push((self.layout.frame_tag(extras).unwrap_or_default(), document));
+        })?;
+        Ok(result)
+    }
+
+    /// Returns every document written at or after `since`, in milliseconds since the Unix
+    /// epoch, paired with the time at which it was written. Only the headers of older
+    /// documents are read.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` if the file was not created with
+    ///   [`ClientBuilder::timestamps`] enabled.
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `bincode::Error` if a document fails to deserialize.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn tail_since(&mut self, since: u64) -> Result<Vec<(u64, T)>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        if !self.layout.timestamps {
+            return Err(DatabaseError::Unsupported("file does not store timestamps"));
+        }
+        let mut result = Vec::new();
+        for offset in frame_offsets(&mut self.file, &self.layout)? {
+            self.file
+                .seek(SeekFrom::Start(offset + self.layout.frame_header_len()))?;
+            let timestamp = self.layout.endianness.read_u64(&mut self.file)?;
+            if timestamp >= since {
+                let document = read_document_at::<F, _, _>(&self.file, &self.layout, offset)?;
+                result.push((timestamp, document));
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns the document `n` positions before the last one, where `n == 0` is the
+    /// last document. Returns `Ok(None)` if the collection has `n` documents or fewer.
+    ///
+    /// Only the headers of the other documents are read, so this is cheaper than loading
+    /// the whole collection.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `bincode::Error` if the document fails to deserialize.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn nth_last(&mut self, n: usize) -> Result<Option<T>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        let offsets = frame_offsets(&mut self.file, &self.layout)?;
+        if n >= offsets.len() {
+            return Ok(None);
+        }
+        let offset = offsets[offsets.len() - 1 - n];
+        let document = read_document_at::<F, _, _>(&self.file, &self.layout, offset)?;
+        Ok(Some(document))
+    }
+
+    /// Removes the last document and returns it, or `Ok(None)` if the collection is empty,
+    /// e.g. to use the file as a stack. The file is truncated in place to the start of
+    /// the frame of the document, dropping any removed documents after it.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned and nothing is removed.
+    ///
+    /// - `bincode::Error` if the document fails to deserialize.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn pop(&mut self) -> Result<Option<T>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        let offsets = frame_offsets(&mut self.file, &self.layout)?;
+        let Some(&offset) = offsets.last() else {
+            return Ok(None);
+        };
+        let document = read_document_at::<F, _, _>(&self.file, &self.layout, offset)?;
+        strip_footer(&mut self.file, &self.layout)?;
+        self.unique = None;
+        self.appended = None;
+        self.file.set_len(offset)?;
+        self.commit_overwrite()?;
+        Ok(Some(document))
+    }
+
+    /// Returns the last document, or `Ok(None)` if the collection is empty, for files in
+    /// which every document is a snapshot of the whole state of an application, appended
+    /// whenever it changes, so that only the newest one matters. See
+    /// [`nth_last`](Client::nth_last).
+    ///
+    /// # Errors
+    ///
+    /// See [`nth_last`](Client::nth_last).
+    pub fn load_latest_snapshot(&mut self) -> Result<Option<T>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        self.nth_last(0)
+    }
+
+    /// Removes every document but the last one, returning the number of documents
+    /// removed, e.g. to shrink a file of snapshots read with
+    /// [`load_latest_snapshot`](Client::load_latest_snapshot). See
+    /// [`trim_prefix`](Client::trim_prefix).
+    ///
+    /// # Errors
+    ///
+    /// See [`trim_prefix`](Client::trim_prefix).
+    pub fn compact_to_latest(&mut self) -> Result<usize, DatabaseError> {
+        let len = frame_offsets(&mut self.file, &self.layout)?.len();
+        self.trim_prefix(len.saturating_sub(1))
+    }
+
+    /// Returns the document at `index`, or `Ok(None)` if the collection has `index`
+    /// documents or fewer. Removed documents are not counted.
+    ///
+    /// Only the frame headers are read up to the document, seeking past every payload,
+    /// and only the document itself is read and deserialized. To read several documents,
+    /// [`get_many`](Client::get_many) walks the frame headers only once.
+    ///
+    /// Like [`load`](Client::load), this method reads the file with positioned reads
+    /// instead of moving its cursor, so a client shared between threads can serve both
+    /// at once without a lock.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `bincode::Error` if the document fails to deserialize.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document before the requested one.
+    pub fn get(&self, index: usize) -> Result<Option<T>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        self.layout.require_frame_per_document()?;
+        let mut remaining = index;
+        let mut found = None;
+        walk_frames(&self.file, &self.layout, |frame| {
+            if frame.removed {
+                ControlFlow::Continue(())
+            } else if remaining == 0 {
+                found = Some(frame.offset);
+                ControlFlow::Break(())
+            } else {
+                remaining -= 1;
+                ControlFlow::Continue(())
+            }
+        })?;
+        let Some(offset) = found else {
+            return Ok(None);
+        };
+        read_document_at::<F, _, _>(&self.file, &self.layout, offset).map(Some)
+    }
+
+    /// Returns the documents at the given `indices`, in the same order as `indices`, with
+    /// `None` for indices past the end of the collection. Removed documents are not
+    /// counted.
+    ///
+    /// The frame headers are walked once, and only the requested documents are read
+    /// and deserialized, in the order they appear in the file. This is much cheaper than
+    /// reading the documents one by one when a few of them are needed out of many.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `bincode::Error` if a requested document fails to deserialize.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn get_many(&mut self, indices: &[usize]) -> Result<Vec<Option<T>>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        let offsets = frame_offsets(&mut self.file, &self.layout)?;
+        let mut order: Vec<usize> = (0..indices.len()).collect();
+        order.sort_unstable_by_key(|&i| indices[i]);
+        let mut documents: Vec<Option<T>> = std::iter::repeat_with(|| None)
+            .take(indices.len())
+            .collect();
+        for i in order {
+            let Some(&offset) = offsets.get(indices[i]) else {
+                break;
+            };
+            documents[i] = Some(read_document_at::<F, _, _>(
+                &self.file,
+                &self.layout,
+                offset,
+            )?);
+        }
+        Ok(documents)
+    }
+
+    /// Returns up to `len` documents from the one at `start` on, e.g. to show a page of
+    /// the collection, or an empty collection if it has `start` documents or fewer.
+    /// Removed documents are not counted.
+    ///
+    /// Like [`get`](Client::get), only the frame headers are read up to the first
+    /// document, and only the returned documents are read and deserialized.
+    ///
+    /// # Errors
+    ///
+    /// See [`get`](Client::get).
+    pub fn load_range(&self, start: usize, len: usize) -> Result<Vec<T>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        self.layout.require_frame_per_document()?;
+        let mut skipped = 0;
+        let mut offsets = Vec::new();
+        if len > 0 {
+            walk_frames(&self.file, &self.layout, |frame| {
+                if frame.removed {
+                    ControlFlow::Continue(())
+                } else if skipped < start {
+                    skipped += 1;
+                    ControlFlow::Continue(())
+                } else {
+                    offsets.push(frame.offset);
+                    if offsets.len() == len {
+                        ControlFlow::Break(())
+                    } else {
+                        ControlFlow::Continue(())
+                    }
+                }
+            })?;
+        }
+        offsets
+            .into_iter()
+            .map(|offset| read_document_at::<F, _, _>(&self.file, &self.layout, offset))
+            .collect()
+    }
+
+    /// Returns the first document, or `Ok(None)` if the collection is empty. Removed
+    /// documents are skipped. See [`get`](Client::get).
+    ///
+    /// # Errors
+    ///
+    /// See [`get`](Client::get).
+    pub fn first(&self) -> Result<Option<T>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        self.get(0)
+    }
+
+    /// Returns the last document, e.g. the latest state in a file of snapshots, or
+    /// `Ok(None)` if the collection is empty. Removed documents are skipped.
+    ///
+    /// Every frame header is read to find the last frame, seeking past every payload,
+    /// and only the last document itself is read and deserialized.
+    ///
+    /// # Errors
+    ///
+    /// See [`get`](Client::get).
+    pub fn last(&self) -> Result<Option<T>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        self.layout.require_frame_per_document()?;
+        let mut found = None;
+        walk_frames(&self.file, &self.layout, |frame| {
+            if !frame.removed {
+                found = Some(frame.offset);
+            }
+            ControlFlow::Continue(())
+        })?;
+        let Some(offset) = found else {
+            return Ok(None);
+        };
+        read_document_at::<F, _, _>(&self.file, &self.layout, offset).map(Some)
+    }
+
+    /// Writes the provided serializable documents to disk. If no file is found,
+    /// a new file will be created and written to. Returns how many bytes were written
+    /// and whether this was the first write to the file.
+    ///
+    /// Writing no documents reports 0 bytes written and writes no empty frame. It leaves
+    /// the file untouched, including its footer, except in overwrite mode, where the old
+    /// documents are replaced by none like with any other write. To empty a file that
+    /// holds documents in any mode, use [`replace_all`](Client::replace_all) with no
+    /// documents.
+    ///
+    /// # Errors
+    ///
+    /// - `std::num::TryFromIntError` occurs when a document you are inserting
+    ///   takes up more space than `u32::MAX` bytes.
+    ///
+    /// - `std::io::Error`
+    ///
+    /// - `bincode::Error` when the documents provided fails to serialize for some reason.
+    pub fn write_many(&mut self, documents: &[T]) -> Result<WriteReport, DatabaseError>
+    where
+        T: Serialize,
+    {
+        if documents.is_empty() {
+            self.layout.require_whole_file()?;
+            self.commit_overwrite()?;
+            return Ok(WriteReport {
+                bytes: 0,
+                created: false,
+            });
+        }
+        self.append_documents(documents)
+    }
+
+    /// Appends every document yielded by `documents`, which may yield documents or
+    /// references to them, returning the number of documents written. Like
+    /// `write_many()`, the documents are encoded one at a time into a single scratch
+    /// buffer and streamed to the file through a write buffer, so they never have to be
+    /// collected first.
+    ///
+    /// # Errors
+    ///
+    /// See [`write_many`](Client::write_many). If anything fails, the file is truncated
+    /// back to its previous length, so none of the documents are written.
+    pub fn write_iter<I>(&mut self, documents: I) -> Result<usize, DatabaseError>
+    where
+        T: Serialize,
+        I: IntoIterator,
+        I::Item: Borrow<T>,
+    {
+        let mut written = 0;
+        self.append_documents(documents.into_iter().inspect(|_| written += 1))?;
+        Ok(written)
+    }
+
+    /// Appends every document yielded by `documents` until it yields an error, returning
+    /// the number of documents written. This streams the documents to the file like
+    /// `write_many()`, without collecting them first.
+    ///
+    /// # Errors
+    ///
+    /// - `MixedError::Source` with the error yielded by `documents` and the number of
+    ///   documents written before it. Those documents stay in the file.
+    ///
+    /// - `MixedError::Database` if a document fails to serialize or writing fails, in
+    ///   which case the file is truncated back to its previous length.
+    pub fn append_try_iter<E, I>(&mut self, documents: I) -> Result<usize, MixedError<E>>
+    where
+        T: Serialize,
+        I: IntoIterator<Item = Result<T, E>>,
+    {
+        let mut written = 0;
+        let mut failure = None;
+        let documents = documents.into_iter().map_while(|document| match document {
+            Ok(document) => {
+                written += 1;
+                Some(document)
+            }
+            Err(error) => {
+                failure = Some(error);
+                None
+            }
+        });
+        self.append_documents(documents)?;
+        match failure {
+            Some(error) => Err(MixedError::Source { written, error }),
+            None => Ok(written),
+        }
+    }
+
+    /// Returns the bytes that `write_many()` would append to the file for `documents`,
+    /// without writing anything. The header of the file, if any, is not included.
+    ///
+    /// # Errors
+    ///
+    /// - `std::num::TryFromIntError` occurs when a document takes up more space than
+    ///   `u32::MAX` bytes.
+    ///
+    /// - `bincode::Error` when a document fails to serialize for some reason.
+    pub fn encode_many(&self, documents: &[T]) -> Result<Vec<u8>, DatabaseError>
+    where
+        T: Serialize,
+    {
+        vec_to_binary::<F, _>(documents, &self.layout)
+    }
+
+    /// Writes the provided serializable document to disk. If no file is found,
+    /// a new file will be created and written to. If the write fails halfway, the file
+    /// is truncated back to its previous length. See `write_many()`.
+    ///
+    /// # Errors
+    ///
+    /// - `std::num::TryFromIntError` occurs when a document you are inserting
+    ///   takes up more space than `u32::MAX` bytes.
+    ///
+    /// - `std::io::Error`
+    ///
+    /// - `bincode::Error` when the document provided fails to serialize for some reason.
+    pub fn write(&mut self, document: &T) -> Result<WriteReport, DatabaseError>
+    where
+        T: Serialize,
+    {
+        let len = strip_footer(&mut self.file, &self.layout)?;
+        let (layout, scratch) = (&self.layout, &mut self.scratch);
+        append_frames(&mut self.file, layout, len, |file| {
+            write_encoded::<F, _, T, _>(file, std::array::from_ref(document), layout, scratch)
+        })?;
+        self.commit_overwrite()?;
+        self.write_report(len)
+    }
+
+    /// Appends a document like [`write`](Client::write), compressing it first, e.g. for
+    /// large text documents in a file that also holds documents that are already
+    /// compressed, which are better written with `write`. Only the documents written
+    /// with this method are compressed, and each one is decompressed when it is read.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` if the file was not created with
+    ///   [`ClientBuilder::frame_compression`], or without the `gzip` feature.
+    ///
+    /// - See [`write`](Client::write).
+    pub fn write_compressed(&mut self, document: &T) -> Result<WriteReport, DatabaseError>
+    where
+        T: Serialize,
+    {
+        if !self.layout.compression {
+            return Err(DatabaseError::Unsupported(
+                "file does not store per-document compression",
+            ));
+        }
+        let len = strip_footer(&mut self.file, &self.layout)?;
+        let layout = Layout {
+            compress: true,
+            ..self.layout
+        };
+        let scratch = &mut self.scratch;
+        append_frames(&mut self.file, &layout, len, |file| {
+            write_encoded::<F, _, T, _>(file, std::array::from_ref(document), &layout, scratch)
+        })?;
+        self.commit_overwrite()?;
+        self.write_report(len)
+    }
+
+    /// Appends a document like [`write`](Client::write), storing `tag` with it, e.g. to
+    /// record which kind of event it is. The tag is covered by the checksum of the
+    /// document and can be read back with [`load_tagged`](Client::load_tagged).
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` if the file was not created with
+    ///   [`ClientBuilder::frame_tags`] enabled.
+    ///
+    /// - See [`write`](Client::write).
+    pub fn write_tagged(&mut self, tag: u16, document: &T) -> Result<WriteReport, DatabaseError>
+    where
+        T: Serialize,
+    {
+        if !self.layout.tags {
+            return Err(DatabaseError::Unsupported("file does not store type tags"));
+        }
+        let len = strip_footer(&mut self.file, &self.layout)?;
+        let layout = Layout { tag, ..self.layout };
+        let scratch = &mut self.scratch;
+        append_frames(&mut self.file, &layout, len, |file| {
+            write_encoded::<F, _, T, _>(file, std::array::from_ref(document), &layout, scratch)
+        })?;
+        self.commit_overwrite()?;
+        self.write_report(len)
+    }
+
+    /// Appends `document` unless a document with the same encoded bytes is already in the
+    /// file, returning whether it was appended, e.g. to make appends to an event log
+    /// idempotent. Removed documents are ignored.
+    ///
+    /// The checksums of every document are collected on the first call and kept in
+    /// memory, so that later calls only compare the checksum of the new document against
+    /// them. The file is only read again to compare the bytes of the documents whose
+    /// checksum matches, or if the file was changed in any other way than through this
+    /// method, e.g. by another client.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned and nothing is written.
+    ///
+    /// - `std::num::TryFromIntError` occurs when the document takes up more space than
+    ///   `u32::MAX` bytes.
+    ///
+    /// - `bincode::Error` when the document fails to serialize for some reason.
+    ///
+    /// - `std::io::Error`
+    pub fn append_unique(&mut self, document: &T) -> Result<bool, DatabaseError>
+    where
+        T: Serialize,
+    {
+        let mut encoded = Vec::new();
+        F::serialize_into(&mut encoded, document)?;
+        let checksum = self.layout.checksum(&encoded);
+        let len = self.file.size()?;
+        let mut checksums = match self.unique.take() {
+            Some((cached, checksums)) if cached == len => checksums,
+            _ => {
+                let mut checksums = HashSet::new();
+                let _ = for_each_encoded(&mut self.file, &self.layout, |document| {
+                    checksums.insert(self.layout.checksum(document));
+                    ControlFlow::Continue(())
+                })?;
+                checksums
+            }
+        };
+        let duplicate = checksums.contains(&checksum)
+            && for_each_encoded(&mut self.file, &self.layout, |document| {
+                if document == encoded.as_slice() {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })?
+            .is_break();
+        if !duplicate {
+            self.write(document)?;
+            checksums.insert(checksum);
+        }
+        self.unique = Some((self.file.size()?, checksums));
+        Ok(!duplicate)
+    }
+
+    /// Appends a document like [`write`](Client::write) and returns its index, e.g. to
+    /// read it back later with [`get`](Client::get). Removed documents are not
+    /// counted.
+    ///
+    /// The documents are counted on the first call, from the frame headers, and the count
+    /// is kept in memory, so that later calls do not read the file. It is only counted
+    /// again if the file was changed in any other way than through this method, e.g. by
+    /// another client.
+    ///
+    /// # Errors
+    ///
+    /// See [`write`](Client::write) and [`len`](Client::len).
+    pub fn append(&mut self, document: &T) -> Result<u64, DatabaseError>
+    where
+        T: Serialize,
+    {
+        let len = self.file.size()?;
+        let index = match self.appended.take() {
+            Some((cached, count)) if cached == len => count,
+            _ => self.len()?,
+        };
+        self.write(document)?;
+        self.appended = Some((self.file.size()?, index + 1));
+        Ok(index as u64)
+    }
+
+    /// Allocates disk space for `additional_bytes` more bytes after the end of the file
+    /// ahead of a burst of writes, so that the file system does not have to grow the
+    /// file piece by piece. The length of the file does not change, so readers never see
+    /// the reserved space.
+    ///
+    /// This is a hint: on Linux, the space is allocated with `fallocate`, if the file
+    /// system supports it. On other platforms and for a [`Backend`] other than a file,
+    /// this does nothing.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn reserve(&mut self, additional_bytes: u64) -> Result<(), DatabaseError> {
+        let len = self.file.size()?;
+        match self.file.as_file() {
+            Some(file) => allocate(file, len, additional_bytes),
+            None => Ok(()),
+        }
+    }
+
+    /// Describes a write that started at offset `len`, the previous length of the file.
+    fn write_report(&mut self, len: u64) -> Result<WriteReport, DatabaseError> {
+        let bytes = self.file.stream_position()? - len;
+        Ok(WriteReport {
+            bytes,
+            created: bytes > 0 && len == self.layout.data_start,
+        })
+    }
+
+    /// Appends already framed bytes, such as the contents of another file written by
+    /// this crate, to the end of the file without deserializing and reserializing them.
+    /// If `validate` is `true`, the checksum of every frame is checked before anything
+    /// is written, otherwise the bytes are written as is.
+    ///
+    /// # Errors
+    ///
+    /// - If `validate` is `true` and a checksum mismatch occurs, a
+    ///   `DatabaseError::MismatchedChecksum` or `DatabaseError::MismatchedChecksum64` error
+    ///   is returned and nothing is written.
+    ///
+    /// - If `validate` is `true` and the last frame is incomplete, a `std::io::Error` of
+    ///   kind `UnexpectedEof` is returned and nothing is written.
+    ///
+    /// - `std::io::Error`
+    pub fn append_raw_frames(
+        &mut self,
+        frames: &[u8],
+        validate: bool,
+    ) -> Result<(), DatabaseError> {
+        if validate {
+            let report = verify_binary(frames, 0, &self.layout);
+            if let Some(&(_, saved, expected)) = report.mismatches.first() {
+                return Err(DatabaseError::MismatchedChecksum { saved, expected });
+            }
+            if let Some(&(_, saved, expected)) = report.mismatches64.first() {
+                return Err(DatabaseError::MismatchedChecksum64 { saved, expected });
+            }
+            if report.truncated {
+                return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+            }
+        }
+        let len = strip_footer(&mut self.file, &self.layout)?;
+        append_frames(&mut self.file, &self.layout, len, |file| {
+            Ok(file.write_all(frames)?)
+        })?;
+        self.commit_overwrite()
+    }
+
+    /// Streams the documents to the end of the file one at a time, so that only a single
+    /// serialized document is held in memory on top of the write buffer. If anything
+    /// fails, the file is truncated back to its previous length.
+    fn append_documents<I>(&mut self, documents: I) -> Result<WriteReport, DatabaseError>
+    where
+        T: Serialize,
+        I: IntoIterator,
+        I::Item: Borrow<T>,
+    {
+        let len = strip_footer(&mut self.file, &self.layout)?;
+        let (layout, scratch) = (&self.layout, &mut self.scratch);
+        append_frames(&mut self.file, layout, len, |file| {
+            let mut writer = BufWriter::new(file);
+            write_encoded::<F, _, T, _>(&mut writer, documents, layout, scratch)?;
+            Ok(writer.flush()?)
+        })?;
+        self.commit_overwrite()?;
+        self.write_report(len)
+    }
+
+    /// Removes the document at `index`, returning `false` if the collection has `index`
+    /// documents or fewer.
+    ///
+    /// The document is only marked as removed by overwriting a single byte in place, so
+    /// this is cheap regardless of the size of the file. Removed documents are skipped by
+    /// every read, and the space they take up is reclaimed by `vacuum()`.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` if the file was not created with
+    ///   [`ClientBuilder::tombstones`] enabled.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn remove_at(&mut self, index: usize) -> Result<bool, DatabaseError> {
+        self.require_tombstones()?;
+        let offsets = frame_offsets(&mut self.file, &self.layout)?;
+        let Some(&offset) = offsets.get(index) else {
+            return Ok(false);
+        };
+        self.mark_removed(&[offset])?;
+        Ok(true)
+    }
+
+    /// Removes the document at `index` and returns it, or `Ok(None)` if the collection has
+    /// `index` documents or fewer. Removed documents are not counted.
+    ///
+    /// In a file with [`ClientBuilder::tombstones`], the document is marked as removed in
+    /// place like in `remove_at()`, and the space it takes up is reclaimed by `vacuum()`.
+    /// Otherwise, the other documents are copied without being decoded into a new file
+    /// which replaces the original the same way as in `vacuum()`, which is expensive for
+    /// repeated removals from a large file.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned and nothing is removed.
+    ///
+    /// - `bincode::Error` if the document fails to deserialize.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn remove(&mut self, index: usize) -> Result<Option<T>, DatabaseError>
+    where
+        T: DeserializeOwned,
+    {
+        self.layout.require_frame_per_document()?;
+        let Some(frame) = scan_file(&mut self.file, &self.layout)?
+            .into_iter()
+            .filter(|frame| !frame.removed)
+            .nth(index)
+        else {
+            return Ok(None);
+        };
+        let document = read_document_at::<F, _, _>(&self.file, &self.layout, frame.offset)?;
+        if self.layout.tombstones {
+            self.mark_removed(&[frame.offset])?;
+        } else {
+            let mut data = read_file(&self.file, &self.layout)?;
+            let start = (frame.offset - self.layout.data_start) as usize;
+            data.drain(start..start + self.layout.frame_len(frame.len) as usize);
+            self.replace_data(&data)?;
+        }
+        Ok(Some(document))
+    }
+
+    /// Recomputes the checksum of the document at `index` from its contents and saves it
+    /// in place, e.g. after the document was edited by another tool that left the saved
+    /// checksum stale. Only the four bytes of the checksum are written. Removed documents
+    /// are not counted.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::IndexOutOfRange` if the collection has `index` documents or
+    ///   fewer.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn refresh_checksum_at(&mut self, index: usize) -> Result<(), DatabaseError> {
+        let offsets = frame_offsets(&mut self.file, &self.layout)?;
+        let Some(&offset) = offsets.get(index) else {
+            return Err(DatabaseError::IndexOutOfRange {
+                index,
+                len: offsets.len(),
+            });
+        };
+        self.file.seek(SeekFrom::Start(offset))?;
+        let frame = read_frame(&mut self.file, &self.layout)?;
+        let mut checksum = Vec::with_capacity(4);
+        self.layout
+            .endianness
+            .write_u32(&mut checksum, self.layout.checksum(&frame.data))?;
+        self.write_in_place(|file| {
+            file.seek(SeekFrom::Start(offset))?;
+            Ok(file.write_all(&checksum)?)
+        })
+    }
+
+    /// Removes every document for which `predicate` returns `true`, returning the number
+    /// of documents removed. See `remove_at()`.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` if the file was not created with
+    ///   [`ClientBuilder::tombstones`] enabled.
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned and nothing is removed.
+    ///
+    /// - `bincode::Error` if a document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    pub fn remove_where<P>(&mut self, mut predicate: P) -> Result<usize, DatabaseError>
+    where
+        T: DeserializeOwned,
+        P: FnMut(&T) -> bool,
+    {
+        self.require_tombstones()?;
+        let mut removed = Vec::new();
+        for offset in frame_offsets(&mut self.file, &self.layout)? {
+            let document = read_document_at::<F, T, _>(&self.file, &self.layout, offset)?;
+            if predicate(&document) {
+                removed.push(offset);
+            }
+        }
+        self.mark_removed(&removed)?;
+        Ok(removed.len())
+    }
+
+    fn require_tombstones(&self) -> Result<(), DatabaseError> {
+        if !self.layout.tombstones {
+            return Err(DatabaseError::Unsupported(
+                "file was not created with tombstones enabled",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Runs `write`, which overwrites bytes in place. A file is written through a separate
+    /// handle, because positioned writes through a handle opened in append mode end up at
+    /// the end of the file, and any other backend is written directly. The footer is
+    /// removed first, since it no longer matches once bytes change.
+    fn write_in_place<G>(&mut self, write: G) -> Result<(), DatabaseError>
+    where
+        G: FnOnce(&mut dyn Backend) -> Result<(), DatabaseError>,
+    {
+        self.unique = None;
+        self.appended = None;
+        strip_footer(&mut self.file, &self.layout)?;
+        if self.file.as_file().is_none() {
+            return write(&mut self.file);
+        }
+        let mut options = OpenOptions::new();
+        options.write(true);
+        if self.write_through {
+            set_write_through(&mut options)?;
+        }
+        write(&mut options.open(self.current_path())?)
+    }
+
+    /// Sets the removed bit in the status byte of the frames at `offsets`.
+    fn mark_removed(&mut self, offsets: &[u64]) -> Result<(), DatabaseError> {
+        if offsets.is_empty() {
+            return Ok(());
+        }
+        self.write_in_place(|file| {
+            for &offset in offsets {
+                file.seek(SeekFrom::Start(offset + FRAME_HEADER_LEN as u64))?;
+                file.write_all(&[STATUS_REMOVED])?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Rewrites the file without the bytes that no longer belong to any document, such as
+    /// removed documents or an incomplete document left behind by an interrupted write,
+    /// and returns the number of bytes reclaimed. The file is rewritten into a temporary
+    /// file which is then renamed over the original, so the file is never left
+    /// half-rewritten.
+    ///
+    /// Nothing is rewritten if there is nothing to reclaim.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned and the file is left untouched.
+    ///
+    /// - `std::io::Error`
+    pub fn vacuum(&mut self) -> Result<u64, DatabaseError> {
+        let buf = read_file(&self.file, &self.layout)?;
+        let mut kept = Vec::with_capacity(buf.len());
+        let mut raw_data = buf.as_slice();
+        loop {
+            let start = raw_data;
+            let frame = match FrameRef::split(&mut raw_data, &self.layout) {
+                Some(frame) if frame.is_complete() => frame,
+                _ => break,
+            };
+            if frame.removed {
+                continue;
+            }
+            frame.verify(&self.layout)?;
+            kept.extend_from_slice(&start[..start.len() - raw_data.len()]);
+        }
+        let reclaimed = (buf.len() - kept.len()) as u64;
+        if reclaimed > 0 {
+            self.replace_data(&kept)?;
+        }
+        Ok(reclaimed)
+    }
+
+    /// Truncates the file right after the last frame that is complete and matches its
+    /// checksum, and returns the number of bytes discarded, e.g. to resume appending to a
+    /// file after a crash left half a document at its end. Frames are checked from the
+    /// start of the file, so everything from the first bad frame onwards is discarded,
+    /// including intact frames that follow it. Use [`load_resync`](Client::load_resync)
+    /// to read those first.
+    ///
+    /// Unlike `vacuum()`, the file is truncated in place rather than rewritten, so this
+    /// is cheap but does not reclaim the space of removed documents.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn truncate_to_last_valid(&mut self) -> Result<u64, DatabaseError> {
+        self.layout.require_whole_file()?;
+        let buf = read_file(&self.file, &self.layout)?;
+        let mut raw_data = buf.as_slice();
+        let mut valid = 0;
+        loop {
+            let start = raw_data.len();
+            let Some(frame) = FrameRef::split(&mut raw_data, &self.layout) else {
+                break;
+            };
+            let len = (start - raw_data.len()) as u64;
+            if len != self.layout.frame_len(frame.data_len) || frame.verify(&self.layout).is_err() {
+                break;
+            }
+            valid += len;
+        }
+        let discarded = buf.len() as u64 - valid;
+        if discarded > 0 {
+            self.unique = None;
+            self.appended = None;
+            self.file.set_len(self.layout.data_start + valid)?;
+        }
+        Ok(discarded)
+    }
+
+    /// Truncates the last frame if it ends past the end of the file, and returns the
+    /// number of bytes discarded, e.g. to resume appending to a file after a crash left
+    /// half a document at its end. Unlike
+    /// [`truncate_to_last_valid`](Client::truncate_to_last_valid), only the frame headers
+    /// are read and no checksum is checked, so complete frames are never discarded, even
+    /// if they are corrupted.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn recover_tail(&mut self) -> Result<u64, DatabaseError> {
+        self.layout.require_whole_file()?;
+        let end = data_end(&self.file, &self.layout)?;
+        let header_len = self.layout.frame_header_len();
+        let mut offset = self.layout.data_start;
+        while end - offset >= header_len {
+            let mut header = ReadAt {
+                file: &self.file,
+                offset: offset + 4,
+            };
+            let len = self.layout.endianness.read_u32(&mut header)?;
+            let next = offset + self.layout.frame_len(len);
+            if next > end {
+                break;
+            }
+            offset = next;
+        }
+        let discarded = end - offset;
+        if discarded > 0 {
+            self.unique = None;
+            self.appended = None;
+            self.file.set_len(offset)?;
+        }
+        Ok(discarded)
+    }
+
+    /// Removes every document, along with the footer, by truncating the file in place
+    /// to its header, in any mode, so that the client can keep appending to the emptied
+    /// file. A file without a header is left with a length of 0.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn clear(&mut self) -> Result<(), DatabaseError> {
+        self.layout.require_whole_file()?;
+        self.unique = None;
+        self.appended = None;
+        self.file.set_len(self.layout.data_start)?;
+        self.file.seek(SeekFrom::Start(self.layout.data_start))?;
+        self.commit_overwrite()
+    }
+
+    /// Removes the `n` oldest documents, returning the number of documents removed,
+    /// which is smaller than `n` if the collection has fewer documents. This is useful
+    /// to keep a rolling window of recent documents.
+    ///
+    /// Since documents can only be found by walking the file from the start, the
+    /// remaining documents are copied into a new file which replaces the original the
+    /// same way as in `vacuum()`.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn trim_prefix(&mut self, n: usize) -> Result<usize, DatabaseError> {
+        self.layout.require_frame_per_document()?;
+        let frames = scan_file(&mut self.file, &self.layout)?;
+        let mut removed = 0;
+        let mut start = self.layout.data_start;
+        for frame in frames {
+            if removed == n {
+                break;
+            }
+            if !frame.removed {
+                removed += 1;
+            }
+            start = frame.offset + self.layout.frame_len(frame.len);
+        }
+        if removed == 0 {
+            return Ok(0);
+        }
+        let end = data_end(&self.file, &self.layout)?;
+        let mut remaining = Vec::new();
+        ReadAt {
+            file: &self.file,
+            offset: start,
+        }
+        .take(end - start)
+        .read_to_end(&mut remaining)?;
+        self.replace_data(&remaining)?;
+        Ok(removed)
+    }
+
+    /// Appends `document` like [`write`](Client::write) and then, if the file has grown
+    /// past `max_bytes`, removes the oldest documents like [`trim_prefix`](Client::trim_prefix)
+    /// until it is back under budget, e.g. for a buffer of recent events that must not
+    /// grow without bounds. Returns the number of documents removed. The newest document
+    /// is always kept, even if it alone takes up more than `max_bytes`.
+    ///
+    /// Since trimming rewrites the remaining documents, the file is trimmed down to three
+    /// quarters of `max_bytes` rather than just under it, so that the following appends
+    /// fit without another rewrite. Once the file is full, a rewrite of at most
+    /// `max_bytes` thus happens for every quarter of `max_bytes` appended, which amounts
+    /// to copying up to 4 bytes for every byte appended.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` for files with blocks.
+    ///
+    /// - See [`write`](Client::write) and [`trim_prefix`](Client::trim_prefix).
+    pub fn append_with_budget(
+        &mut self,
+        document: &T,
+        max_bytes: u64,
+    ) -> Result<usize, DatabaseError>
+    where
+        T: Serialize,
+    {
+        self.layout.require_frame_per_document()?;
+        self.write(document)?;
+        let len = self.file.size()?;
+        if len <= max_bytes {
+            return Ok(0);
+        }
+        let target = max_bytes - max_bytes / 4;
+        let frames = scan_file(&mut self.file, &self.layout)?;
+        let mut removed = 0;
+        let mut start = self.layout.data_start;
+        for frame in &frames[..frames.len() - 1] {
+            if len - (start - self.layout.data_start) <= target {
+                break;
+            }
+            if !frame.removed {
+                removed += 1;
+            }
+            start = frame.offset + self.layout.frame_len(frame.len);
+        }
+        if start == self.layout.data_start {
+            return Ok(0);
+        }
+        let end = data_end(&self.file, &self.layout)?;
+        let mut remaining = Vec::new();
+        ReadAt {
+            file: &self.file,
+            offset: start,
+        }
+        .take(end - start)
+        .read_to_end(&mut remaining)?;
+        self.replace_data(&remaining)?;
+        Ok(removed)
+    }
+
+    /// Inserts `document` in front of the document at `index`, shifting it and every
+    /// following document up by one. An `index` equal to the number of documents appends
+    /// it. Removed documents are not counted.
+    ///
+    /// The following documents are copied without being decoded into a new file which
+    /// replaces the original the same way as in `vacuum()`.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::IndexOutOfRange` if `index` is larger than the number of
+    ///   documents.
+    ///
+    /// - `std::num::TryFromIntError` occurs when the document takes up more space than
+    ///   `u32::MAX` bytes.
+    ///
+    /// - `bincode::Error` when the document fails to serialize for some reason.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn insert_at(&mut self, index: usize, document: &T) -> Result<(), DatabaseError>
+    where
+        T: Serialize,
+    {
+        let offsets = frame_offsets(&mut self.file, &self.layout)?;
+        if index > offsets.len() {
+            return Err(DatabaseError::IndexOutOfRange {
+                index,
+                len: offsets.len(),
+            });
+        }
+        let frame = vec_to_binary::<F, _>(std::array::from_ref(document), &self.layout)?;
+        let mut data = read_file(&self.file, &self.layout)?;
+        let split = offsets.get(index).map_or(data.len(), |&offset| {
+            (offset - self.layout.data_start) as usize
+        });
+        data.splice(split..split, frame);
+        self.replace_data(&data)
+    }
+
+    /// Replaces the first document whose key, as returned by `key`, equals the key of
+    /// `document`, or appends `document` if there is none. Returns `true` if a document
+    /// was replaced and `false` if it was appended. Removed documents are skipped.
+    ///
+    /// Documents are decoded one at a time until a match is found. If the new document
+    /// encodes to a frame of the same size, it is overwritten in place, which is cheap
+    /// but leaves a frame with a mismatched checksum if the process crashes halfway.
+    /// Otherwise, the other documents are copied without being decoded into a new file
+    /// which replaces the original the same way as in `vacuum()`.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `std::num::TryFromIntError` occurs when the document takes up more space than
+    ///   `u32::MAX` bytes.
+    ///
+    /// - `bincode::Error` if a document fails to serialize or deserialize.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn upsert<K, G>(&mut self, key: G, document: &T) -> Result<bool, DatabaseError>
+    where
+        T: Serialize + DeserializeOwned,
+        K: Eq,
+        G: Fn(&T) -> K,
+    {
+        self.layout.require_frame_per_document()?;
+        let wanted = key(document);
+        let mut found = None;
+        for frame in scan_file(&mut self.file, &self.layout)? {
+            if frame.removed {
+                continue;
+            }
+            let existing = read_document_at::<F, T, _>(&self.file, &self.layout, frame.offset)?;
+            if key(&existing) == wanted {
+                found = Some(frame);
+                break;
+            }
+        }
+        let Some(old) = found else {
+            self.write(document)?;
+            return Ok(false);
+        };
+        self.replace_frame(old, document)?;
+        Ok(true)
+    }
+
+    /// Replaces the document at `index` with `document`. Removed documents are not
+    /// counted.
+    ///
+    /// If the new document encodes to a frame of the same size, it is overwritten in
+    /// place like in `upsert()`, which is cheap but leaves a frame with a mismatched
+    /// checksum if the process crashes halfway. Otherwise, the other documents are copied
+    /// without being decoded into a new file which replaces the original the same way as
+    /// in `vacuum()`.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::IndexOutOfRange` if the collection has `index` documents or
+    ///   fewer.
+    ///
+    /// - `std::num::TryFromIntError` occurs when the document takes up more space than
+    ///   `u32::MAX` bytes.
+    ///
+    /// - `bincode::Error` when the document fails to serialize for some reason.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+    ///   a document.
+    pub fn replace(&mut self, index: usize, document: &T) -> Result<(), DatabaseError>
+    where
+        T: Serialize,
+    {
+        self.layout.require_frame_per_document()?;
+        let frames: Vec<_> = scan_file(&mut self.file, &self.layout)?
+            .into_iter()
+            .filter(|frame| !frame.removed)
+            .collect();
+        let Some(&old) = frames.get(index) else {
+            return Err(DatabaseError::IndexOutOfRange {
+                index,
+                len: frames.len(),
+            });
+        };
+        self.replace_frame(old, document)
+    }
+
+    /// Replaces the frame `old` with a frame holding `document`, in place if both frames
+    /// have the same size.
+    fn replace_frame(&mut self, old: FrameMeta, document: &T) -> Result<(), DatabaseError>
+    where
+        T: Serialize,
+    {
+        // The document is encoded only once, so that the bytes whose size decides whether
+        // they fit in place are the bytes that get written. Encoding is not guaranteed to
+        // be deterministic, e.g. for a `HashMap`, whose entries come in a different order
+        // in every map.
+        let frame = vec_to_binary::<F, _>(std::array::from_ref(document), &self.layout)?;
+        let old_len = self.layout.frame_len(old.len);
+        if frame.len() as u64 == old_len {
+            return self.write_in_place(|file| {
+                file.seek(SeekFrom::Start(old.offset))?;
+                Ok(file.write_all(&frame)?)
+            });
+        }
+        let mut data = read_file(&self.file, &self.layout)?;
+        let start = (old.offset - self.layout.data_start) as usize;
+        data.splice(start..start + old_len as usize, frame);
+        self.replace_data(&data)
+    }
+
+    /// Replaces every document in the file with the documents of `documents`, returning
+    /// the number of documents written. The documents are streamed one at a time into a
+    /// temporary file which then replaces the original the same way as in `vacuum()`, so
+    /// memory use stays bounded and the file holds either the old or the new documents,
+    /// even if the process crashes. The file is left untouched if an error occurs.
+    ///
+    /// # Errors
+    ///
+    /// - `std::num::TryFromIntError` occurs when a document takes up more space than
+    ///   `u32::MAX` bytes.
+    ///
+    /// - `bincode::Error` when a document fails to serialize for some reason.
+    ///
+    /// - `std::io::Error`
+    pub fn replace_all_iter<I>(&mut self, documents: I) -> Result<usize, DatabaseError>
+    where
+        T: Serialize,
+        I: IntoIterator<Item = T>,
+    {
+        self.replace_documents(documents)
+    }
+
+    /// Replaces every document in the file with `documents` like
+    /// [`replace_all_iter`](Client::replace_all_iter). With no documents, this empties
+    /// the file, keeping only its header if it has one, so that `load()` returns
+    /// `Ok(None)`.
+    ///
+    /// # Errors
+    ///
+    /// See [`replace_all_iter`](Client::replace_all_iter).
+    pub fn replace_all(&mut self, documents: &[T]) -> Result<usize, DatabaseError>
+    where
+        T: Serialize,
+    {
+        self.replace_documents(documents)
+    }
+
+    fn replace_documents<I>(&mut self, documents: I) -> Result<usize, DatabaseError>
+    where
+        T: Serialize,
+        I: IntoIterator,
+        I::Item: Borrow<T>,
+    {
+        self.layout.require_whole_file()?;
+        self.commit_overwrite()?;
+        if self.keep_backup {
+            backup_file(&self.path)?;
+        }
+        let mut layout = self.layout;
+        let mut count = 0;
+        let scratch = &mut self.scratch;
+        replace_contents(&mut self.file, &self.path, |temp| {
+            let mut writer = BufWriter::new(temp);
+            if layout.needs_header() {
+                writer.write_all(&layout.encode_header())?;
+            }
+            let documents = documents.into_iter().inspect(|_| count += 1);
+            write_encoded::<F, _, T, _>(&mut writer, documents, &layout, scratch)?;
+            writer.flush()?;
+            Ok(())
+        })?;
+        self.after_replace()?;
+        Ok(count)
+    }
+
+    /// Replaces every document in the file with the documents read from `reader`, e.g. a
+    /// file downloaded or decompressed on the fly, and returns the number of documents
+    /// written. `reader` has to hold a file written by this crate with the same layout as
+    /// this file, header included if it has one. Removed documents are dropped, and so is
+    /// the footer of a file saved with [`finalize`](Client::finalize).
+    ///
+    /// Frames are read, checked and copied one at a time into a temporary file which then
+    /// replaces the original the same way as in `vacuum()`, so memory use stays bounded
+    /// and the file is left untouched if anything fails, e.g. if `reader` ends in the
+    /// middle of a frame.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` if the documents of `reader` are encoded
+    ///   differently from the documents of this file.
+    ///
+    /// - `DatabaseError::BadHeader` or `DatabaseError::UnsupportedVersion` if `reader`
+    ///   has a header this version of the crate cannot read.
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if `reader` ends in the middle of a
+    ///   frame.
+    pub fn load_from_reader<R: Read>(&mut self, mut reader: R) -> Result<usize, DatabaseError> {
+        self.layout.require_whole_file()?;
+        let mut prefix = Vec::with_capacity(MAGIC.len());
+        (&mut reader)
+            .take(MAGIC.len() as u64)
+            .read_to_end(&mut prefix)?;
+        let mut source = BufReader::new(prefix.as_slice().chain(reader));
+        let source_layout = if prefix == MAGIC {
+            Layout::read(&mut source, self.layout.checksum_algorithm)?
+        } else {
+            Layout::default()
+        };
+        if !source_layout.same_frames(&self.layout) {
+            return Err(DatabaseError::Unsupported(
+                "documents are encoded differently in the source",
+            ));
+        }
+        self.commit_overwrite()?;
+        if self.keep_backup {
+            backup_file(&self.path)?;
+        }
+        let mut layout = self.layout;
+        let header_len = layout.frame_header_len() as usize;
+        let mut count = 0;
+        replace_contents(&mut self.file, &self.path, |temp| {
+            let mut writer = BufWriter::new(temp);
+            if layout.needs_header() {
+                writer.write_all(&layout.encode_header())?;
+            }
+            let mut scratch = Vec::new();
+            // The next bytes of `source`, as many as a footer takes up, so that a footer
+            // at the end can be told apart from a frame before it is read as one.
+            let mut pending = Vec::with_capacity(FOOTER_LEN);
+            let mut data_len = 0;
+            loop {
+                let missing = FOOTER_LEN - pending.len();
+                (&mut source)
+                    .take(missing as u64)
+                    .read_to_end(&mut pending)?;
+                if pending.is_empty() {
+                    break;
+                }
+                if pending.len() == FOOTER_LEN
+                    && pending[..4] == FOOTER_MAGIC
+                    && layout.endianness.u64_from_slice(&pending[8..]) == data_len
+                    && source.fill_buf()?.is_empty()
+                {
+                    break;
+                }
+                let mut head = pending.as_slice();
+                let frame = read_frame(&mut (&mut head).chain(&mut source), &layout)?;
+                pending = head.to_vec();
+                if frame.data.len() != frame.data_len as usize {
+                    return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+                }
+                data_len += layout.frame_len(frame.data_len);
+                if frame.removed {
+                    continue;
+                }
+                let body = frame.verify(&layout)?;
+                let (_, data) = layout.split_body(&body)?;
+                for document in layout.split_documents(data) {
+                    document?;
+                    count += 1;
+                }
+                scratch.clear();
+                scratch.resize(header_len, 0);
+                scratch.extend_from_slice(&body);
+                finish_frame(&mut scratch, &layout)?;
+                writer.write_all(&scratch)?;
+            }
+            writer.flush()?;
+            Ok(())
+        })?;
+        self.after_replace()?;
+        Ok(count)
+    }
+
+    /// Atomically replaces the documents in the file with the already framed `data`,
+    /// keeping the header of the file.
+    fn replace_data(&mut self, data: &[u8]) -> Result<(), DatabaseError> {
+        self.layout.require_whole_file()?;
+        self.commit_overwrite()?;
+        let mut layout = self.layout;
+        replace_contents(&mut self.file, &self.path, |temp| {
+            if layout.needs_header() {
+                temp.write_all(&layout.encode_header())?;
+            }
+            temp.write_all(data)?;
+            Ok(())
+        })?;
+        self.after_replace()
+    }
+
+    /// Reopens the file with the write-through flag after it was replaced, since the
+    /// handle to the replacement was opened without it, and forgets the checksums cached
+    /// for `append_unique()` and the count cached for `append()`.
+    fn after_replace(&mut self) -> Result<(), DatabaseError> {
+        self.unique = None;
+        self.appended = None;
+        if self.write_through {
+            self.reopen()?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the handle of the client with a new handle to the file at its path, e.g.
+    /// if the file may have been replaced by another client.
+    fn reopen(&mut self) -> Result<(), DatabaseError> {
+        let mut options = OpenOptions::new();
+        options.read(true).write(true);
+        if self.write_through {
+            set_write_through(&mut options)?;
+        }
+        // Only a file can be replaced behind the back of the client.
+        let path = self.current_path().to_path_buf();
+        if let Some(file) = self.file.as_file() {
+            *file = options.open(path)?;
+        }
+        self.unique = None;
+        self.appended = None;
+        Ok(())
+    }
+
+    /// Returns the path of the file that the handle of the client points to.
+    fn current_path(&self) -> &Path {
+        self.pending
+            .as_ref()
+            .map_or(&self.path, |pending| &pending.temp_path)
+    }
+
+    /// Renames the temporary file written to since the client was opened in overwrite
+    /// mode over the file, once it is on disk, so that a crash never leaves the file
+    /// half-written.
+    pub(crate) fn commit_overwrite(&mut self) -> Result<(), DatabaseError> {
+        let Some(pending) = &mut self.pending else {
+            return Ok(());
+        };
+        self.file.sync()?;
+        pending.replace()?;
+        self.pending = None;
+        Ok(())
+    }
+
+    /// Flushes every write made so far to disk with `sync_data` if the interval configured
+    /// with [`ClientBuilder::flush_every`] has elapsed since the last flush, returning
+    /// whether a flush happened. Call this from the loop that writes documents to bound
+    /// how much data can be lost on a crash without paying for a flush on every write.
+    ///
+    /// Always returns `Ok(false)` if no interval is configured.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn maybe_flush(&mut self) -> Result<bool, DatabaseError> {
+        let Some(interval) = self.flush_every else {
+            return Ok(false);
+        };
+        if self.last_flush.elapsed() < interval {
+            return Ok(false);
+        }
+        match self.file.as_file() {
+            Some(file) => file.sync_data()?,
+            None => self.file.sync()?,
+        }
+        self.last_flush = Instant::now();
+        Ok(true)
+    }
+
+    /// Flushes every write made so far to disk with `sync_all`, and only returns once
+    /// the operating system reports that they are durable. On Unix, the directory
+    /// containing the file is synced as well, so that a file that was just created or
+    /// replaced, e.g. by `vacuum()`, does not lose its directory entry on a crash. Any
+    /// other [`Backend`] is flushed with [`Backend::sync`] instead.
+    ///
+    /// Writes made after `fence()` returns can never become durable before the writes
+    /// made before it, which orders writes across files: to make an index file durable
+    /// only after the data file it points into, call `fence()` on the data client before
+    /// writing to the index client, and then `fence()` on the index client. Writes made
+    /// to the same file without a fence in between may reach the disk in any order.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn fence(&mut self) -> Result<(), DatabaseError> {
+        self.commit_overwrite()?;
+        self.file.sync()?;
+        #[cfg(unix)]
+        if self.file.as_file().is_some() {
+            let parent = match self.path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            File::open(parent)?.sync_all()?;
+        }
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    /// Starts a [`Transaction`] which buffers documents in memory until it is committed,
+    /// at which point all of them are written to disk at once.
+    pub fn begin(&mut self) -> Transaction<'_, T, F, B>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        Transaction::new(self)
+    }
+}
+
+/// Iterates over every document in the file, consuming the client. Like
+/// [`Client::iter`], iteration always starts at the first document of the file,
+/// regardless of what the client did before.
+impl<T, F, B> IntoIterator for Client<T, F, B>
+where
+    T: DeserializeOwned,
+    F: Format,
+    B: Backend,
+{
+    type Item = Result<T, DatabaseError>;
+    type IntoIter = IntoIter<T, F, B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter::new(self.file, self.layout)
+    }
+}
+
+/// Iterates over every document in the file without consuming the client. This is the
+/// same as calling [`Client::iter`].
+impl<'a, T, F, B> IntoIterator for &'a mut Client<T, F, B>
+where
+    T: DeserializeOwned,
+    F: Format,
+    B: Backend,
+{
+    type Item = Result<T, DatabaseError>;
+    type IntoIter = RecordIter<'a, T, F, B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// How [`ClientBuilder::open`] treats the contents of an existing file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OpenMode {
+    /// The file is emptied when it is opened, so that writes replace its documents.
+    /// This is the default.
+    ///
+    /// An existing file is not truncated in place: documents are written to a temporary
+    /// file next to it, which is synced and renamed over the file once the first write
+    /// succeeds, or when the client is dropped. A crash before then leaves the previous
+    /// documents intact. Later writes are appended to the new file.
+    #[default]
+    Overwrite,
+    /// Documents are always appended to the end of the file, and files created in this
+    /// mode are marked as append logs. See [`ClientBuilder::append`].
+    Append,
+    /// The file is opened for reading and writing without being truncated, so that its
+    /// documents can be edited in place, e.g. with [`Client::upsert`] or
+    /// [`Client::refresh_checksum_at`], while new documents are still appended to its
+    /// end. Unlike append mode, files created in this mode are not marked as append logs.
+    Update,
+}
+
+/// Options used to configure how a [`Client`] is opened.
+///
+/// Options such as the byte order only take effect when a file is created, or truncated
+/// because it is opened in overwrite mode. Existing files are always read and appended to
+/// using the layout recorded in them.
+///
+/// # Example
+///
+/// ```ignore
+/// use crio::{Client, ClientBuilder, Endianness};
+///
+/// let client: Client<Message> = ClientBuilder::new()
+///     .append(true)
+///     .endianness(Endianness::Big)
+///     .open("messages")?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ClientBuilder {
+    open_mode: OpenMode,
+    endianness: Endianness,
+    timestamps: bool,
+    tombstones: bool,
+    create_dirs: bool,
+    flush_every: Option<Duration>,
+    deserialize_limit: Option<u64>,
+    max_record_size: Option<u32>,
+    reject_trailing_bytes: bool,
+    checksum_algorithm: Option<&'static Algorithm<u32>>,
+    checksum: Checksum,
+    alignment: u32,
+    block_size: u32,
+    frame_version: Option<u8>,
+    frame_tags: bool,
+    frame_compression: bool,
+    compression_level: Option<u32>,
+    write_header: bool,
+    verify_on_open: bool,
+    verify_after_write: bool,
+    probe_filesystem: bool,
+    force: bool,
+    app_id: Option<[u8; 8]>,
+    write_through: bool,
+    keep_backup: bool,
+    exclusive: bool,
+    wait_for_lock: bool,
+    #[cfg(unix)]
+    mode: Option<u32>,
+    #[cfg(unix)]
+    custom_flags: i32,
+    #[cfg(windows)]
+    share_mode: Option<u32>,
+    restrict_permissions: bool,
+}
+
+impl ClientBuilder {
+    /// Creates a builder with the default options: overwrite mode and little-endian frames.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set to `true` to append data to the file, or `false` to overwrite all data while
+    /// calling `write()` or `write_many()`. See [`Client::new`].
+    ///
+    /// Files created in append mode are marked as append logs in a header at the start of
+    /// the file. Opening an append log that contains documents in overwrite mode fails
+    /// unless [`force`](ClientBuilder::force) is enabled, since it would wipe them.
+    pub fn append(&mut self, append: bool) -> &mut Self {
+        self.open_mode = if append {
+            OpenMode::Append
+        } else {
+            OpenMode::Overwrite
+        };
+        self
+    }
+
+    /// Sets how the file is opened, which is overwrite mode by default. See [`OpenMode`].
+    pub fn open_mode(&mut self, open_mode: OpenMode) -> &mut Self {
+        self.open_mode = open_mode;
+        self
+    }
+
+    /// Sets the byte order used to store the checksum and length of every document.
+    /// Anything other than the default little-endian order is recorded in a header at
+    /// the start of the file so that readers pick the matching byte order.
+    pub fn endianness(&mut self, endianness: Endianness) -> &mut Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Set to `true` to store the time at which every document was written, in
+    /// milliseconds since the Unix epoch. This adds 8 bytes to every document and is
+    /// recorded in a header at the start of the file. The timestamps can be read with
+    /// [`Client::load_with_timestamps`] and [`Client::tail_since`].
+    pub fn timestamps(&mut self, timestamps: bool) -> &mut Self {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// Sets the schema version stored with every document written from now on, e.g. to
+    /// bump it whenever a field is added to the document type. This adds 1 byte to every
+    /// document and is recorded in a header at the start of the file, if the file is
+    /// created by this client. The versions can be read with [`Client::load_versioned`].
+    ///
+    /// Files created without versions keep storing none. Documents written to a file
+    /// with versions by a client without a version set are stored with version 0.
+    pub fn frame_version(&mut self, version: u8) -> &mut Self {
+        self.frame_version = Some(version);
+        self
+    }
+
+    /// Set to `true` to store a type tag with every document, e.g. to tell apart the
+    /// kinds of events in a log. This adds 2 bytes to every document and is recorded in a
+    /// header at the start of the file, if the file is created by this client. Documents
+    /// are tagged with [`Client::write_tagged`] and the tags can be read with
+    /// [`Client::load_tagged`].
+    ///
+    /// Documents written to a file with tags by any other method are stored with tag 0.
+    pub fn frame_tags(&mut self, frame_tags: bool) -> &mut Self {
+        self.frame_tags = frame_tags;
+        self
+    }
+
+    /// Set to `true` to let every document choose whether it is compressed, by writing it
+    /// with [`Client::write_compressed`] instead of [`Client::write`]. This adds 1 byte
+    /// of flags to every document and is recorded in a header at the start of the file,
+    /// if the file is created by this client. Reading compressed documents requires the
+    /// `gzip` feature.
+    ///
+    /// This cannot be combined with [`block_size`](ClientBuilder::block_size).
+    pub fn frame_compression(&mut self, frame_compression: bool) -> &mut Self {
+        self.frame_compression = frame_compression;
+        self
+    }
+
+    /// Compresses every document written by the client with deflate at `level`, from `0`
+    /// for no compression to `9` for the best compression, e.g. for documents with many
+    /// repeated strings. This enables [`frame_compression`](ClientBuilder::frame_compression),
+    /// so existing files have to have been created with it, and requires the `gzip`
+    /// feature. The checksum of every document covers its compressed bytes.
+    pub fn compression_level(&mut self, level: u32) -> &mut Self {
+        self.compression_level = Some(level);
+        self
+    }
+
+    /// Set to `true` to start a new file with a header even if it uses the default layout,
+    /// so that the file is self-describing: opening it with another [`Format`] or with an
+    /// incompatible version of this crate then fails with a `FormatMismatch`, `BadHeader`
+    /// or `UnsupportedVersion` error instead of a checksum mismatch. Files using the
+    /// default layout are written without a header by default so that they stay readable
+    /// by older versions of this crate. Files without a header can always be opened.
+    pub fn write_header(&mut self, write_header: bool) -> &mut Self {
+        self.write_header = write_header;
+        self
+    }
+
+    /// Set to `true` to give every document a status byte so that it can be removed in
+    /// place with [`Client::remove_at`] or [`Client::remove_where`]. This adds 1 byte to
+    /// every document and is recorded in a header at the start of the file.
+    pub fn tombstones(&mut self, tombstones: bool) -> &mut Self {
+        self.tombstones = tombstones;
+        self
+    }
+
+    /// Set to `true` to create the parent directories of the file if they are missing.
+    pub fn create_dirs(&mut self, create_dirs: bool) -> &mut Self {
+        self.create_dirs = create_dirs;
+        self
+    }
+
+    /// Sets the permission bits a new file is created with, e.g. `0o600` for a file only
+    /// the owner may read. The mode is applied atomically when the file is created and
+    /// is still reduced by the umask of the process. Existing files keep their
+    /// permissions.
+    #[cfg(unix)]
+    pub fn mode(&mut self, mode: u32) -> &mut Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Sets extra flags to pass to `open(2)` when the file is opened, e.g.
+    /// `libc::O_NOFOLLOW` to refuse a path that is a symbolic link. The flags are
+    /// combined with those this crate sets itself, such as for
+    /// [`write_through`](ClientBuilder::write_through). Files that later replace the
+    /// file, e.g. in [`Client::vacuum`], are opened without them.
+    #[cfg(unix)]
+    pub fn custom_flags(&mut self, flags: i32) -> &mut Self {
+        self.custom_flags = flags;
+        self
+    }
+
+    /// Sets the share mode the file is opened with, which decides whether other
+    /// processes may read, write or delete the file while the client has it open. By
+    /// default, all three are allowed. Files that later replace the file, e.g. in
+    /// [`Client::vacuum`], are opened with the default share mode.
+    #[cfg(windows)]
+    pub fn share_mode(&mut self, share_mode: u32) -> &mut Self {
+        self.share_mode = Some(share_mode);
+        self
+    }
+
+    /// Set to `true` to make a new file accessible to its owner only, on a best-effort
+    /// basis. On Unix, this creates the file with mode `0o600` unless another
+    /// [`mode`](ClientBuilder::mode) is configured. On other platforms, files inherit the
+    /// access rules of their directory, which are left as is. Existing files keep their
+    /// permissions.
+    pub fn restrict_permissions(&mut self, restrict_permissions: bool) -> &mut Self {
+        self.restrict_permissions = restrict_permissions;
+        self
+    }
+
+    /// Sets the interval after which [`Client::maybe_flush`] flushes written documents to
+    /// disk. No background thread is spawned; the client only flushes when asked to.
+    pub fn flush_every(&mut self, interval: Duration) -> &mut Self {
+        self.flush_every = Some(interval);
+        self
+    }
+
+    /// Limits how many bytes a single document may allocate while it is decoded, so that
+    /// a corrupted or malicious file cannot cause an out-of-memory condition. Documents
+    /// exceeding the limit fail to load with `DatabaseError::LimitExceeded`.
+    ///
+    /// The limit is enforced by [`Bincode`]; other formats may ignore it.
+    pub fn deserialize_limit(&mut self, bytes: u64) -> &mut Self {
+        self.deserialize_limit = Some(bytes);
+        self
+    }
+
+    /// Rejects frames that declare a body of more than `bytes` bytes with
+    /// `DatabaseError::RecordTooLarge` when they are read one at a time, e.g. by
+    /// [`Client::iter`] or [`Client::get`], before a buffer is allocated for them. Without
+    /// a limit, a corrupted length can still not make a frame allocate much more memory
+    /// than the file holds.
+    pub fn max_record_size(&mut self, bytes: u32) -> &mut Self {
+        self.max_record_size = Some(bytes);
+        self
+    }
+
+    /// Set to `true` to fail decoding a document with `DatabaseError::TrailingBytes` if
+    /// its frame holds more bytes than the document, instead of ignoring them. This
+    /// catches frames whose length and checksum are intact but whose content is not what
+    /// was written, e.g. a document of another type.
+    ///
+    /// Leftover bytes are detected by [`Bincode`]; other formats may ignore them.
+    pub fn reject_trailing_bytes(&mut self, reject: bool) -> &mut Self {
+        self.reject_trailing_bytes = reject;
+        self
+    }
+
+    /// Sets the CRC32 algorithm used to compute the checksum of every document, e.g. to
+    /// produce files that external tools using another variant can validate. The default
+    /// is `CRC_32_ISO_HDLC`. Any other algorithm is identified in a header at the start of
+    /// the file, and existing files can only be opened with the algorithm they were
+    /// created with.
+    pub fn checksum_algorithm(&mut self, algorithm: &'static Algorithm<u32>) -> &mut Self {
+        self.checksum_algorithm = Some(algorithm);
+        self
+    }
+
+    /// Sets the width of the checksum that protects every document. The default is
+    /// [`Checksum::Crc32`]. With [`Checksum::Crc64`], every frame also stores a 64-bit
+    /// checksum of its document, which is checked wherever a document is read and fails
+    /// with `DatabaseError::MismatchedChecksum64`. With [`Checksum::None`], no checksum
+    /// is computed or checked. This is recorded in a header at the start of the file,
+    /// and is ignored when opening an existing file.
+    pub fn checksum(&mut self, checksum: Checksum) -> &mut Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Pads every frame so that every encoded document starts at a multiple of
+    /// `alignment` bytes from the start of the file, e.g. to speed up reading a file
+    /// that is mapped into memory. This is recorded in a header at the start of the file.
+    /// The alignment must be a power of two no larger than 4096; `1` disables padding.
+    pub fn align(&mut self, alignment: u32) -> &mut Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Groups the documents of every write into blocks of up to `documents` documents,
+    /// which share a single frame and therefore a single checksum, e.g. to write many
+    /// small documents with less overhead. A corrupted block fails to load with
+    /// `DatabaseError::CorruptedBlock`, and loses every document in it. This is recorded
+    /// in a header at the start of the file; `0` and `1` store every document in a frame
+    /// of its own.
+    ///
+    /// Only documents written together share a block, so `write()` always writes a block
+    /// of one document. Blocks cannot be combined with
+    /// [`tombstones`](ClientBuilder::tombstones) or
+    /// [`timestamps`](ClientBuilder::timestamps), and operations that locate a document
+    /// by its frame, such as [`Client::get_many`], are not supported for them.
+    pub fn block_size(&mut self, documents: u32) -> &mut Self {
+        self.block_size = documents;
+        self
+    }
+
+    /// Set to `true` to allow overwrite mode to wipe a file that was created in append
+    /// mode. See [`append`](ClientBuilder::append).
+    pub fn force(&mut self, force: bool) -> &mut Self {
+        self.force = force;
+        self
+    }
+
+    /// Sets an identifier of the application, such as `b"MYAPP001"`, which is recorded in
+    /// a header at the start of the file if the file is created by this client, so that
+    /// files of different applications are not mistaken for one another.
+    ///
+    /// Opening a file that holds documents fails with `DatabaseError::WrongAppId` unless
+    /// it records the same identifier. In overwrite mode, a file recording another
+    /// identifier is not wiped either, unless [`force`](ClientBuilder::force) is enabled.
+    pub fn app_id(&mut self, app_id: &[u8; 8]) -> &mut Self {
+        self.app_id = Some(*app_id);
+        self
+    }
+
+    /// Set to `true` to keep the previous contents of the file in a `.bak` file next to
+    /// it whenever they are overwritten, i.e. when an existing file is opened in
+    /// overwrite mode and by [`Client::replace_all_iter`]. Only one previous version is
+    /// kept, and it can be swapped back in with [`Client::restore_backup`].
+    ///
+    /// The file is copied into a temporary file which is then renamed over the backup, so
+    /// the backup always holds a whole previous version, at the cost of copying the file
+    /// before every overwrite.
+    pub fn keep_backup(&mut self, keep_backup: bool) -> &mut Self {
+        self.keep_backup = keep_backup;
+        self
+    }
+
+    /// Set to `true` to take the lock of [`Client::lock`] when the file is opened and to
+    /// hold it until the client is dropped, so that no other exclusive client, in this
+    /// process or another one, can write to the file at the same time. Opening the file
+    /// fails with `DatabaseError::Locked` while another client holds the lock, unless
+    /// [`wait_for_lock`](ClientBuilder::wait_for_lock) is enabled.
+    ///
+    /// Like the lock of `Client::lock`, this lock is advisory, so clients opened without
+    /// this option are not kept out.
+    pub fn exclusive(&mut self, exclusive: bool) -> &mut Self {
+        self.exclusive = exclusive;
+        self
+    }
+
+    /// Set to `true` to block until the lock of [`exclusive`](ClientBuilder::exclusive) is
+    /// released by the client holding it, instead of failing.
+    pub fn wait_for_lock(&mut self, wait_for_lock: bool) -> &mut Self {
+        self.wait_for_lock = wait_for_lock;
+        self
+    }
+
+    /// Set to `true` to check the checksum of every document when the file is opened, so
+    /// that a corrupted file is detected right away instead of when the corrupted document
+    /// is read. This reads the whole file and is therefore disabled by default.
+    pub fn verify_on_open(&mut self, verify_on_open: bool) -> &mut Self {
+        self.verify_on_open = verify_on_open;
+        self
+    }
+
+    /// Set to `true` to read every write back right after it was made and check the
+    /// checksum of every frame written, so that a write the file system corrupted is
+    /// detected right away instead of when the document is read. A write that fails the
+    /// check is undone and returns `DatabaseError::WriteVerifyFailed`.
+    ///
+    /// Since every write is followed by a read of the same size, this is disabled by
+    /// default. The read may be served from the cache of the operating system, so it
+    /// does not prove that the data reached the disk.
+    pub fn verify_after_write(&mut self, verify_after_write: bool) -> &mut Self {
+        self.verify_after_write = verify_after_write;
+        self
+    }
+
+    /// Set to `true` to check that the file system of the file supports what this crate
+    /// relies on before the file is opened, rather than finding out through a lost
+    /// write. This matters on network file systems, which may accept the calls but not
+    /// honor them.
+    ///
+    /// A temporary file next to the file is created, locked as by [`Client::lock`], and
+    /// renamed over a second temporary file, as the file itself is replaced by
+    /// [`Client::vacuum`]. Both temporary files are removed afterwards.
+    pub fn probe_filesystem(&mut self, probe_filesystem: bool) -> &mut Self {
+        self.probe_filesystem = probe_filesystem;
+        self
+    }
+
+    /// Set to `true` to open the file in write-through mode, so that every write only
+    /// returns once the data has reached the disk, without calling `sync_data`. This
+    /// makes writes much slower, and suits files that are rarely written to but must
+    /// never lose a write, such as configuration stores.
+    ///
+    /// The file is opened with `O_DSYNC` on Unix and `FILE_FLAG_WRITE_THROUGH` on
+    /// Windows. Whether the data also bypasses the write cache of the drive itself
+    /// depends on the operating system and the drive. On other platforms, opening the
+    /// file fails with `DatabaseError::Unsupported`.
+    pub fn write_through(&mut self, write_through: bool) -> &mut Self {
+        self.write_through = write_through;
+        self
+    }
+
+    /// Opens the file at `path` with the configured options. It opens the file if a
+    /// file with the same name exists or creates a new file if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Open` if it fails to open or create a new file.
+    ///
+    /// - `DatabaseError::NotAFile` if `path` points to a directory or a special file.
+    ///
+    /// - `DatabaseError::ParentMissing` if the parent directory of `path` does not exist
+    ///   and [`create_dirs`](ClientBuilder::create_dirs) is not enabled.
+    ///
+    /// - `DatabaseError::BadHeader` or `DatabaseError::UnsupportedVersion` if an existing
+    ///   file has a header this version of the crate cannot read.
+    ///
+    /// - `DatabaseError::FormatMismatch` if an existing file was written with a different
+    ///   [`Format`].
+    ///
+    /// - `DatabaseError::Unsupported` if the configured [`align`](ClientBuilder::align)
+    ///   is not a power of two no larger than 4096, or a
+    ///   [`block_size`](ClientBuilder::block_size) is combined with tombstones or
+    ///   timestamps.
+    ///
+    /// - `DatabaseError::ModeConflict` if the file is an append log that contains
+    ///   documents, it is opened in overwrite mode and [`force`](ClientBuilder::force) is
+    ///   not enabled.
+    ///
+    /// - `DatabaseError::UnsupportedFilesystem` if
+    ///   [`probe_filesystem`](ClientBuilder::probe_filesystem) is enabled and the file
+    ///   system cannot lock or atomically replace files.
+    ///
+    /// - `DatabaseError::Locked` if [`exclusive`](ClientBuilder::exclusive) is enabled
+    ///   and another client holds the lock of the file.
+    ///
+    /// - If [`verify_on_open`](ClientBuilder::verify_on_open) is enabled, a
+    ///   `DatabaseError::CorruptedFrame` error for the first corrupted document, or a
+    ///   `std::io::Error` of kind `UnexpectedEof` if the last document is incomplete.
+    pub fn open<T, F, P>(&self, path: P) -> Result<Client<T, F>, DatabaseError>
+    where
+        F: Format,
+        P: AsRef<Path>,
+    {
+        if self.alignment > 1
+            && (!self.alignment.is_power_of_two() || self.alignment > MAX_ALIGNMENT)
+        {
+            return Err(DatabaseError::Unsupported(
+                "alignment must be a power of two no larger than 4096",
+            ));
+        }
+        if self.block_size > 1 && (self.tombstones || self.timestamps) {
+            return Err(DatabaseError::Unsupported(
+                "blocks cannot be combined with tombstones or timestamps",
+            ));
+        }
+        if self.compression_level.is_some_and(|level| level > 9) {
+            return Err(DatabaseError::Unsupported(
+                "compression level must be between 0 and 9",
+            ));
+        }
+        let frame_compression = self.frame_compression || self.compression_level.is_some();
+        if self.block_size > 1 && frame_compression {
+            return Err(DatabaseError::Unsupported(
+                "blocks cannot be combined with per-document compression",
+            ));
+        }
+        let lock = if self.exclusive {
+            self.create_parent(path.as_ref())?;
+            Some(lock::lock_file(path.as_ref(), self.wait_for_lock)?)
+        } else {
+            None
+        };
+        if self.open_mode == OpenMode::Overwrite
+            && !self.force
+            && is_append_log(path.as_ref(), self.checksum_algorithm)
+        {
+            return Err(DatabaseError::ModeConflict(path.as_ref().to_path_buf()));
+        }
+        if let Some(expected) = self.app_id {
+            if self.open_mode == OpenMode::Overwrite && !self.force {
+                if let Some(found) = recorded_app_id(path.as_ref(), self.checksum_algorithm) {
+                    if found != expected {
+                        return Err(DatabaseError::WrongAppId {
+                            expected,
+                            found: Some(found),
+                        });
+                    }
+                }
+            }
+        }
+        let (mut file, created, pending) = if self.open_mode == OpenMode::Overwrite
+            && std::fs::metadata(path.as_ref()).is_ok_and(|m| m.is_file() && m.len() > 0)
+        {
+            let (file, temp_path) = self.open_replacement(path.as_ref())?;
+            let replacement = Replacement {
+                temp_path,
+                path: path.as_ref().to_path_buf(),
+                replaced: false,
+            };
+            (file, false, Some(replacement))
+        } else {
+            let (file, created) = self.open_file(path.as_ref())?;
+            (file, created, None)
+        };
+        let layout = self.layout::<F, _>(&mut file)?;
+        if self.verify_on_open {
+            let report = verify_file(&mut file, &layout)?;
+            if let Some(&(offset, saved, expected)) = report.mismatches.first() {
+                return Err(DatabaseError::CorruptedFrame {
+                    offset,
+                    saved,
+                    expected,
+                });
+            }
+            if let Some(&(_, saved, expected)) = report.mismatches64.first() {
+                return Err(DatabaseError::MismatchedChecksum64 { saved, expected });
+            }
+            if report.truncated {
+                return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+            }
+        }
+        Ok(Client {
+            file,
+            path: path.as_ref().to_path_buf(),
+            created,
+            layout,
+            flush_every: self.flush_every,
+            last_flush: Instant::now(),
+            scratch: Vec::new(),
+            write_through: self.write_through,
+            keep_backup: self.keep_backup,
+            unique: None,
+            appended: None,
+            pending,
+            lock,
+            _phantom: std::marker::PhantomData,
+        })
+    }
+
+    /// Creates the layout of an empty file, writing its header, or reads the layout of a
+    /// file that holds data, and applies the settings that are not stored in the header.
+    fn layout<F: Format, B: Backend>(&self, file: &mut B) -> Result<Layout, DatabaseError> {
+        let frame_compression = self.frame_compression || self.compression_level.is_some();
+        let mut layout = if file.size()? == 0 {
+            let mut layout = Layout {
+                endianness: self.endianness,
+                format: F::ID,
+                timestamps: self.timestamps,
+                tombstones: self.tombstones,
+                checksum_algorithm: Layout::custom_algorithm(self.checksum_algorithm),
+                alignment: self.alignment,
+                append_log: self.open_mode == OpenMode::Append,
+                block_size: (self.block_size > 1).then_some(self.block_size),
+                versions: self.frame_version.is_some(),
+                tags: self.frame_tags,
+                compression: frame_compression,
+                app_id: self.app_id,
+                crc64: self.checksum == Checksum::Crc64,
+                no_checksum: self.checksum == Checksum::None,
+                header: self.write_header,
+                ..Layout::default()
+            };
+            if layout.needs_header() {
+                file.write_all(&layout.encode_header())?;
+            }
+            layout
+        } else {
+            file.seek(SeekFrom::Start(0))?;
+            let layout = Layout::read(file, self.checksum_algorithm)?;
+            if layout.format != F::ID {
+                return Err(DatabaseError::FormatMismatch {
+                    expected: F::ID,
+                    found: layout.format,
+                });
+            }
+            if layout.record_size.is_some() {
+                return Err(DatabaseError::Unsupported(
+                    "file stores fixed-size documents, open it with a FixedClient",
+                ));
+            }
+            if let Some(expected) = self.app_id {
+                if layout.app_id != Some(expected) {
+                    return Err(DatabaseError::WrongAppId {
+                        expected,
+                        found: layout.app_id,
+                    });
+                }
+            }
+            layout
+        };
+        layout.deserialize_limit = self.deserialize_limit;
+        layout.max_record_size = self.max_record_size;
+        layout.reject_trailing_bytes = self.reject_trailing_bytes;
+        layout.version = self.frame_version.unwrap_or_default();
+        layout.verify_after_write = self.verify_after_write;
+        if self.compression_level.is_some() {
+            if !layout.compression {
+                return Err(DatabaseError::Unsupported(
+                    "file does not store per-document compression",
+                ));
+            }
+            layout.compress = true;
+            layout.compression_level = self.compression_level;
+        }
+        Ok(layout)
+    }
+
+    /// Opens the file itself, returning whether it was created.
+    pub(crate) fn open_file(&self, path: &Path) -> Result<(File, bool), DatabaseError> {
+        if let Ok(metadata) = std::fs::metadata(path) {
+            if !metadata.is_file() {
+                return Err(DatabaseError::NotAFile(path.to_path_buf()));
+            }
+        }
+        self.create_parent(path)?;
+        if self.probe_filesystem {
+            probe_filesystem(path)?;
+        }
+        if self.keep_backup
+            && self.open_mode == OpenMode::Overwrite
+            && std::fs::metadata(path).is_ok_and(|m| m.len() > 0)
+        {
+            backup_file(path)?;
+        }
+        let options = self.file_options()?;
+        match options.clone().create_new(true).open(path) {
+            Ok(file) => Ok((file, true)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                Ok((options.open(path).map_err(open_failed(path))?, false))
+            }
+            Err(e) => Err(open_failed(path)(e)),
+        }
+    }
+
+    /// Checks that the directory of the file at `path` exists, creating it if
+    /// [`create_dirs`](ClientBuilder::create_dirs) is enabled.
+    fn create_parent(&self, path: &Path) -> Result<(), DatabaseError> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.is_dir() {
+                if !self.create_dirs {
+                    return Err(DatabaseError::ParentMissing(parent.to_path_buf()));
+                }
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Opens an empty temporary file next to the file at `path`, which holds data and is
+    /// opened in overwrite mode, so that the data is only replaced once documents have
+    /// been written to the temporary file in full. Returns the path of the temporary file.
+    fn open_replacement(&self, path: &Path) -> Result<(File, PathBuf), DatabaseError> {
+        if self.probe_filesystem {
+            probe_filesystem(path)?;
+        }
+        if self.keep_backup {
+            backup_file(path)?;
+        }
+        let mut temp_path = path.to_path_buf().into_os_string();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+        let file = self
+            .file_options()?
+            .create(true)
+            .open(&temp_path)
+            .map_err(open_failed(&temp_path))?;
+        file.set_permissions(std::fs::metadata(path)?.permissions())?;
+        Ok((file, temp_path))
+    }
+
+    /// Returns the options to open the file with in the configured mode.
+    fn file_options(&self) -> Result<OpenOptions, DatabaseError> {
+        let mut options = OpenOptions::new();
+        options.read(true);
+        match self.open_mode {
+            OpenMode::Overwrite => options.write(true).truncate(true),
+            OpenMode::Append => options.append(true),
+            OpenMode::Update => options.write(true),
+        };
+        if self.write_through {
+            set_write_through(&mut options)?;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+
+            if let Some(mode) = self.mode.or(self.restrict_permissions.then_some(0o600)) {
+                options.mode(mode);
+            }
+            if self.custom_flags != 0 {
+                let write_through = if self.write_through { libc::O_DSYNC } else { 0 };
+                options.custom_flags(self.custom_flags | write_through);
+            }
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::OpenOptionsExt;
+
+            if let Some(share_mode) = self.share_mode {
+                options.share_mode(share_mode);
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// A read-only handle to the file backing a [`Client`], obtained via [`Client::reader`].
+///
+/// Readers do not share a cursor with the client or with each other, so they can be
+/// used from different threads at the same time.
+pub struct Reader<T: DeserializeOwned, F: Format = Bincode> {
+    file: File,
+    layout: Layout,
+    _phantom: std::marker::PhantomData<(T, F)>,
+}
+
+impl<T, F> Reader<T, F>
+where
+    T: DeserializeOwned,
+    F: Format,
+{
+    /// Returns a collection. If the file is empty, this method
+    /// returns `Ok(None)`. See [`Client::load`].
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::CorruptedFrame` error is
+    ///   returned.
+    ///
+    /// - `bincode::Error` if the deserializer fails to deserialize bytes from
+    ///   the file to your requested document type.
+    ///
+    /// - `std::io::Error`
+    pub fn load(&mut self) -> Result<Option<Vec<T>>, DatabaseError> {
+        Ok(load_file::<F, _, _>(&self.file, &self.layout, true)?)
+    }
+
+    /// Validates the checksums of the documents in the collection. See [`Client::validate`].
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn validate(&mut self) -> Result<bool, DatabaseError> {
+        validate_file(&mut self.file, &self.layout)
+    }
+
+    /// Checks the checksum of every frame in the file. See [`Client::verify`].
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn verify(&mut self) -> Result<VerifyReport, DatabaseError> {
+        verify_file(&mut self.file, &self.layout)
+    }
+}
+
+/// A read-only handle to a fixed prefix of the file backing a [`Client`], obtained via
+/// [`Client::snapshot_reader`].
+///
+/// The length of the file is recorded when the snapshot is taken and nothing past it is
+/// ever read, so every read sees the same documents even while documents are appended.
+/// Rewriting the file, e.g. by opening it in overwrite mode or vacuuming it, and removing
+/// documents in place are not guarded against.
+pub struct SnapshotReader<T: DeserializeOwned, F: Format = Bincode> {
+    file: File,
+    layout: Layout,
+    len: u64,
+    _phantom: std::marker::PhantomData<(T, F)>,
+}
+
+impl<T, F> SnapshotReader<T, F>
+where
+    T: DeserializeOwned,
+    F: Format,
+{
+    /// Returns the length of the file, in bytes, at the time the snapshot was taken.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the file was empty at the time the snapshot was taken.
+    pub fn is_empty(&self) -> bool {
+        self.len <= self.layout.data_start
+    }
+
+    /// Returns the documents in the snapshot. If the snapshot is empty, this method
+    /// returns `Ok(None)`. See [`Client::load`].
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::CorruptedFrame` error is
+    ///   returned.
+    ///
+    /// - `bincode::Error` if the deserializer fails to deserialize bytes from
+    ///   the file to your requested document type.
+    ///
+    /// - `std::io::Error`
+    pub fn load(&mut self) -> Result<Option<Vec<T>>, DatabaseError> {
+        let buf = read_file_until(&self.file, &self.layout, self.len)?;
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        let result = binary_to_vec::<F, _>(&buf, &self.layout, true)?;
+        Ok(Some(result))
+    }
+
+    /// Validates the checksums of the documents in the snapshot. See [`Client::validate`].
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn validate(&mut self) -> Result<bool, DatabaseError> {
+        let buf = read_file_until(&self.file, &self.layout, self.len)?;
+        validate_binary(&buf, &self.layout)
+    }
+
+    /// Checks the checksum of every frame in the snapshot. See [`Client::verify`].
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn verify(&mut self) -> Result<VerifyReport, DatabaseError> {
+        let buf = read_file_until(&self.file, &self.layout, self.len)?;
+        Ok(verify_binary(&buf, self.layout.data_start, &self.layout))
+    }
+}
+
+/// The result of [`Client::write`] and [`Client::write_many`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteReport {
+    /// Number of bytes appended to the file.
+    pub bytes: u64,
+    /// Whether the file held no documents before this write, e.g. because the client
+    /// created it. This is useful for side effects that should only happen on the
+    /// first write.
+    pub created: bool,
+}
+
+/// The header of a single frame, returned by [`Client::scan_frames`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameMeta {
+    /// Offset of the start of the frame in the file.
+    pub offset: u64,
+    /// Length of the frame body, which is the encoded document along with any per-frame
+    /// metadata such as a timestamp.
+    pub len: u32,
+    /// Checksum saved for the frame body.
+    pub checksum: u32,
+    /// Whether the document has been removed with [`Client::remove_at`] or
+    /// [`Client::remove_where`].
+    pub removed: bool,
+}
+
+/// A region of the file skipped by [`Client::load_lenient`], which starts with a frame
+/// that is incomplete, fails its checksum or does not decode, and ends where the next
+/// intact frame starts or at the end of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CorruptFrame {
+    /// Offset of the start of the region, and of the failing frame, in the file.
+    pub offset: u64,
+    /// Number of bytes skipped.
+    pub len: u64,
+    /// Checksum saved in the header of the failing frame, or `None` if the file ends in
+    /// the middle of the header.
+    pub saved: Option<u32>,
+    /// Checksum of the body of the failing frame, or `None` if the file ends in the
+    /// middle of the body. It equals `saved` if the frame is intact but does not decode.
+    pub expected: Option<u32>,
+}
+
+/// The result of [`Client::verify`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Number of frames whose saved checksum matches their contents.
+    pub valid: usize,
+    /// `(offset, saved, expected)` for every frame whose saved checksum does not match
+    /// its contents, in file order. The offset points to the start of the frame.
+    pub mismatches: Vec<(u64, u32, u32)>,
+    /// `(offset, saved, expected)` for every frame of a file with [`Checksum::Crc64`]
+    /// whose frame checksum matches but whose 64-bit checksum does not, in file order. A
+    /// frame too short to hold the 64-bit checksum is listed with a saved checksum of 0.
+    pub mismatches64: Vec<(u64, u64, u64)>,
+    /// Whether the file ends in the middle of a frame.
+    pub truncated: bool,
+}
+
+impl VerifyReport {
+    /// Returns the number of corrupted frames.
+    pub fn corrupted(&self) -> usize {
+        self.mismatches.len() + self.mismatches64.len()
+    }
+
+    /// Returns `true` if no frame is corrupted and the file is not truncated.
+    pub fn is_ok(&self) -> bool {
+        self.corrupted() == 0 && !self.truncated
+    }
+
+    /// Returns the offset of the first corrupted frame, if any.
+    fn first_corrupted(&self) -> Option<u64> {
+        let first = self.mismatches.first().map(|mismatch| mismatch.0);
+        let first64 = self.mismatches64.first().map(|mismatch| mismatch.0);
+        first.into_iter().chain(first64).min()
+    }
+}
+
+/// Checks the checksum of every frame of the file at `path` like [`Client::verify`],
+/// without a client and thus without knowing the type of its documents or their
+/// [`Format`], e.g. for a health check that covers files of any type. The file is only
+/// opened for reading.
+///
+/// # Errors
+///
+/// - `DatabaseError::Open` if the file cannot be opened.
+///
+/// - `DatabaseError::ChecksumAlgorithmMismatch` if the file uses a custom checksum
+///   algorithm, which is not recorded in its header.
+///
+/// - `DatabaseError::Unsupported` for files of fixed-size documents.
+///
+/// - See [`inspect_header`] and [`Client::verify`].
+pub fn verify_path<P: AsRef<Path>>(path: P) -> Result<VerifyReport, DatabaseError> {
+    let path = path.as_ref();
+    let mut file = File::open(path).map_err(open_failed(path))?;
+    let layout = Layout::read(&mut file, None)?;
+    if layout.record_size.is_some() {
+        return Err(DatabaseError::Unsupported(
+            "file stores fixed-size documents, open it with a FixedClient",
+        ));
+    }
+    verify_file(&mut file, &layout)
+}
+
+/// Attaches `path` to an error that occurred while opening it.
+fn open_failed(path: &Path) -> impl FnOnce(std::io::Error) -> DatabaseError + '_ {
+    |source| DatabaseError::Open {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+/// Returns `true` if the file at `path` is an append log that contains documents.
+fn is_append_log(path: &Path, checksum_algorithm: Option<&'static Algorithm<u32>>) -> bool {
+    let Ok(mut file) = File::open(path) else {
+        return false;
+    };
+    let Ok(len) = file.metadata().map(|metadata| metadata.len()) else {
+        return false;
+    };
+    Layout::read(&mut file, checksum_algorithm)
+        .is_ok_and(|layout| layout.append_log && len > layout.data_start)
+}
+
+/// Returns the application identifier recorded in the header of the file at `path`, if
+/// it can be read and records one.
+fn recorded_app_id(
+    path: &Path,
+    checksum_algorithm: Option<&'static Algorithm<u32>>,
+) -> Option<[u8; 8]> {
+    let mut file = File::open(path).ok()?;
+    Layout::read(&mut file, checksum_algorithm).ok()?.app_id
+}
+
+fn read_file<B: Backend>(file: &B, layout: &Layout) -> Result<Vec<u8>, DatabaseError> {
+    let end = data_end(file, layout)?;
+    read_file_until(file, layout, end)
+}
+
+/// The footer written by [`Client::finalize`].
+struct Footer {
+    offset: u64,
+    checksum: u32,
+    data_len: u64,
+}
+
+impl Footer {
+    /// Returns `true` if the footer records the size of the documents in front of it.
+    /// Otherwise, frames were added or dropped after the footer was written, or the last
+    /// bytes of a document merely happen to look like a footer.
+    fn matches(&self, layout: &Layout) -> bool {
+        self.data_len == self.offset - layout.data_start
+    }
+}
+
+/// Returns the footer the file ends with, if any. See [`Footer::matches`].
+fn read_footer<B: Backend>(file: &B, layout: &Layout) -> Result<Option<Footer>, DatabaseError> {
+    let len = file_end(file, layout)?;
+    let Some(offset) = len
+        .checked_sub(FOOTER_LEN as u64)
+        .filter(|&offset| offset >= layout.data_start)
+    else {
+        return Ok(None);
+    };
+    let mut footer = [0; FOOTER_LEN];
+    ReadAt { file, offset }.read_exact(&mut footer)?;
+    if footer[..4] != FOOTER_MAGIC {
+        return Ok(None);
+    }
+    Ok(Some(Footer {
+        offset,
+        checksum: layout.endianness.u32_from_slice(&footer[4..8]),
+        data_len: layout.endianness.u64_from_slice(&footer[8..]),
+    }))
+}
+
+/// Returns the footer the file ends with if it matches the documents in front of it.
+fn valid_footer<B: Backend>(file: &B, layout: &Layout) -> Result<Option<Footer>, DatabaseError> {
+    Ok(read_footer(file, layout)?.filter(|footer| footer.matches(layout)))
+}
+
+/// Returns the offset at which the documents of the file end, which is the end of the
+/// file unless it ends with a footer.
+fn data_end<B: Backend>(file: &B, layout: &Layout) -> Result<u64, DatabaseError> {
+    match valid_footer(file, layout)? {
+        Some(footer) => Ok(footer.offset),
+        None => file_end(file, layout),
+    }
+}
+
+/// Returns the end of the file, or of the region of the file the client was opened on.
+fn file_end<B: Backend>(file: &B, layout: &Layout) -> Result<u64, DatabaseError> {
+    let len = file.size()?;
+    Ok(layout.region_end.map_or(len, |end| end.min(len)))
+}
+
+/// Truncates the footer of the file, if any, before documents are added or changed, and
+/// leaves the cursor at the new end of the file, whose offset is returned.
+fn strip_footer<B: Backend>(file: &mut B, layout: &Layout) -> Result<u64, DatabaseError> {
+    layout.require_whole_file()?;
+    if let Some(footer) = valid_footer(file, layout)? {
+        file.set_len(footer.offset)?;
+    }
+    Ok(file.seek(SeekFrom::End(0))?)
+}
+
+/// Reads the documents of a file, ignoring everything at or past offset `end`.
+fn read_file_until<B: Backend>(
+    file: &B,
+    layout: &Layout,
+    end: u64,
+) -> Result<Vec<u8>, DatabaseError> {
+    let mut buf = Vec::new();
+    let offset = layout.data_start;
+    ReadAt { file, offset }
+        .take(end.saturating_sub(offset))
+        .read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Reads a file from `offset` on with positioned reads, which leave the cursor of the
+/// file where it is, so that a client can read through a shared reference, even from
+/// several threads at once.
+struct ReadAt<'a, B> {
+    file: &'a B,
+    offset: u64,
+}
+
+impl<B: Backend> Read for ReadAt<'_, B> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.file.read_at(buf, self.offset)?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
+
+fn load_file<F: Format, T: DeserializeOwned, B: Backend>(
+    file: &B,
+    layout: &Layout,
+    verify_checksums: bool,
+) -> Result<Option<Vec<T>>, LoadError<T>> {
+    let buf = read_file(file, layout)?;
+    if buf.is_empty() {
+        return Ok(None);
+    }
+    let result = binary_to_vec::<F, _>(&buf, layout, verify_checksums)?;
+    Ok(Some(result))
+}
+
+fn validate_file<B: Backend>(file: &mut B, layout: &Layout) -> Result<bool, DatabaseError> {
+    let buf = read_file(file, layout)?;
+    validate_binary(&buf, layout)
+}
+
+fn validate_binary(mut raw_data: &[u8], layout: &Layout) -> Result<bool, DatabaseError> {
+    while let Some(frame) = FrameRef::split(&mut raw_data, layout) {
+        match frame.verify(layout) {
+            Ok(_) => (),
+            Err(
+                DatabaseError::MismatchedChecksum { .. }
+                | DatabaseError::MismatchedChecksum64 { .. },
+            ) => return Ok(false),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+fn verify_file<B: Backend>(file: &mut B, layout: &Layout) -> Result<VerifyReport, DatabaseError> {
+    let buf = read_file(file, layout)?;
+    Ok(verify_binary(&buf, layout.data_start, layout))
+}
+
+/// Returns the number of documents in the file. Unless the file has blocks, this only
+/// reads the frame headers.
+fn count_documents<B: Backend>(file: &mut B, layout: &Layout) -> Result<usize, DatabaseError> {
+    if layout.block_size.is_none() {
+        let mut count = 0;
+        walk_frames(file, layout, |frame| {
+            count += usize::from(!frame.removed);
+            ControlFlow::Continue(())
+        })?;
+        return Ok(count);
+    }
+    let buf = read_file(file, layout)?;
+    let mut raw_data = buf.as_slice();
+    let mut count = 0;
+    while let Some(frame) = FrameRef::split(&mut raw_data, layout) {
+        if !frame.is_complete() {
+            return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+        }
+        for document in layout.split_documents(frame.data) {
+            document?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Returns the offset of every frame that has not been removed, without reading any
+/// payloads.
+fn frame_offsets<B: Backend>(file: &mut B, layout: &Layout) -> Result<Vec<u64>, DatabaseError> {
+    layout.require_frame_per_document()?;
+    Ok(scan_file(file, layout)?
+        .into_iter()
+        .filter(|frame| !frame.removed)
+        .map(|frame| frame.offset)
+        .collect())
+}
+
+/// Returns the header of every frame of a file. See `walk_frames`.
+fn scan_file<B: Backend>(file: &mut B, layout: &Layout) -> Result<Vec<FrameMeta>, DatabaseError> {
+    let mut frames = Vec::new();
+    walk_frames(file, layout, |frame| {
+        frames.push(frame);
+        ControlFlow::Continue(())
+    })?;
+    Ok(frames)
+}
+
+/// Walks the frame headers of a file, seeking past every payload, and passes each one to
+/// `f` until it breaks.
+fn walk_frames<B: Backend, G>(file: &B, layout: &Layout, mut f: G) -> Result<(), DatabaseError>
+where
+    G: FnMut(FrameMeta) -> ControlFlow<()>,
+{
+    let end = data_end(file, layout)?;
+    let mut offset = layout.data_start;
+    while offset < end {
+        let mut header = ReadAt { file, offset };
+        let checksum = layout.endianness.read_u32(&mut header)?;
+        let len = layout.endianness.read_u32(&mut header)?;
+        let removed = layout.tombstones && read_status(&mut header)? & STATUS_REMOVED != 0;
+        let next = offset + layout.frame_len(len);
+        if next > end {
+            return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+        }
+        let frame = FrameMeta {
+            offset,
+            len,
+            checksum,
+            removed,
+        };
+        if f(frame).is_break() {
+            break;
+        }
+        offset = next;
+    }
+    Ok(())
+}
+
+fn read_document_at<F: Format, T: DeserializeOwned, B: Backend>(
+    file: &B,
+    layout: &Layout,
+    offset: u64,
+) -> Result<T, DatabaseError> {
+    let raw_doc = process_document(&mut ReadAt { file, offset }, layout)?;
+    let (extras, data) = layout.split_body(&raw_doc)?;
+    layout.decode::<F, T>(&layout.frame_data(extras, data)?)
+}
+
+/// Checks every frame of `raw_data`, reporting offsets relative to `base`.
+fn verify_binary(raw_data: &[u8], base: u64, layout: &Layout) -> VerifyReport {
+    let mut report = VerifyReport::default();
+    let header_len = layout.frame_header_len() as usize;
+    let mut offset = 0;
+    while offset < raw_data.len() {
+        let Some(header) = raw_data.get(offset..offset + header_len) else {
+            report.truncated = true;
+            break;
+        };
+        let saved = layout.endianness.u32_from_slice(&header[0..4]);
+        let body_len = layout.endianness.u32_from_slice(&header[4..8]);
+        let data_len = body_len as usize;
+        let start = offset + header_len;
+        let Some(data) = raw_data.get(start..start.saturating_add(data_len)) else {
+            report.truncated = true;
+            break;
+        };
+        let frame_offset = base + offset as u64;
+        match check_checksum(layout, saved, data) {
+            Ok(()) => report.valid += 1,
+            Err(DatabaseError::MismatchedChecksum { saved, expected }) => {
+                report.mismatches.push((frame_offset, saved, expected));
+            }
+            Err(DatabaseError::MismatchedChecksum64 { saved, expected }) => {
+                report.mismatches64.push((frame_offset, saved, expected));
+            }
+            Err(_) => {
+                let expected = layout.checksum64(data);
+                report.mismatches64.push((frame_offset, 0, expected));
+            }
+        }
+        offset = start + data_len + layout.padding(body_len) as usize;
+    }
+    report
+}
+
+fn binary_to_vec<F: Format, T: DeserializeOwned>(
+    raw_data: &[u8],
+    layout: &Layout,
+    verify_checksums: bool,
+) -> Result<Vec<T>, LoadError<T>> {
+    let mut result = Vec::new();
+    match decode_binary::<F, T, _>(raw_data, layout, verify_checksums, |_, document| {
+        result.push(document);
+    }) {
+        Ok(()) => Ok(result),
+        Err(DatabaseError::CorruptedFrame {
+            offset,
+            saved,
+            expected,
+        }) => Err(LoadError::Poisoned(DataPoisonError {
+            documents: result,
+            offset,
+            saved,
+            expected,
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Decodes every frame of `raw_data`, passing the frame metadata and the document to `f`.
+fn decode_binary<F, T, G>(
+    raw_data: &[u8],
+    layout: &Layout,
+    verify_checksums: bool,
+    mut f: G,
+) -> Result<(), DatabaseError>
+where
+    F: Format,
+    T: DeserializeOwned,
+    G: FnMut(&[u8], T),
+{
+    if !raw_data.is_empty() && raw_data.len() < layout.frame_header_len() as usize {
+        return Err(DatabaseError::TruncatedHeader(raw_data.len()));
+    }
+    for (index, (offset, frame)) in split_frames(raw_data, layout).enumerate() {
+        decode_frame::<F, T, _>(&frame, index, offset, layout, verify_checksums, &mut f)?;
+    }
+    Ok(())
+}
+
+/// Splits `raw_data`, which holds the documents of a file from its first frame on, into
+/// frames along with the offsets in the file at which they start.
+fn split_frames<'a>(
+    mut raw_data: &'a [u8],
+    layout: &'a Layout,
+) -> impl Iterator<Item = (u64, FrameRef<'a>)> + 'a {
+    let len = raw_data.len();
+    std::iter::from_fn(move || {
+        let offset = layout.data_start + (len - raw_data.len()) as u64;
+        FrameRef::split(&mut raw_data, layout).map(|frame| (offset, frame))
+    })
+}
+
+/// Decodes the frame at `index`, which starts at `offset` in the file, passing the frame
+/// metadata and every document in it to `f`. A removed frame has no documents.
+fn decode_frame<F, T, G>(
+    frame: &FrameRef,
+    index: usize,
+    offset: u64,
+    layout: &Layout,
+    verify_checksums: bool,
+    f: &mut G,
+) -> Result<(), DatabaseError>
+where
+    F: Format,
+    T: DeserializeOwned,
+    G: FnMut(&[u8], T),
+{
+    if frame.removed {
+        return Ok(());
+    }
+    let raw_doc = if verify_checksums {
+        frame
+            .verify(layout)
+            .map_err(in_frame(layout, index, offset))?
+    } else if !frame.is_complete() {
+        return Err(DatabaseError::TruncatedFrame {
+            offset,
+            expected_len: frame.data_len,
+            available: frame.data.len() as u64,
+        });
+    } else {
+        frame.data
+    };
+    let (extras, data) = layout.split_body(raw_doc)?;
+    let data = layout.frame_data(extras, data)?;
+    for document in layout.split_documents(&data) {
+        f(extras, layout.decode::<F, T>(document?)?);
+    }
+    Ok(())
+}
+
+/// Decodes the frames of `raw_data` like `binary_to_vec`, split into one contiguous run
+/// of frames per available thread. The runs are joined in order, and each one stops at
+/// its first error, so the error returned is always that of the first failing frame.
+fn binary_to_vec_parallel<F, T>(raw_data: &[u8], layout: &Layout) -> Result<Vec<T>, DatabaseError>
+where
+    F: Format,
+    T: DeserializeOwned + Send,
+{
+    let frames: Vec<_> = split_frames(raw_data, layout).collect();
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    let run_len = frames.len().div_ceil(threads).max(1);
+    std::thread::scope(|scope| {
+        let runs: Vec<_> = frames
+            .chunks(run_len)
+            .enumerate()
+            .map(|(run, frames)| {
+                scope.spawn(move || {
+                    let mut documents = Vec::new();
+                    for (i, (offset, frame)) in frames.iter().enumerate() {
+                        decode_frame::<F, T, _>(
+                            frame,
+                            run * run_len + i,
+                            *offset,
+                            layout,
+                            true,
+                            &mut |_, document| documents.push(document),
+                        )?;
+                    }
+                    Ok(documents)
+                })
+            })
+            .collect();
+        let mut result = Vec::new();
+        for run in runs {
+            let documents: Result<Vec<T>, DatabaseError> = match run.join() {
+                Ok(documents) => documents,
+                Err(panic) => std::panic::resume_unwind(panic),
+            };
+            result.extend(documents?);
+        }
+        Ok(result)
+    })
+}
+
+/// Decodes every intact frame of `raw_data`, which starts at offset `base` in the file,
+/// moving on by a single byte wherever a frame is not intact, and returns the documents
+/// along with the regions that were skipped.
+fn resync_binary<F: Format, T: DeserializeOwned>(
+    raw_data: &[u8],
+    base: u64,
+    layout: &Layout,
+) -> (Vec<T>, Vec<CorruptFrame>) {
+    let mut documents = Vec::new();
+    let mut corrupt: Vec<CorruptFrame> = Vec::new();
+    let mut in_region = false;
+    let mut rest = raw_data;
+    while !rest.is_empty() {
+        let mut next = rest;
+        let frame = FrameRef::split(&mut next, layout);
+        match frame
+            .as_ref()
+            .and_then(|frame| decode_intact::<F, T>(frame, layout))
+        {
+            Some(decoded) => {
+                documents.extend(decoded);
+                rest = next;
+                in_region = false;
+            }
+            None => {
+                match corrupt.last_mut() {
+                    Some(region) if in_region => region.len += 1,
+                    _ => corrupt.push(CorruptFrame {
+                        offset: base + (raw_data.len() - rest.len()) as u64,
+                        len: 1,
+                        saved: frame.as_ref().map(|frame| frame.saved),
+                        expected: frame
+                            .filter(FrameRef::is_complete)
+                            .map(|frame| layout.checksum(frame.data)),
+                    }),
+                }
+                in_region = true;
+                rest = &rest[1..];
+            }
+        }
+    }
+    (documents, corrupt)
+}
+
+/// Returns the documents of `frame` if it is complete, matches its checksum and decodes
+/// into `T`. A removed frame has no documents.
+fn decode_intact<F: Format, T: DeserializeOwned>(
+    frame: &FrameRef,
+    layout: &Layout,
+) -> Option<Vec<T>> {
+    if !frame.is_complete() {
+        return None;
+    }
+    let body = frame.verify(layout).ok()?;
+    if frame.removed {
+        return Some(Vec::new());
+    }
+    let (extras, data) = layout.split_body(body).ok()?;
+    let data = layout.frame_data(extras, data).ok()?;
+    layout
+        .split_documents(&data)
+        .map(|document| layout.decode::<F, T>(document.ok()?).ok())
+        .collect()
+}
+
+/// Passes the encoded bytes of every document in the file to `f` until it breaks, and
+/// returns whether it did. Removed documents are skipped.
+fn for_each_encoded<B: Backend, G>(
+    file: &mut B,
+    layout: &Layout,
+    mut f: G,
+) -> Result<ControlFlow<()>, DatabaseError>
+where
+    G: FnMut(&[u8]) -> ControlFlow<()>,
+{
+    let buf = read_file(file, layout)?;
+    let mut raw_data = buf.as_slice();
+    let mut index = 0;
+    while let Some(frame) = FrameRef::split(&mut raw_data, layout) {
+        index += 1;
+        if frame.removed {
+            continue;
+        }
+        let body = frame.verify(layout).map_err(in_block(layout, index - 1))?;
+        let (extras, data) = layout.split_body(body)?;
+        let data = layout.frame_data(extras, data)?;
+        for document in layout.split_documents(&data) {
+            if f(document?).is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+    }
+    Ok(ControlFlow::Continue(()))
+}
+
+/// Turns a checksum mismatch of the frame at `index` into a `CorruptedBlock` error if the
+/// file has blocks, since the frame is then a whole block of documents.
+fn in_block(layout: &Layout, index: usize) -> impl FnOnce(DatabaseError) -> DatabaseError {
+    let blocks = layout.block_size.is_some();
+    move |e| match e {
+        DatabaseError::MismatchedChecksum { saved, expected } if blocks => {
+            DatabaseError::CorruptedBlock {
+                index,
+                saved,
+                expected,
+            }
+        }
+        e => e,
+    }
+}
+
+/// Like `in_block`, but turns a checksum mismatch of a frame without blocks, which starts
+/// at `offset` in the file, into a `CorruptedFrame` error.
+fn in_frame(
+    layout: &Layout,
+    index: usize,
+    offset: u64,
+) -> impl FnOnce(DatabaseError) -> DatabaseError {
+    let in_block = in_block(layout, index);
+    move |e| match in_block(e) {
+        DatabaseError::MismatchedChecksum { saved, expected } => DatabaseError::CorruptedFrame {
+            offset,
+            saved,
+            expected,
+        },
+        e => e,
+    }
+}
+
+/// A frame as it is stored on disk, before its checksum is checked.
+struct RawFrame {
+    saved: u32,
+    data_len: u32,
+    removed: bool,
+    data: Vec<u8>,
+}
+
+impl RawFrame {
+    /// Returns the payload of this frame if it matches the saved checksum.
+    fn verify(self, layout: &Layout) -> Result<Vec<u8>, DatabaseError> {
+        check_checksum(layout, self.saved, &self.data)?;
+        Ok(self.data)
+    }
+}
+
+/// A frame borrowed from a buffer holding the documents of a whole file, so that
+/// decoding a file of many small documents does not allocate for every frame. Like the
+/// data of a `RawFrame`, `data` is cut short if the buffer ends in the middle of it.
+struct FrameRef<'a> {
+    saved: u32,
+    data_len: u32,
+    removed: bool,
+    data: &'a [u8],
+}
+
+impl<'a> FrameRef<'a> {
+    /// Splits the next frame, along with its padding, off the front of `raw_data`.
+    /// Returns `None` once not even the header of a frame is left, which is where
+    /// `read_frame` fails with `UnexpectedEof`.
+    fn split(raw_data: &mut &'a [u8], layout: &Layout) -> Option<Self> {
+        let header_len = layout.frame_header_len() as usize;
+        if raw_data.len() < header_len {
+            return None;
+        }
+        let (header, rest) = raw_data.split_at(header_len);
+        let data_len = layout.endianness.u32_from_slice(&header[4..8]);
+        let (data, rest) = rest.split_at(rest.len().min(data_len as usize));
+        let padding = rest.len().min(layout.padding(data_len) as usize);
+        *raw_data = &rest[padding..];
+        Some(Self {
+            saved: layout.endianness.u32_from_slice(&header[..4]),
+            data_len,
+            removed: layout.tombstones && header[8] & STATUS_REMOVED != 0,
+            data,
+        })
+    }
+
+    fn is_complete(&self) -> bool {
+        self.data.len() == self.data_len as usize
+    }
+
+    /// Returns the payload of this frame if it matches the saved checksum.
+    fn verify(&self, layout: &Layout) -> Result<&'a [u8], DatabaseError> {
+        check_checksum(layout, self.saved, self.data)?;
+        Ok(self.data)
+    }
+}
+
+fn check_checksum(layout: &Layout, saved: u32, data: &[u8]) -> Result<(), DatabaseError> {
+    let expected = layout.checksum(data);
+    if expected != saved {
+        return Err(DatabaseError::MismatchedChecksum { saved, expected });
+    }
+    if layout.crc64 {
+        let (extras, data) = layout.split_body(data)?;
+        let saved = layout
+            .endianness
+            .u64_from_slice(&extras[extras.len() - 8..]);
+        let expected = layout.checksum64(data);
+        if expected != saved {
+            return Err(DatabaseError::MismatchedChecksum64 { saved, expected });
+        }
+    }
+    Ok(())
+}
+
+/// Reads a single frame from a stream, copying its body into a buffer of its own. This is
+/// the path for reading a file one document at a time; documents that are already in
+/// memory are parsed with [`FrameRef`] instead, which borrows them.
+///
+/// Like every other IO path of this crate, this only goes through `read_exact`,
+/// `read_to_end` and `io::copy`, which retry reads failing with `ErrorKind::Interrupted`.
+fn read_frame<R: Read>(f: &mut R, layout: &Layout) -> Result<RawFrame, DatabaseError> {
+    let saved = layout.endianness.read_u32(f)?;
+    let data_len = layout.endianness.read_u32(f)?;
+    let removed = layout.tombstones && read_status(f)? & STATUS_REMOVED != 0;
+    if let Some(max) = layout.max_record_size.filter(|&max| data_len > max) {
+        return Err(DatabaseError::RecordTooLarge {
+            declared: data_len,
+            max,
+        });
+    }
+    // The buffer grows as the body is read, so a corrupted length that points past the
+    // end of the file does not allocate all of it upfront.
+    let mut data = Vec::with_capacity((data_len as usize).min(PREALLOC_LIMIT));
+    f.take(u64::from(data_len)).read_to_end(&mut data)?;
+    std::io::copy(&mut f.take(layout.padding(data_len)), &mut std::io::sink())?;
+    Ok(RawFrame {
+        saved,
+        data_len,
+        removed,
+        data,
+    })
+}
+
+fn read_status<R: Read>(f: &mut R) -> std::io::Result<u8> {
+    let mut status = [0];
+    f.read_exact(&mut status)?;
+    Ok(status[0])
+}
+
+fn process_document<R: Read>(f: &mut R, layout: &Layout) -> Result<Vec<u8>, DatabaseError> {
+    read_frame(f, layout)?.verify(layout)
+}
+
+fn vec_to_binary<F: Format, T: Serialize>(
+    data: &[T],
+    layout: &Layout,
+) -> Result<Vec<u8>, DatabaseError> {
+    let mut buf = Vec::new();
+    write_encoded::<F, _, T, _>(&mut buf, data, layout, &mut Vec::new())?;
+    Ok(buf)
+}
+
+/// The largest buffer allocated for the body of a frame before any of it is read.
+const PREALLOC_LIMIT: usize = 64 * 1024;
+
+/// Scratch buffers that grew larger than this while encoding a document are freed
+/// afterwards instead of being kept around for the next one.
+const SCRATCH_RETAIN: usize = 64 * 1024;
+
+/// Encodes every document, or every block of documents if the file has blocks, as a
+/// whole frame into `scratch`, which is cleared but not freed between frames, and writes
+/// it to `w` in a single call.
+fn write_encoded<F, W, T, I>(
+    w: &mut W,
+    documents: I,
+    layout: &Layout,
+    scratch: &mut Vec<u8>,
+) -> Result<(), DatabaseError>
+where
+    F: Format,
+    W: Write,
+    T: Serialize,
+    I: IntoIterator,
+    I::Item: Borrow<T>,
+{
+    let result = encode_frames::<F, _, T, _>(w, documents, layout, scratch);
+    if scratch.capacity() > SCRATCH_RETAIN {
+        *scratch = Vec::new();
+    }
+    result
+}
+
+fn encode_frames<F, W, T, I>(
+    w: &mut W,
+    documents: I,
+    layout: &Layout,
+    scratch: &mut Vec<u8>,
+) -> Result<(), DatabaseError>
+where
+    F: Format,
+    W: Write,
+    T: Serialize,
+    I: IntoIterator,
+    I::Item: Borrow<T>,
+{
+    let header_len = layout.frame_header_len() as usize;
+    let per_frame = layout.block_size.unwrap_or(1);
+    let mut in_frame = 0;
+    for document in documents {
+        if in_frame == 0 {
+            scratch.clear();
+            scratch.resize(header_len, 0);
+            if layout.timestamps {
+                layout.endianness.write_u64(scratch, unix_millis())?;
+            }
+            if layout.versions {
+                scratch.push(layout.version);
+            }
+            if layout.tags {
+                layout.endianness.write_u16(scratch, layout.tag)?;
+            }
+            if layout.compression {
+                scratch.push(layout.frame_flags());
+            }
+            if layout.crc64 {
+                scratch.extend_from_slice(&[0; 8]);
+            }
+        }
+        if layout.block_size.is_some() {
+            let start = scratch.len();
+            scratch.extend_from_slice(&[0; 4]);
+            F::serialize_into(scratch, document.borrow())?;
+            let len = u32::try_from(scratch.len() - start - 4)?;
+            layout
+                .endianness
+                .write_u32(&mut &mut scratch[start..start + 4], len)?;
+        } else {
+            F::serialize_into(scratch, document.borrow())?;
+        }
+        in_frame += 1;
+        if in_frame == per_frame {
+            if layout.compress {
+                compress_frame(scratch, layout)?;
+            }
+            fill_checksum64(scratch, layout)?;
+            finish_frame(scratch, layout)?;
+            w.write_all(scratch)?;
+            in_frame = 0;
+        }
+    }
+    if in_frame > 0 {
+        fill_checksum64(scratch, layout)?;
+        finish_frame(scratch, layout)?;
+        w.write_all(scratch)?;
+    }
+    Ok(())
+}
+
+/// Replaces the encoded document at the end of `scratch`, which holds room for a frame
+/// header followed by a frame body, with its compressed form.
+fn compress_frame(scratch: &mut Vec<u8>, layout: &Layout) -> Result<(), DatabaseError> {
+    let start = layout.frame_header_len() as usize + layout.extras_len();
+    let compressed = deflate(&scratch[start..], layout.compression_level)?;
+    scratch.truncate(start);
+    scratch.extend_from_slice(&compressed);
+    Ok(())
+}
+
+/// Fills in the 64-bit checksum at the end of the metadata of the frame body in
+/// `scratch` once its document is final, if the file has them.
+fn fill_checksum64(scratch: &mut [u8], layout: &Layout) -> Result<(), DatabaseError> {
+    if !layout.crc64 {
+        return Ok(());
+    }
+    let start = layout.frame_header_len() as usize + layout.extras_len();
+    let checksum = layout.checksum64(&scratch[start..]);
+    layout
+        .endianness
+        .write_u64(&mut &mut scratch[start - 8..start], checksum)?;
+    Ok(())
+}
+
+/// Turns `scratch`, which holds room for a frame header followed by a frame body, into a
+/// whole frame by filling in the header and appending the padding.
+fn finish_frame(scratch: &mut Vec<u8>, layout: &Layout) -> Result<(), DatabaseError> {
+    let header_len = layout.frame_header_len() as usize;
+    let body_len = u32::try_from(scratch.len() - header_len)?;
+    let checksum = layout.checksum(&scratch[header_len..]);
+    layout
+        .endianness
+        .write_u32(&mut &mut scratch[..4], checksum)?;
+    layout
+        .endianness
+        .write_u32(&mut &mut scratch[4..8], body_len)?;
+    scratch.resize(scratch.len() + layout.padding(body_len) as usize, 0);
+    Ok(())
+}
+
+/// Appends frames like [`append_or_rollback`], and reads them back to check their
+/// checksums first if the client verifies its writes, undoing the write if one does not
+/// match.
+fn append_frames<B: Backend, G>(
+    file: &mut B,
+    layout: &Layout,
+    len: u64,
+    write: G,
+) -> Result<(), DatabaseError>
+where
+    G: FnOnce(&mut B) -> Result<(), DatabaseError>,
+{
+    append_or_rollback(file, len, |file| {
+        write(file)?;
+        if layout.verify_after_write {
+            verify_written(file, layout, len)?;
+        }
+        Ok(())
+    })
+}
+
+/// Reads the frames from `len` to the end of `file` back and checks their checksums,
+/// leaving the cursor at the end of the file.
+fn verify_written<B: Backend>(
+    file: &mut B,
+    layout: &Layout,
+    len: u64,
+) -> Result<(), DatabaseError> {
+    file.seek(SeekFrom::Start(len))?;
+    let mut written = Vec::new();
+    file.read_to_end(&mut written)?;
+    let report = verify_binary(&written, len, layout);
+    if let Some(offset) = report.first_corrupted() {
+        return Err(DatabaseError::WriteVerifyFailed { offset });
+    }
+    if report.truncated {
+        return Err(DatabaseError::WriteVerifyFailed { offset: len });
+    }
+    Ok(())
+}
+
+/// Runs `write`, which appends to `file`, and truncates `file` back to `len`, its length
+/// before the write, if `write` fails. Every append goes through this, so that a write
+/// that fails halfway, e.g. because the disk is full, never leaves the file ending in the
+/// middle of a frame.
+fn append_or_rollback<B: Backend, G>(file: &mut B, len: u64, write: G) -> Result<(), DatabaseError>
+where
+    G: FnOnce(&mut B) -> Result<(), DatabaseError>,
+{
+    if let Err(e) = write(file) {
+        file.set_len(len)?;
+        file.seek(SeekFrom::End(0))?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Atomically replaces the file at `path`, which `file` is an open handle to, with a new
+/// file whose contents are written by `write`. On success, `file` is a handle to the new
+/// file.
+fn replace_file<G>(file: &mut File, path: &Path, write: G) -> Result<(), DatabaseError>
+where
+    G: FnOnce(&mut File) -> Result<(), DatabaseError>,
+{
+    let mut temp_path = path.to_path_buf().into_os_string();
+    temp_path.push(".tmp");
+    let mut temp = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&temp_path)
+        .map_err(open_failed(Path::new(&temp_path)))?;
+    let result = file
+        .metadata()
+        .and_then(|metadata| temp.set_permissions(metadata.permissions()))
+        .map_err(DatabaseError::from)
+        .and_then(|()| write(&mut temp))
+        .and_then(|()| Ok(temp.sync_all()?));
+    if let Err(e) = result {
+        drop(temp);
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    // Windows refuses to replace a file that this process still has open, so the handle to
+    // the old file is closed first. The handle to the temporary file stays valid across
+    // the rename and becomes the handle to the new file.
+    drop(std::mem::replace(file, temp));
+    if let Err(e) = rename_over(temp_path.as_ref(), path) {
+        *file = OpenOptions::new().read(true).write(true).open(path)?;
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Replaces the contents of `backend` with the bytes written by `write`, atomically with
+/// `replace_file` if it is a file. Any other backend is rewritten once `write` succeeded,
+/// so it is left untouched if `write` fails.
+fn replace_contents<B, G>(backend: &mut B, path: &Path, write: G) -> Result<(), DatabaseError>
+where
+    B: Backend,
+    G: FnOnce(&mut dyn Write) -> Result<(), DatabaseError>,
+{
+    if let Some(file) = backend.as_file() {
+        return replace_file(file, path, |temp| write(temp));
+    }
+    let mut contents = Vec::new();
+    write(&mut contents)?;
+    backend.set_len(0)?;
+    backend.seek(SeekFrom::Start(0))?;
+    Ok(backend.write_all(&contents)?)
+}
+
+/// The temporary file that a client opened in overwrite mode writes to instead of the
+/// file at `path`, which it replaces at the latest when dropped, e.g. along with a client
+/// that never wrote to it, as if the file had been truncated when it was opened.
+struct Replacement {
+    temp_path: PathBuf,
+    path: PathBuf,
+    replaced: bool,
+}
+
+impl Replacement {
+    fn replace(&mut self) -> std::io::Result<()> {
+        if !self.replaced {
+            rename_over(&self.temp_path, &self.path)?;
+            self.replaced = true;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Replacement {
+    fn drop(&mut self) {
+        let _ = self.replace();
+    }
+}
+
+/// Returns the path of the backup of the file at `path`.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup_path = path.to_path_buf().into_os_string();
+    backup_path.push(".bak");
+    backup_path.into()
+}
+
+/// Replaces the backup of the file at `path` with a copy of the file. The file is copied
+/// into a temporary file first, so the backup is never left half-written.
+fn backup_file(path: &Path) -> Result<(), DatabaseError> {
+    let backup = backup_path(path);
+    let mut temp_path = backup.clone().into_os_string();
+    temp_path.push(".tmp");
+    let result = std::fs::copy(path, &temp_path)
+        .and_then(|_| File::open(&temp_path)?.sync_all())
+        .and_then(|()| rename_over(temp_path.as_ref(), &backup));
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Checks that the file system of `path` can lock files and atomically replace one file
+/// with another, using two temporary files next to `path`.
+fn probe_filesystem(path: &Path) -> Result<(), DatabaseError> {
+    let probe_path = |suffix: &str| {
+        let mut probe_path = path.to_path_buf().into_os_string();
+        probe_path.push(suffix);
+        PathBuf::from(probe_path)
+    };
+    let source = probe_path(".probe");
+    let target = probe_path(".probe.tmp");
+    let result = (|| {
+        std::fs::write(&target, b"old")?;
+        let probe = File::create(&source).map_err(open_failed(&source))?;
+        if probe.try_lock().is_err() || probe.unlock().is_err() {
+            return Err(DatabaseError::UnsupportedFilesystem {
+                capability: "file locking",
+            });
+        }
+        (&probe).write_all(b"new")?;
+        drop(probe);
+        if rename_over(&source, &target).is_err() || std::fs::read(&target)? != b"new" {
+            return Err(DatabaseError::UnsupportedFilesystem {
+                capability: "atomic rename",
+            });
+        }
+        Ok(())
+    })();
+    let _ = std::fs::remove_file(&source);
+    let _ = std::fs::remove_file(&target);
+    result
+}
+
+/// Allocates `additional` bytes of disk space after `offset` without changing the length
+/// of the file.
+#[cfg(target_os = "linux")]
+fn allocate(file: &File, offset: u64, additional: u64) -> Result<(), DatabaseError> {
+    use std::os::unix::io::AsRawFd;
+
+    if additional == 0 {
+        return Ok(());
+    }
+    let (Ok(offset), Ok(additional)) = (i64::try_from(offset), i64::try_from(additional)) else {
+        return Err(std::io::Error::from(ErrorKind::InvalidInput).into());
+    };
+    // SAFETY: `fallocate` only operates on the descriptor, which `file` keeps open.
+    let result = unsafe {
+        libc::fallocate(
+            file.as_raw_fd(),
+            libc::FALLOC_FL_KEEP_SIZE,
+            offset,
+            additional,
+        )
+    };
+    if result != 0 {
+        let e = std::io::Error::last_os_error();
+        if e.raw_os_error() != Some(libc::EOPNOTSUPP) {
+            return Err(e.into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn allocate(_file: &File, _offset: u64, _additional: u64) -> Result<(), DatabaseError> {
+    Ok(())
+}
+
+/// Makes every write through files opened with `options` reach the disk before it
+/// returns.
+#[cfg(unix)]
+fn set_write_through(options: &mut OpenOptions) -> Result<(), DatabaseError> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    options.custom_flags(libc::O_DSYNC);
+    Ok(())
+}
+
+/// Makes every write through files opened with `options` reach the disk before it
+/// returns.
+#[cfg(windows)]
+fn set_write_through(options: &mut OpenOptions) -> Result<(), DatabaseError> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const FILE_FLAG_WRITE_THROUGH: u32 = 0x8000_0000;
+    options.custom_flags(FILE_FLAG_WRITE_THROUGH);
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn set_write_through(_options: &mut OpenOptions) -> Result<(), DatabaseError> {
+    Err(DatabaseError::Unsupported(
+        "write-through is not supported on this platform",
+    ))
+}
+
+/// Renames `from` to `to`, replacing `to` if it exists.
+#[cfg(not(windows))]
+fn rename_over(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::rename(from, to)
+}
+
+/// Renames `from` to `to`, replacing `to` if it exists. Virus scanners and the search
+/// indexer briefly open files without allowing them to be replaced, so sharing violations
+/// are retried for a short while before giving up.
+#[cfg(windows)]
+fn rename_over(from: &Path, to: &Path) -> std::io::Result<()> {
+    let mut attempts = 0;
+    loop {
+        match std::fs::rename(from, to) {
+            Err(e) if e.kind() == ErrorKind::PermissionDenied && attempts < 10 => {
+                attempts += 1;
+                std::thread::sleep(Duration::from_millis(10 * attempts));
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Formats a size in bytes with a binary unit, e.g. `1.2 MiB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        binary_to_vec, header::Layout, read_frame, vec_to_binary, verify_binary, write_encoded,
+        Bincode, Client, ClientBuilder, CrioQueue, DatabaseError, Endianness, LoadError,
+        MixedError,
+    };
+    use proptest::prelude::*;
+    use serde_derive::{Deserialize, Serialize};
+    use std::{io::Write, path::PathBuf};
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    struct Test {
+        id: usize,
+        message: String,
+    }
+
+    fn generate_test_data() -> Vec<Test> {
+        let test1 = Test {
+            id: 1,
+            message: "Hello there, you suck".to_string(),
+        };
+        let test2 = Test {
+            id: 2,
+            message: "No you".to_string(),
+        };
+        let test3 = Test {
+            id: 3,
+            message: "You both suck".to_string(),
+        };
+        vec![test1, test2, test3]
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("crio-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn binary_vec_conversion() {
+        let test_messages = generate_test_data();
+        let binary = vec_to_binary::<Bincode, _>(&test_messages, &Layout::default()).unwrap();
+        let vec: Vec<Test> =
+            binary_to_vec::<Bincode, _>(&binary, &Layout::default(), true).unwrap();
+        assert_eq!(test_messages, vec);
+    }
+
+    #[test]
+    fn reader_sees_client_writes() {
+        let path = temp_path("reader");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        let mut reader = client.reader().unwrap();
+        let handle = std::thread::spawn(move || reader.load().unwrap());
+        assert_eq!(handle.join().unwrap(), Some(test_messages));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn was_created_on_first_open_only() {
+        let path = temp_path("created");
+        let client: Client<Test> = Client::new(&path, true).unwrap();
+        assert!(client.was_created());
+        let client: Client<Test> = Client::new(&path, false).unwrap();
+        assert!(!client.was_created());
+        assert_eq!(client.path(), path);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn verify_continues_past_corruption() {
+        let test_messages = generate_test_data();
+        let mut binary = vec_to_binary::<Bincode, _>(&test_messages, &Layout::default()).unwrap();
+        binary[8] ^= 0xff;
+        binary.truncate(binary.len() - 1);
+        let report = verify_binary(&binary, 0, &Layout::default());
+        assert_eq!(report.valid, 1);
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].0, 0);
+        assert!(report.truncated);
+    }
+
+    #[test]
+    fn queue_is_fifo_and_durable() {
+        let path = temp_path("queue");
+        let test_messages = generate_test_data();
+        let mut queue: CrioQueue<Test> = CrioQueue::new(&path).unwrap();
+        for message in &test_messages {
+            queue.push(message).unwrap();
+        }
+        assert_eq!(queue.pop().unwrap().as_ref(), Some(&test_messages[0]));
+        drop(queue);
+        let mut queue: CrioQueue<Test> = CrioQueue::new(&path).unwrap();
+        assert_eq!(queue.peek().unwrap().as_ref(), Some(&test_messages[1]));
+        assert_eq!(queue.pop().unwrap().as_ref(), Some(&test_messages[1]));
+        assert_eq!(queue.pop().unwrap().as_ref(), Some(&test_messages[2]));
+        assert_eq!(queue.pop().unwrap(), None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn big_endian_is_recorded_in_header() {
+        let path = temp_path("big-endian");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new()
+            .endianness(Endianness::Big)
+            .open(&path)
+            .unwrap();
+        client.write_many(&test_messages).unwrap();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        client.write(&test_messages[0]).unwrap();
+        let loaded = client.load().unwrap().unwrap();
+        assert_eq!(loaded.len(), 4);
+        assert_eq!(loaded[..3], test_messages[..]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn newer_minor_version_is_readable() {
+        let path = temp_path("minor-version");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new()
+            .endianness(Endianness::Big)
+            .open(&path)
+            .unwrap();
+        client.write_many(&test_messages).unwrap();
+        drop(client);
+        let mut raw = std::fs::read(&path).unwrap();
+        raw[10] |= 0x80;
+        std::fs::write(&path, &raw).unwrap();
+        assert!(matches!(
+            Client::<Test>::new(&path, true),
+            Err(DatabaseError::BadHeader)
+        ));
+        raw[11] = 1;
+        std::fs::write(&path, &raw).unwrap();
+        let client: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        raw[4] = 2;
+        std::fs::write(&path, &raw).unwrap();
+        assert!(matches!(
+            Client::<Test>::new(&path, true),
+            Err(DatabaseError::UnsupportedVersion(2))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn nth_last_counts_from_the_end() {
+        let path = temp_path("nth-last");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(
+            client.nth_last(0).unwrap().as_ref(),
+            Some(&test_messages[2])
+        );
+        assert_eq!(
+            client.nth_last(2).unwrap().as_ref(),
+            Some(&test_messages[0])
+        );
+        assert_eq!(client.nth_last(3).unwrap(), None);
+        assert_eq!(client.nth_last(usize::MAX).unwrap(), None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn transaction_writes_only_on_commit() {
+        let path = temp_path("transaction");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        let mut tx = client.begin();
+        tx.write(&test_messages[0]).unwrap();
+        tx.rollback();
+        let mut tx = client.begin();
+        tx.write(&test_messages[1]).unwrap();
+        tx.write_many(&test_messages[2..]).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages[1..]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_files_are_rejected_by_bincode_clients() {
+        use crate::{Cbor, DatabaseError};
+
+        let path = temp_path("cbor");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test, Cbor> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        let client: Client<Test, Cbor> = Client::new(&path, true).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        let result: Result<Client<Test>, _> = Client::new(&path, true);
+        assert!(matches!(result, Err(DatabaseError::FormatMismatch { .. })));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn varint_files_are_smaller_and_tagged() {
+        use crate::BincodeVarint;
+
+        let path = temp_path("varint");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test, BincodeVarint> = Client::new(&path, false).unwrap();
+        let varint = client.encode_many(&test_messages).unwrap();
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        let mut fixint = Vec::new();
+        crate::write_frames(&mut fixint, &test_messages).unwrap();
+        assert!(varint.len() < fixint.len());
+        let result: Result<Client<Test>, _> = Client::new(&path, true);
+        assert!(matches!(result, Err(DatabaseError::FormatMismatch { .. })));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn write_compressed_mixes_compressed_documents() {
+        let path = temp_path("frame-compression");
+        let big = Test {
+            id: 4,
+            message: "a".repeat(1000),
+        };
+        let mut client: Client<Test> = ClientBuilder::new()
+            .append(true)
+            .frame_compression(true)
+            .open(&path)
+            .unwrap();
+        client.write(&generate_test_data()[0]).unwrap();
+        let report = client.write_compressed(&big).unwrap();
+        assert!(report.bytes < 100);
+        let expected = vec![generate_test_data()[0].clone(), big];
+        assert_eq!(client.load().unwrap().unwrap(), expected);
+        let iterated: Vec<Test> = client.iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(iterated, expected);
+        let mut plain: Client<Test> =
+            Client::new(temp_path("frame-compression-plain"), true).unwrap();
+        assert!(matches!(
+            plain.write_compressed(&expected[1]),
+            Err(DatabaseError::Unsupported(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(temp_path("frame-compression-plain")).unwrap();
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn with_compression_compresses_every_document() {
+        let path = temp_path("compression-level");
+        let big = Test {
+            id: 4,
+            message: "a".repeat(1000),
+        };
+        let mut client: Client<Test> = Client::with_compression(&path, true, 9).unwrap();
+        let report = client.write_many(&[big.clone(), big.clone()]).unwrap();
+        assert!(report.bytes < 200);
+        assert!(crate::inspect_header(&path).unwrap().frame_compression);
+        let mut plain: Client<Test> = Client::new(&path, true).unwrap();
+        plain.write(&big).unwrap();
+        assert_eq!(
+            client.load().unwrap().unwrap(),
+            vec![big.clone(), big.clone(), big]
+        );
+        assert!(matches!(
+            Client::<Test>::with_compression(temp_path("compression-level-bad"), true, 10),
+            Err(DatabaseError::Unsupported(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_files_round_trip() {
+        use crate::GzClient;
+        use std::io::Read;
+
+        let path = temp_path("gzip");
+        let test_messages = generate_test_data();
+        let mut client: GzClient<Test> = GzClient::new(&path, false).unwrap();
+        client.write_many(&test_messages[..2]).unwrap();
+        client.flush().unwrap();
+        drop(client);
+        let mut client: GzClient<Test> = GzClient::new(&path, true).unwrap();
+        client.write(&test_messages[2]).unwrap();
+        drop(client);
+        let mut raw = Vec::new();
+        let file = std::fs::File::open(&path).unwrap();
+        flate2::read::GzDecoder::new(file)
+            .read_to_end(&mut raw)
+            .unwrap();
+        assert_eq!(
+            binary_to_vec::<Bincode, Test>(&raw, &Layout::default(), true).unwrap(),
+            test_messages
+        );
+        let client: GzClient<Test> = GzClient::new(&path, true).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn timestamps_are_stored_per_document() {
+        let path = temp_path("timestamps");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new().timestamps(true).open(&path).unwrap();
+        client.write(&test_messages[0]).unwrap();
+        let loaded = client.load_with_timestamps().unwrap().unwrap();
+        let cutoff = loaded[0].0 + 1;
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        client.write_many(&test_messages[1..]).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        let tail: Vec<Test> = client
+            .tail_since(cutoff)
+            .unwrap()
+            .into_iter()
+            .map(|(_, document)| document)
+            .collect();
+        assert_eq!(tail, test_messages[1..]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn open_reports_bad_paths() {
+        use crate::DatabaseError;
+
+        let dir = temp_path("dirs");
+        let nested = dir.join("nested").join("data");
+        let result: Result<Client<Test>, _> = Client::new(&nested, true);
+        assert!(matches!(result, Err(DatabaseError::ParentMissing(_))));
+        let _: Client<Test> = ClientBuilder::new()
+            .create_dirs(true)
+            .open(&nested)
+            .unwrap();
+        let result: Result<Client<Test>, _> = Client::new(&dir, true);
+        assert!(matches!(result, Err(DatabaseError::NotAFile(_))));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn fixed_client_reads_by_index() {
+        use crate::{DatabaseError, FixedClient};
+
+        let path = temp_path("fixed");
+        let mut client: FixedClient<(u32, u64)> = FixedClient::new(&path, false).unwrap();
+        client.write_many(&[(1, 10), (2, 20), (3, 30)]).unwrap();
+        assert_eq!(client.record_size(), Some(12));
+        let mut client: FixedClient<(u32, u64)> = FixedClient::new(&path, true).unwrap();
+        assert_eq!(client.len().unwrap(), 3);
+        assert_eq!(client.get_at(1).unwrap(), Some((2, 20)));
+        assert_eq!(client.get_at(3).unwrap(), None);
+        assert_eq!(client.load().unwrap().unwrap()[2], (3, 30));
+        let result: Result<Client<(u32, u64)>, _> = Client::new(&path, true);
+        assert!(matches!(result, Err(DatabaseError::Unsupported(_))));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn raw_payloads_yield_encoded_documents() {
+        let path = temp_path("raw-payloads");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        let payloads: Vec<Vec<u8>> = client.raw_payloads().collect::<Result<_, _>>().unwrap();
+        let expected: Vec<Vec<u8>> = test_messages
+            .iter()
+            .map(|message| bincode::serialize(message).unwrap())
+            .collect();
+        assert_eq!(payloads, expected);
+        let file_len = std::fs::metadata(&path).unwrap().len();
+        let mut payloads = client.raw_payloads();
+        payloads.next().unwrap().unwrap();
+        assert_eq!(payloads.frames_seen(), 1);
+        assert_eq!(payloads.bytes_consumed(), 8 + expected[0].len() as u64);
+        assert_eq!(payloads.by_ref().count(), 2);
+        assert_eq!(payloads.frames_seen(), 3);
+        assert_eq!(payloads.bytes_consumed(), file_len);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn vacuum_reclaims_torn_tail() {
+        let path = temp_path("vacuum");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(client.vacuum().unwrap(), 0);
+        let len = std::fs::metadata(&path).unwrap().len();
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(&[1, 2, 3, 4, 200, 0, 0, 0, 5]).unwrap();
+        drop(file);
+        assert_eq!(client.vacuum().unwrap(), 9);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), len);
+        client.write(&test_messages[0]).unwrap();
+        let mut expected = test_messages.clone();
+        expected.push(test_messages[0].clone());
+        assert_eq!(client.load().unwrap().unwrap(), expected);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn tombstones_skip_removed_documents() {
+        let path = temp_path("tombstones");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new().tombstones(true).open(&path).unwrap();
+        client.write_many(&test_messages).unwrap();
+        let len = std::fs::metadata(&path).unwrap().len();
+        assert!(client.remove_at(1).unwrap());
+        assert!(!client.remove_at(2).unwrap());
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), len);
+        assert!(client.validate().unwrap());
+        let expected = vec![test_messages[0].clone(), test_messages[2].clone()];
+        assert_eq!(client.load().unwrap().unwrap(), expected);
+        assert_eq!(client.nth_last(1).unwrap(), Some(test_messages[0].clone()));
+        assert_eq!(client.remove_where(|t| t.id == 3).unwrap(), 1);
+        assert!(client.vacuum().unwrap() > 0);
+        let client: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(
+            client.load().unwrap().unwrap(),
+            vec![test_messages[0].clone()]
+        );
+        std::fs::remove_file(&path).unwrap();
+        let mut plain: Client<Test> = Client::new(&path, false).unwrap();
+        assert!(plain.remove_at(0).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn remove_returns_document_with_or_without_tombstones() {
+        let path = temp_path("remove");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        assert_eq!(client.remove(1).unwrap(), Some(test_messages[1].clone()));
+        assert_eq!(client.remove(2).unwrap(), None);
+        let expected = vec![test_messages[0].clone(), test_messages[2].clone()];
+        assert_eq!(client.load().unwrap().unwrap(), expected);
+        std::fs::remove_file(&path).unwrap();
+        let mut client: Client<Test> = ClientBuilder::new().tombstones(true).open(&path).unwrap();
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(client.remove(1).unwrap(), Some(test_messages[1].clone()));
+        assert_eq!(client.remove(1).unwrap(), Some(test_messages[2].clone()));
+        assert_eq!(client.load().unwrap().unwrap(), test_messages[..1]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn shared_client_reads_from_several_threads() {
+        let path = temp_path("shared-reads");
+        let test_messages = generate_test_data();
+        let client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for (i, message) in test_messages.iter().enumerate() {
+                        assert_eq!(client.get(i).unwrap().as_ref(), Some(message));
+                        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+                    }
+                });
+            }
+        });
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn snapshot_reader_ignores_later_appends() {
+        let path = temp_path("snapshot");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        let mut snapshot = client.snapshot_reader().unwrap();
+        client.write(&test_messages[0]).unwrap();
+        assert_eq!(snapshot.load().unwrap().unwrap(), test_messages);
+        assert!(snapshot.validate().unwrap());
+        assert_eq!(client.load().unwrap().unwrap().len(), 4);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn maybe_flush_respects_interval() {
+        let path = temp_path("maybe-flush");
+        let mut client: Client<Test> = ClientBuilder::new()
+            .flush_every(std::time::Duration::from_millis(50))
+            .open(&path)
+            .unwrap();
+        client.write_many(&generate_test_data()).unwrap();
+        assert!(!client.maybe_flush().unwrap());
+        std::thread::sleep(std::time::Duration::from_millis(60));
+        assert!(client.maybe_flush().unwrap());
+        assert!(!client.maybe_flush().unwrap());
+        let mut unbounded: Client<Test> = Client::new(&path, true).unwrap();
+        assert!(!unbounded.maybe_flush().unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn scan_frames_reads_headers() {
+        let path = temp_path("scan-frames");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        let frames = client.scan_frames().unwrap();
+        assert_eq!(frames.len(), test_messages.len());
+        let mut offset = 0;
+        for (frame, message) in frames.iter().zip(&test_messages) {
+            let encoded = bincode::serialize(message).unwrap();
+            assert_eq!(frame.offset, offset);
+            assert_eq!(frame.len as usize, encoded.len());
+            assert_eq!(frame.checksum, crate::CRC.checksum(&encoded));
+            assert!(!frame.removed);
+            offset += 8 + encoded.len() as u64;
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn bytes_reader_and_typed_reads_share_handle() {
+        use std::io::Read;
+
+        let path = temp_path("bytes-reader");
+        let copy_path = temp_path("bytes-reader-copy");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        let mut partial = [0; 3];
+        client
+            .as_bytes_reader()
+            .unwrap()
+            .read_exact(&mut partial)
+            .unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        let mut frames = Vec::new();
+        client
+            .as_bytes_reader()
+            .unwrap()
+            .read_to_end(&mut frames)
+            .unwrap();
+        assert_eq!(frames, std::fs::read(&path).unwrap());
+        let mut copy: Client<Test> = Client::new(&copy_path, false).unwrap();
+        copy.append_raw_frames(&frames, true).unwrap();
+        assert_eq!(copy.load().unwrap().unwrap(), test_messages);
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(copy_path).unwrap();
+    }
+
+    #[test]
+    fn max_record_size_rejects_declared_lengths() {
+        let path = temp_path("max-record-size");
+        let test_messages = generate_test_data();
+        Client::<Test>::create_with(&path, test_messages.clone()).unwrap();
+        let mut client: Client<Test> = ClientBuilder::new()
+            .append(true)
+            .max_record_size(1024)
+            .open(&path)
+            .unwrap();
+        assert_eq!(client.get(2).unwrap(), Some(test_messages[2].clone()));
+        let mut raw = std::fs::read(&path).unwrap();
+        raw[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        std::fs::write(&path, &raw).unwrap();
+        assert!(matches!(
+            client.iter().next(),
+            Some(Err(DatabaseError::RecordTooLarge {
+                declared: u32::MAX,
+                max: 1024
+            }))
+        ));
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        assert!(client.iter().next().unwrap().is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn deserialize_limit_rejects_large_documents() {
+        let path = temp_path("deserialize-limit");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        let limited: Client<Test> = ClientBuilder::new()
+            .append(true)
+            .deserialize_limit(16)
+            .open(&path)
+            .unwrap();
+        assert!(matches!(
+            limited.load(),
+            Err(LoadError::Database(DatabaseError::LimitExceeded(16)))
+        ));
+        let generous: Client<Test> = ClientBuilder::new()
+            .append(true)
+            .deserialize_limit(1024)
+            .open(&path)
+            .unwrap();
+        assert_eq!(generous.load().unwrap().unwrap(), test_messages);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reject_trailing_bytes_catches_wider_documents() {
+        let path = temp_path("reject-trailing-bytes");
+        let mut client: Client<(u32, u32)> = Client::new(&path, false).unwrap();
+        client.write(&(1, 2)).unwrap();
+        let lenient: Client<u32> = ClientBuilder::new().append(true).open(&path).unwrap();
+        assert_eq!(lenient.load().unwrap().unwrap(), vec![1]);
+        for limit in [None, Some(1024)] {
+            let mut builder = ClientBuilder::new();
+            builder.append(true).reject_trailing_bytes(true);
+            if let Some(limit) = limit {
+                builder.deserialize_limit(limit);
+            }
+            let strict: Client<u32> = builder.open(&path).unwrap();
+            assert!(matches!(
+                strict.load(),
+                Err(LoadError::Database(DatabaseError::TrailingBytes(4)))
+            ));
+            let exact: Client<(u32, u32)> = builder.open(&path).unwrap();
+            assert_eq!(exact.load().unwrap().unwrap(), vec![(1, 2)]);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn footer_detects_reordered_frames() {
+        let path = temp_path("footer");
+        let mut client: Client<u32> = Client::new(&path, true).unwrap();
+        client.write_many(&[1, 2, 3]).unwrap();
+        assert!(client.verify_footer().is_err());
+        client.finalize().unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), vec![1, 2, 3]);
+        assert_eq!(client.iter().count(), 3);
+        assert!(client.verify_footer().unwrap());
+        client.write(&4).unwrap();
+        client.finalize().unwrap();
+        client.finalize().unwrap();
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().len(),
+            (crate::FILE_HEADER_LEN + 4 * 12 + crate::FOOTER_LEN) as u64
+        );
+        assert!(client.verify_footer().unwrap());
+        let mut raw = std::fs::read(&path).unwrap();
+        raw[crate::FILE_HEADER_LEN..][..24].rotate_left(12);
+        std::fs::write(&path, &raw).unwrap();
+        let mut client: Client<u32> = Client::new(&path, true).unwrap();
+        assert!(client.validate().unwrap());
+        assert!(!client.verify_footer().unwrap());
+        assert_eq!(client.load().unwrap().unwrap(), vec![2, 1, 3, 4]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn upsert_replaces_or_appends() {
+        let path = temp_path("upsert");
+        let mut client: Client<(u32, String)> = Client::new(&path, true).unwrap();
+        client
+            .write_many(&[(1, "one".to_string()), (2, "two".to_string())])
+            .unwrap();
+        let key = |document: &(u32, String)| document.0;
+        assert!(client.upsert(key, &(2, "TWO".to_string())).unwrap());
+        assert!(client.upsert(key, &(1, "uno, eins".to_string())).unwrap());
+        assert!(!client.upsert(key, &(3, "three".to_string())).unwrap());
+        assert!(client.validate().unwrap());
+        assert_eq!(
+            client.load().unwrap().unwrap(),
+            vec![
+                (1, "uno, eins".to_string()),
+                (2, "TWO".to_string()),
+                (3, "three".to_string()),
+            ]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn replace_overwrites_document_at_index() {
+        let path = temp_path("replace");
+        let mut client: Client<(u32, String)> = Client::new(&path, true).unwrap();
+        client
+            .write_many(&[(1, "one".to_string()), (2, "two".to_string())])
+            .unwrap();
+        let len = std::fs::metadata(&path).unwrap().len();
+        client.replace(1, &(2, "TWO".to_string())).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), len);
+        client.replace(0, &(1, "uno, eins".to_string())).unwrap();
+        assert!(matches!(
+            client.replace(2, &(3, "three".to_string())),
+            Err(DatabaseError::IndexOutOfRange { index: 2, len: 2 })
+        ));
+        assert!(client.validate().unwrap());
+        assert_eq!(
+            client.load().unwrap().unwrap(),
+            vec![(1, "uno, eins".to_string()), (2, "TWO".to_string())]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn many_small_documents_round_trip() {
+        let path = temp_path("many-small");
+        let documents: Vec<u64> = (0..200_000).collect();
+        let mut client: Client<u64> = Client::new(&path, false).unwrap();
+        client.write_many(&documents).unwrap();
+        assert!(client.validate().unwrap());
+        assert_eq!(client.load().unwrap().unwrap(), documents);
+        assert_eq!(client.vacuum().unwrap(), 0);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn multi_client_rolls_over() {
+        let dir = temp_path("multi");
+        std::fs::create_dir_all(&dir).unwrap();
+        let shard = |n: usize| dir.join(format!("data.{n}.crio"));
+        let test_messages = generate_test_data();
+        let mut first: Client<Test> = Client::new(shard(0), true).unwrap();
+        first.write(&test_messages[0]).unwrap();
+        let mut client: crate::MultiClient<Test> =
+            crate::MultiClient::open(&[shard(0), shard(1)]).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), &test_messages[..1]);
+        let next_dir = dir.clone();
+        client.rollover_at(1, move |n| next_dir.join(format!("data.{n}.crio")));
+        client.write(&test_messages[1]).unwrap();
+        client.write(&test_messages[2]).unwrap();
+        assert_eq!(client.paths().len(), 3);
+        assert_eq!(client.len().unwrap(), 3);
+        let documents: Vec<Test> = client.iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(documents, test_messages);
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn multi_client_merges_sorted_files() {
+        let dir = temp_path("multi-sorted");
+        std::fs::create_dir_all(&dir).unwrap();
+        let shard = |n: usize| dir.join(format!("data.{n}.crio"));
+        let mut client: crate::MultiClient<(u32, u32)> =
+            crate::MultiClient::open(&[shard(0), shard(1), shard(2)]).unwrap();
+        for (n, keys) in [[1, 4, 7], [2, 4, 9], [0, 3, 5]].iter().enumerate() {
+            let mut shard: Client<(u32, u32)> = Client::new(shard(n), true).unwrap();
+            let documents: Vec<_> = keys.iter().map(|&key| (key, n as u32)).collect();
+            shard.write_many(&documents).unwrap();
+        }
+        let merged: Vec<(u32, u32)> = client
+            .iter_sorted(|document| document.0)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            merged,
+            [
+                (0, 2),
+                (1, 0),
+                (2, 1),
+                (3, 2),
+                (4, 0),
+                (4, 1),
+                (5, 2),
+                (7, 0),
+                (9, 1)
+            ]
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn probe_filesystem_cleans_up() {
+        let dir = temp_path("probe-filesystem");
+        let path = dir.join("data");
+        let mut client: Client<Test> = ClientBuilder::new()
+            .create_dirs(true)
+            .probe_filesystem(true)
+            .open(&path)
+            .unwrap();
+        client.write(&generate_test_data()[0]).unwrap();
+        let entries = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(entries, 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn blocks_share_a_checksum() {
+        let path = temp_path("blocks");
+        let documents: Vec<u32> = (0..10).collect();
+        let mut client: Client<u32> = ClientBuilder::new()
+            .append(true)
+            .block_size(4)
+            .open(&path)
+            .unwrap();
+        client.write_many(&documents).unwrap();
+        assert_eq!(client.scan_frames().unwrap().len(), 3);
+        let mut client: Client<u32> = Client::new(&path, true).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), documents);
+        let iterated: Vec<u32> = client.iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(iterated, documents);
+        assert!(matches!(
+            client.get_many(&[0]),
+            Err(DatabaseError::Unsupported(_))
+        ));
+        let second = client.scan_frames().unwrap()[1].offset as usize;
+        let mut raw = std::fs::read(&path).unwrap();
+        raw[second + crate::FRAME_HEADER_LEN + 4] ^= 0xff;
+        std::fs::write(&path, &raw).unwrap();
+        let mut client: Client<u32> = Client::new(&path, true).unwrap();
+        assert!(matches!(
+            client.load(),
+            Err(LoadError::Database(DatabaseError::CorruptedBlock {
+                index: 1,
+                ..
+            }))
+        ));
+        let iterated: Vec<_> = client.iter().collect();
+        assert_eq!(iterated.len(), 7);
+        assert!(matches!(
+            iterated[4],
+            Err(DatabaseError::CorruptedBlock { index: 1, .. })
+        ));
+        assert_eq!(*iterated[6].as_ref().unwrap(), 9);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_resync_skips_damaged_bytes() {
+        let path = temp_path("load-resync");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        let second = client.scan_frames().unwrap()[1].offset;
+        let mut raw = std::fs::read(&path).unwrap();
+        raw[..6].fill(0xff);
+        std::fs::write(&path, &raw).unwrap();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        assert!(client.load().is_err());
+        let (documents, skipped) = client.load_resync().unwrap();
+        assert_eq!(documents, &test_messages[1..]);
+        assert_eq!(skipped, second);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_lenient_reports_corrupt_regions() {
+        let path = temp_path("load-lenient");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        let frames = client.scan_frames().unwrap();
+        let (second, third) = (frames[1].offset, frames[2].offset);
+        let mut raw = std::fs::read(&path).unwrap();
+        raw[second as usize + crate::FRAME_HEADER_LEN] ^= 0xff;
+        std::fs::write(&path, &raw).unwrap();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        let (documents, corrupt) = client.load_lenient().unwrap();
+        assert_eq!(
+            documents,
+            [test_messages[0].clone(), test_messages[2].clone()]
+        );
+        assert_eq!(corrupt.len(), 1);
+        assert_eq!(corrupt[0].offset, second);
+        assert_eq!(corrupt[0].len, third - second);
+        assert_eq!(corrupt[0].saved, Some(frames[1].checksum));
+        assert_ne!(corrupt[0].expected, corrupt[0].saved);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn append_returns_index_of_document() {
+        let path = temp_path("append-index");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        client.write(&test_messages[0]).unwrap();
+        assert_eq!(client.append(&test_messages[1]).unwrap(), 1);
+        let mut other: Client<Test> = Client::new(&path, true).unwrap();
+        other.write(&test_messages[0]).unwrap();
+        assert_eq!(client.append(&test_messages[2]).unwrap(), 3);
+        assert_eq!(
+            client.get_many(&[1, 3]).unwrap(),
+            [
+                Some(test_messages[1].clone()),
+                Some(test_messages[2].clone())
+            ]
+        );
+        drop((client, other));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn pop_removes_last_document() {
+        let path = temp_path("pop");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        client.write_many(&test_messages).unwrap();
+        let third = client.scan_frames().unwrap()[2].offset;
+        assert_eq!(client.pop().unwrap().unwrap(), test_messages[2]);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), third);
+        assert_eq!(client.pop().unwrap().unwrap(), test_messages[1]);
+        assert_eq!(client.pop().unwrap().unwrap(), test_messages[0]);
+        assert_eq!(client.pop().unwrap(), None);
+        client.write_many(&test_messages).unwrap();
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(file.metadata().unwrap().len() - 1).unwrap();
+        let e = client.pop().unwrap_err();
+        assert_eq!(e.kind(), crate::DatabaseErrorKind::Truncated);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn pop_forgets_cached_documents() {
+        let path = temp_path("pop-unique");
+        let mut client: Client<u32> = Client::new(&path, true).unwrap();
+        assert!(client.append_unique(&1).unwrap());
+        assert_eq!(client.pop().unwrap(), Some(1));
+        client.write(&2).unwrap();
+        assert!(!client.append_unique(&2).unwrap());
+        assert_eq!(client.load().unwrap().unwrap(), [2]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn get_reads_single_document() {
+        let path = temp_path("get");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new().tombstones(true).open(&path).unwrap();
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(client.get(1).unwrap().unwrap(), test_messages[1]);
+        client.remove_at(0).unwrap();
+        assert_eq!(client.get(1).unwrap().unwrap(), test_messages[2]);
+        assert_eq!(client.get(2).unwrap(), None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn clear_empties_file_in_any_mode() {
+        let path = temp_path("clear");
+        let test_messages = generate_test_data();
+        for append in [false, true] {
+            let mut client: Client<Test> = Client::new(&path, append).unwrap();
+            client.write_many(&test_messages).unwrap();
+            client.clear().unwrap();
+            let len = std::fs::metadata(&path).unwrap().len();
+            assert_eq!(len, client.layout.data_start);
+            assert_eq!(len == 0, !append);
+            assert_eq!(client.load().unwrap(), None);
+            client.write(&test_messages[0]).unwrap();
+            assert_eq!(client.load().unwrap().unwrap(), test_messages[..1]);
+            drop(client);
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn drain_takes_every_document() {
+        let path = temp_path("drain");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        assert!(client.drain().unwrap().is_empty());
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(client.drain().unwrap(), test_messages);
+        let len = std::fs::metadata(&path).unwrap().len();
+        assert_eq!(len, client.layout.data_start);
+        client.write_many(&test_messages).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 0xff;
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(client.drain().is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
+        drop(client);
+        std::fs::remove_file(&path).unwrap();
+        let mut lock_path = path.into_os_string();
+        lock_path.push(".lock");
+        std::fs::remove_file(lock_path).unwrap();
+    }
+
+    #[test]
+    fn enumerate_skips_removed_documents() {
+        let path = temp_path("enumerate");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new().tombstones(true).open(&path).unwrap();
+        client.write_many(&test_messages).unwrap();
+        client.remove_at(0).unwrap();
+        let enumerated: Vec<(usize, Test)> = client.enumerate().collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            enumerated,
+            vec![(0, test_messages[1].clone()), (1, test_messages[2].clone())]
+        );
+        let (index, document) = &enumerated[1];
+        assert_eq!(
+            client.get_many(&[*index]).unwrap()[0].as_ref(),
+            Some(document)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn fence_orders_writes_across_files() {
+        let data_path = temp_path("fence-data");
+        let index_path = temp_path("fence-index");
+        let test_messages = generate_test_data();
+        let mut data: Client<Test> = Client::new(&data_path, true).unwrap();
+        let mut index: Client<u64> = Client::new(&index_path, true).unwrap();
+        let report = data.write(&test_messages[0]).unwrap();
+        data.fence().unwrap();
+        index.write(&report.bytes).unwrap();
+        index.fence().unwrap();
+        assert_eq!(index.load().unwrap().unwrap(), vec![report.bytes]);
+        assert_eq!(data.load().unwrap().unwrap(), &test_messages[..1]);
+        std::fs::remove_file(data_path).unwrap();
+        std::fs::remove_file(index_path).unwrap();
+    }
+
+    #[test]
+    fn dead_ratio_counts_superseded_documents() {
+        let path = temp_path("dead-ratio");
+        let mut client: Client<(u32, u32)> = Client::new(&path, false).unwrap();
+        assert_eq!(client.dead_ratio(|document| document.0).unwrap(), 0.0);
+        client
+            .write_many(&[(1, 0), (2, 0), (1, 1), (1, 2)])
+            .unwrap();
+        assert_eq!(client.dead_ratio(|document| document.0).unwrap(), 0.5);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn write_frames_matches_file_frames() {
+        let path = temp_path("codec");
+        let test_messages = generate_test_data();
+        let mut buf = Vec::new();
+        crate::write_frames(&mut buf, &test_messages).unwrap();
+        assert_eq!(
+            crate::read_frames::<_, Test>(&mut buf.as_slice()).unwrap(),
+            test_messages
+        );
+        std::fs::write(&path, &buf).unwrap();
+        let client: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn upsert_writes_the_bytes_it_measured() {
+        use std::collections::HashMap;
+
+        let path = temp_path("upsert-hashmap");
+        let mut client: Client<(u32, HashMap<String, u32>)> = Client::new(&path, false).unwrap();
+        let entries = |n: u32| (0..64).map(|i| (format!("key-{i}"), i * n)).collect();
+        client
+            .write_many(&[(1, entries(1)), (2, entries(1))])
+            .unwrap();
+        for n in 2..10 {
+            let document = (1, entries(n % 2 + 1));
+            assert!(client.upsert(|document| document.0, &document).unwrap());
+            assert_eq!(client.load().unwrap().unwrap()[0], document);
+        }
+        assert_eq!(client.load().unwrap().unwrap()[1], (2, entries(1)));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn restore_backup_undoes_overwrite() {
+        let path = temp_path("backup");
+        let backup = super::backup_path(&path);
+        let test_messages = generate_test_data();
+        let mut builder = ClientBuilder::new();
+        builder.keep_backup(true);
+        let mut client: Client<Test> = builder.open(&path).unwrap();
+        assert!(!client.restore_backup().unwrap());
+        client.write_many(&test_messages).unwrap();
+        drop(client);
+        let mut client: Client<Test> = builder.open(&path).unwrap();
+        client.write(&test_messages[0]).unwrap();
+        client
+            .replace_all_iter(test_messages[1..].to_vec())
+            .unwrap();
+        assert!(client.restore_backup().unwrap());
+        assert_eq!(client.load().unwrap().unwrap(), &test_messages[..1]);
+        assert!(!backup.exists());
+        drop(client);
+        let mut client: Client<Test> = builder.open(&path).unwrap();
+        client.restore_backup().unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), &test_messages[..1]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_parallel_matches_load() {
+        let path = temp_path("load-parallel");
+        let mut client: Client<u64> = Client::new(&path, false).unwrap();
+        assert_eq!(client.load_parallel().unwrap(), None);
+        let documents: Vec<u64> = (0..10_000).collect();
+        client.write_many(&documents).unwrap();
+        assert_eq!(client.load_parallel().unwrap().unwrap(), documents);
+        drop(client);
+        let mut bytes = std::fs::read(&path).unwrap();
+        let frame_len = crate::FRAME_HEADER_LEN + 8;
+        bytes[frame_len * 9_000 + crate::FRAME_HEADER_LEN] ^= 1;
+        bytes[frame_len * 5_000 + crate::FRAME_HEADER_LEN] ^= 1;
+        std::fs::write(&path, &bytes).unwrap();
+        let mut client: Client<u64> = Client::new(&path, true).unwrap();
+        let sequential = client.load().unwrap_err().to_string();
+        assert_eq!(client.load_parallel().unwrap_err().to_string(), sequential);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn load_mmap_matches_load() {
+        let path = temp_path("load-mmap");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        assert!(client.load_mmap().unwrap().is_none());
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(client.load_mmap().unwrap().unwrap(), test_messages);
+        client.finalize().unwrap();
+        assert_eq!(client.load_mmap().unwrap().unwrap(), test_messages);
+        let mut raw = std::fs::read(&path).unwrap();
+        let offset = client.scan_frames().unwrap()[2].offset;
+        raw[offset as usize + crate::FRAME_HEADER_LEN] ^= 1;
+        std::fs::write(&path, &raw).unwrap();
+        let Err(LoadError::Poisoned(e)) = client.load_mmap() else {
+            panic!("expected a poisoned load");
+        };
+        assert_eq!(e.offset(), offset);
+        assert_eq!(e.into_inner(), test_messages[..2]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn truncate_to_last_valid_discards_torn_tail() {
+        let path = temp_path("truncate-last-valid");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        client.write_many(&test_messages[..2]).unwrap();
+        assert_eq!(client.truncate_to_last_valid().unwrap(), 0);
+        let len = std::fs::metadata(&path).unwrap().len();
+        client.write(&test_messages[2]).unwrap();
+        let torn = std::fs::metadata(&path).unwrap().len() - 3;
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(torn)
+            .unwrap();
+        assert_eq!(client.truncate_to_last_valid().unwrap(), torn - len);
+        client.write(&test_messages[2]).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn recover_tail_only_discards_incomplete_frame() {
+        let path = temp_path("recover-tail");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        client.write_many(&test_messages[..2]).unwrap();
+        assert_eq!(client.recover_tail().unwrap(), 0);
+        let frames = client.scan_frames().unwrap();
+        let mut raw = std::fs::read(&path).unwrap();
+        raw[frames[0].offset as usize + crate::FRAME_HEADER_LEN] ^= 1;
+        let len = raw.len() as u64;
+        raw.extend_from_slice(&[7, 0, 0, 0, 100, 0]);
+        std::fs::write(&path, &raw).unwrap();
+        assert_eq!(client.recover_tail().unwrap(), 6);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), len);
+        client.write(&test_messages[2]).unwrap();
+        assert_eq!(client.load_resync().unwrap().0, test_messages[1..]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_tagged_returns_the_tag_of_every_document() {
+        let path = temp_path("load-tagged");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new().open(&path).unwrap();
+        assert!(matches!(
+            client.write_tagged(1, &test_messages[0]),
+            Err(DatabaseError::Unsupported(_))
+        ));
+        drop(client);
+        std::fs::remove_file(&path).unwrap();
+        let mut client: Client<Test> = ClientBuilder::new()
+            .append(true)
+            .timestamps(true)
+            .frame_version(3)
+            .frame_tags(true)
+            .open(&path)
+            .unwrap();
+        client.write_tagged(1, &test_messages[0]).unwrap();
+        client.write(&test_messages[1]).unwrap();
+        client.write_tagged(0x0102, &test_messages[2]).unwrap();
+        let tagged = client.load_tagged().unwrap();
+        assert_eq!(
+            tagged,
+            vec![
+                (1, test_messages[0].clone()),
+                (0, test_messages[1].clone()),
+                (0x0102, test_messages[2].clone()),
+            ]
+        );
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        let versioned = client
+            .load_versioned(|version, bytes| {
+                assert_eq!(version, 3);
+                Ok(bincode::deserialize(bytes)?)
+            })
+            .unwrap();
+        assert_eq!(versioned.unwrap(), test_messages);
+        assert!(crate::inspect_header(&path).unwrap().tags);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_versioned_passes_schema_versions() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct V1 {
+            id: u32,
+        }
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct V2 {
+            id: u32,
+            name: Option<String>,
+        }
+
+        let path = temp_path("load-versioned");
+        let mut old: Client<V1> = ClientBuilder::new()
+            .append(true)
+            .frame_version(1)
+            .open(&path)
+            .unwrap();
+        old.write(&V1 { id: 1 }).unwrap();
+        drop(old);
+        let mut new: Client<V2> = ClientBuilder::new()
+            .append(true)
+            .frame_version(2)
+            .open(&path)
+            .unwrap();
+        let v2 = V2 {
+            id: 2,
+            name: Some("two".to_owned()),
+        };
+        new.write(&v2).unwrap();
+        assert!(new.load().is_err());
+        let loaded = new
+            .load_versioned(|version, bytes| match version {
+                1 => {
+                    let V1 { id } = bincode::deserialize(bytes)?;
+                    Ok(V2 { id, name: None })
+                }
+                _ => Ok(bincode::deserialize(bytes)?),
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded, vec![V2 { id: 1, name: None }, v2]);
+        let mut unversioned: Client<Test> = Client::new(temp_path("unversioned"), false).unwrap();
+        assert!(unversioned.load_versioned(|_, _| unreachable!()).is_err());
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(temp_path("unversioned")).unwrap();
+    }
+
+    #[test]
+    fn describe_summarizes_frames() {
+        let path = temp_path("describe");
+        let mut client: Client<u64> = Client::new(&path, false).unwrap();
+        client.write_many(&[1, 2, 3]).unwrap();
+        assert_eq!(
+            client.describe(false).unwrap(),
+            "3 documents, 48 B, 24 bytes overhead, all checksums valid\n"
+        );
+        drop(client);
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[crate::FRAME_HEADER_LEN] ^= 1;
+        bytes.truncate(bytes.len() - 1);
+        std::fs::write(&path, &bytes).unwrap();
+        let mut client: Client<u64> = Client::new(&path, true).unwrap();
+        let description = client.describe(true).unwrap();
+        let mut lines = description.lines();
+        assert_eq!(
+            lines.next(),
+            Some("1 documents, 47 B, 39 bytes overhead, 1 corrupted frames, truncated")
+        );
+        assert!(lines.next().unwrap().contains("corrupted, expected"));
+        assert!(lines.next().unwrap().ends_with("  ok"));
+        assert!(lines.next().unwrap().ends_with("  truncated"));
+        assert_eq!(lines.next(), None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_boxed_matches_load() {
+        let path = temp_path("load-boxed");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        assert!(client.load_boxed().unwrap().is_none());
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(*client.load_boxed().unwrap().unwrap(), *test_messages);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn validate_decodable_catches_wrong_type() {
+        let path = temp_path("validate-decodable");
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&generate_test_data()).unwrap();
+        assert_eq!(client.validate_decodable().unwrap(), 3);
+        let mut wrong: Client<(u64, u64, u64)> = Client::new(&path, true).unwrap();
+        assert!(wrong.validate().unwrap());
+        assert!(wrong.validate_decodable().is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn failed_append_rolls_back_to_frame_boundary() {
+        struct FailAfter<'a> {
+            file: &'a mut std::fs::File,
+            left: usize,
+        }
+
+        impl Write for FailAfter<'_> {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                if self.left == 0 {
+                    return Err(std::io::Error::other("disk full"));
+                }
+                let n = self.file.write(&buf[..buf.len().min(self.left)])?;
+                self.left -= n;
+                Ok(n)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.file.flush()
+            }
+        }
+
+        let path = temp_path("append-rollback");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        client.write(&test_messages[0]).unwrap();
+        let len = std::fs::metadata(&path).unwrap().len();
+        let result = super::append_or_rollback(&mut client.file, len, |file| {
+            let mut writer = FailAfter { file, left: 10 };
+            write_encoded::<Bincode, _, Test, _>(
+                &mut writer,
+                &test_messages[1..],
+                &client.layout,
+                &mut Vec::new(),
+            )
+        });
+        assert!(result.is_err());
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), len);
+        client.write(&test_messages[2]).unwrap();
+        assert_eq!(
+            client.load().unwrap().unwrap(),
+            [test_messages[0].clone(), test_messages[2].clone()]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn count_where_counts_matches() {
+        let path = temp_path("count-where");
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&generate_test_data()).unwrap();
+        assert_eq!(client.count_where(|test| test.id >= 2).unwrap(), 2);
+        assert_eq!(client.count_where(|_| false).unwrap(), 0);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_filter_keeps_matches() {
+        let path = temp_path("load-filter");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        assert_eq!(
+            client.load_filter(|test| test.id >= 2).unwrap(),
+            test_messages[1..]
+        );
+        assert!(client.load_filter(|_| false).unwrap().is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn append_unique_skips_duplicates() {
+        let path = temp_path("append-unique");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new()
+            .append(true)
+            .tombstones(true)
+            .open(&path)
+            .unwrap();
+        client.write(&test_messages[0]).unwrap();
+        assert!(!client.append_unique(&test_messages[0]).unwrap());
+        assert!(client.append_unique(&test_messages[1]).unwrap());
+        assert!(!client.append_unique(&test_messages[1]).unwrap());
+        client.remove_at(0).unwrap();
+        assert!(client.append_unique(&test_messages[0]).unwrap());
+        assert_eq!(
+            client.load().unwrap().unwrap(),
+            [test_messages[1].clone(), test_messages[0].clone()]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn file_region_is_bounded() {
+        let path = temp_path("file-region");
+        let test_messages = generate_test_data();
+        let frames = vec_to_binary::<Bincode, _>(&test_messages, &Layout::default()).unwrap();
+        let mut container = b"container header".to_vec();
+        container.extend_from_slice(&frames);
+        container.extend_from_slice(b"another section");
+        std::fs::write(&path, &container).unwrap();
+        let open = |start, len| {
+            Client::<Test>::from_file_region(std::fs::File::open(&path).unwrap(), start, len)
+                .unwrap()
+        };
+        let mut client = open(16, frames.len() as u64);
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        assert_eq!(client.iter().count(), 3);
+        assert!(matches!(
+            client.write(&test_messages[0]),
+            Err(DatabaseError::Unsupported(_))
+        ));
+        let dest = temp_path("file-region-filtered");
+        assert_eq!(client.filter_into(&dest, |_| true).unwrap(), 3);
+        let filtered: Client<Test> = Client::new(&dest, true).unwrap();
+        assert_eq!(filtered.load().unwrap().unwrap(), test_messages);
+        std::fs::remove_file(dest).unwrap();
+        let client = open(16, frames.len() as u64 - 1);
+        assert!(client.load().is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn update_mode_keeps_documents() {
+        let path = temp_path("update-mode");
+        let test_messages = generate_test_data();
+        Client::<Test>::create_with(&path, test_messages[..2].to_vec()).unwrap();
+        let mut client: Client<Test> = ClientBuilder::new()
+            .open_mode(crate::OpenMode::Update)
+            .open(&path)
+            .unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), &test_messages[..2]);
+        assert!(client.upsert(|test| test.id, &test_messages[0]).unwrap());
+        client.write(&test_messages[2]).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        drop(client);
+        let client: Client<Test> = Client::new(&path, false).unwrap();
+        assert!(client.load().unwrap().is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_from_reader_replaces_documents() {
+        let path = temp_path("load-from-reader");
+        let source_path = temp_path("load-from-reader-source");
+        let test_messages = generate_test_data();
+        let mut builder = ClientBuilder::new();
+        builder.tombstones(true);
+        let mut source: Client<Test> = builder.open(&source_path).unwrap();
+        source.write_many(&test_messages).unwrap();
+        source.remove_at(1).unwrap();
+        let bytes = std::fs::read(&source_path).unwrap();
+        let mut client: Client<Test> = builder.open(&path).unwrap();
+        client.write(&test_messages[1]).unwrap();
+        assert_eq!(client.load_from_reader(bytes.as_slice()).unwrap(), 2);
+        let expected = [test_messages[0].clone(), test_messages[2].clone()];
+        assert_eq!(client.load().unwrap().unwrap(), expected);
+        assert!(client.load_from_reader(&bytes[..bytes.len() - 1]).is_err());
+        assert_eq!(client.load().unwrap().unwrap(), expected);
+        let mut plain: Client<Test> =
+            Client::new(temp_path("load-from-reader-plain"), false).unwrap();
+        assert!(matches!(
+            plain.load_from_reader(bytes.as_slice()),
+            Err(DatabaseError::Unsupported(_))
+        ));
+        source.finalize().unwrap();
+        let bytes = std::fs::read(&source_path).unwrap();
+        assert_eq!(client.load_from_reader(bytes.as_slice()).unwrap(), 2);
+        assert_eq!(client.load().unwrap().unwrap(), expected);
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(source_path).unwrap();
+        std::fs::remove_file(temp_path("load-from-reader-plain")).unwrap();
+    }
+
+    #[test]
+    fn load_from_reader_reads_frames_shorter_than_a_footer() {
+        let path = temp_path("load-from-reader-short");
+        let bytes = vec_to_binary::<Bincode, u8>(&[1, 2, 3], &Layout::default()).unwrap();
+        let mut client: Client<u8> = Client::new(&path, false).unwrap();
+        assert_eq!(client.load_from_reader(bytes.as_slice()).unwrap(), 3);
+        assert_eq!(client.load().unwrap().unwrap(), [1, 2, 3]);
+        client.finalize().unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(client.load_from_reader(bytes.as_slice()).unwrap(), 3);
+        assert_eq!(client.load().unwrap().unwrap(), [1, 2, 3]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn cursor_skips_and_seeks_frames() {
+        let path = temp_path("cursor");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        let mut cursor = client.cursor().unwrap();
+        assert_eq!(cursor.next().unwrap().unwrap(), test_messages[0]);
+        let second = cursor.current_offset();
+        assert!(cursor.skip_frame().unwrap());
+        assert_eq!(cursor.frame_index(), 2);
+        assert_eq!(cursor.next().unwrap().unwrap(), test_messages[2]);
+        assert!(cursor.next().is_none());
+        assert!(!cursor.skip_frame().unwrap());
+        assert!(cursor.seek_to_frame(1).unwrap());
+        assert_eq!(cursor.current_offset(), second);
+        assert_eq!(cursor.next().unwrap().unwrap(), test_messages[1]);
+        assert!(!cursor.seek_to_frame(5).unwrap());
+        assert_eq!(cursor.frame_index(), 3);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn append_with_budget_keeps_newest_documents() {
+        let path = temp_path("append-budget");
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        let frame_len = client.write(&generate_test_data()[0]).unwrap().bytes;
+        let max_bytes = frame_len * 8;
+        let mut removed = 0;
+        for message in generate_test_data().into_iter().cycle().take(20) {
+            removed += client.append_with_budget(&message, max_bytes).unwrap();
+            assert!(client.file.metadata().unwrap().len() <= max_bytes);
+        }
+        let documents = client.load().unwrap().unwrap();
+        assert_eq!(documents.len() + removed, 21);
+        assert_eq!(documents.last().unwrap(), &generate_test_data()[19 % 3]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn writing_no_documents_leaves_file_untouched() {
+        let path = temp_path("write-many-empty");
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        let report = client.write_many(&[]).unwrap();
+        assert_eq!(report.bytes, 0);
+        assert!(!report.created);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+        assert_eq!(client.load().unwrap(), None);
+        client.write_many(&generate_test_data()).unwrap();
+        let len = std::fs::metadata(&path).unwrap().len();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(client.write_many(&[]).unwrap().bytes, 0);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), len);
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&[]).unwrap();
+        assert_eq!(client.load().unwrap(), None);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+        let reader: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(reader.load().unwrap(), None);
+        drop(client);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn replace_all_with_no_documents_empties_file() {
+        let path = temp_path("replace-all-empty");
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        client.write_many(&generate_test_data()).unwrap();
+        assert_eq!(client.replace_all(&[]).unwrap(), 0);
+        assert_eq!(client.load().unwrap(), None);
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().len(),
+            client.layout.data_start
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn inspect_header_reads_layout_without_creating() {
+        let path = temp_path("inspect-header");
+        assert!(matches!(
+            crate::inspect_header(&path),
+            Err(DatabaseError::Open { .. })
+        ));
+        assert!(!path.exists());
+        let mut client: Client<Test> = ClientBuilder::new()
+            .append(true)
+            .timestamps(true)
+            .endianness(Endianness::Big)
+            .open(&path)
+            .unwrap();
+        client.write_many(&generate_test_data()).unwrap();
+        let info = crate::inspect_header(&path).unwrap();
+        assert_eq!(
+            info.version.map(|(major, _)| major),
+            Some(crate::FORMAT_VERSION)
+        );
+        assert!(info.append_log && info.timestamps);
+        assert!(!info.tombstones && !info.gzip);
+        assert_eq!(info.endianness, Endianness::Big);
+        assert_eq!(info.format, <Bincode as crate::Format>::ID);
+        assert_eq!(info.checksum_check, crc::CRC_32_ISO_HDLC.check);
+        assert_eq!(info.data_start, client.layout.data_start);
+        std::fs::write(&path, []).unwrap();
+        assert_eq!(crate::inspect_header(&path).unwrap().version, None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn into_file_returns_handle_at_end_of_documents() {
+        use std::io::{Seek, SeekFrom, Write};
+
+        let path = temp_path("into-file");
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        client.write_many(&generate_test_data()).unwrap();
+        client.finalize().unwrap();
+        let footer_start = std::fs::metadata(&path).unwrap().len() - crate::FOOTER_LEN as u64;
+        let mut file = client.into_file().unwrap();
+        assert_eq!(file.stream_position().unwrap(), footer_start);
+        file.seek(SeekFrom::End(0)).unwrap();
+        file.write_all(b"trailer").unwrap();
+        let region = Client::<Test>::from_file_region(file, 0, footer_start).unwrap();
+        assert_eq!(
+            region.into_file().unwrap().stream_position().unwrap(),
+            footer_start
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn backend_holds_same_bytes_as_file() {
+        let path = temp_path("backend");
+        let test_messages = generate_test_data();
+        let mut file_client: Client<Test> = Client::new(&path, true).unwrap();
+        file_client.write_many(&test_messages).unwrap();
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        let mut client = Client::<Test, Bincode, _>::from_backend(&mut bytes, true).unwrap();
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        assert_eq!(client.iter().count(), 3);
+        drop(client);
+        assert_eq!(*bytes.get_ref(), std::fs::read(&path).unwrap());
+        let mut client = Client::<Test, Bincode, _>::from_backend(bytes, true).unwrap();
+        assert_eq!(client.pop().unwrap(), Some(test_messages[2].clone()));
+        client.replace(1, &test_messages[1]).unwrap();
+        client.replace_all(&test_messages[1..]).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), &test_messages[1..]);
+        let bytes = client.into_backend().unwrap();
+        let client = Client::<Test, Bincode, _>::from_backend(bytes, false).unwrap();
+        assert!(client.load().unwrap().is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn split_into_distributes_documents_contiguously() {
+        let root = temp_path("split-into");
+        std::fs::create_dir_all(&root).unwrap();
+        let path = root.join("data.crio");
+        let dir = root.join("parts");
+        let test_messages: Vec<Test> = generate_test_data().into_iter().cycle().take(7).collect();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        let paths = client.split_into(&dir, 3).unwrap();
+        assert_eq!(paths[0], dir.join("data.0.crio"));
+        let mut loaded = Vec::new();
+        for (path, len) in paths.iter().zip([3, 2, 2]) {
+            let part: Client<Test> = Client::new(path, true).unwrap();
+            let documents = part.load().unwrap().unwrap();
+            assert_eq!(documents.len(), len);
+            loaded.extend(documents);
+        }
+        assert_eq!(loaded, test_messages);
+        assert!(matches!(
+            client.split_into(&dir, 0),
+            Err(DatabaseError::Unsupported(_))
+        ));
+        let blocked = dir.join("data.1.crio");
+        std::fs::remove_file(&blocked).unwrap();
+        std::fs::create_dir(&blocked).unwrap();
+        assert!(matches!(
+            client.split_into(&dir, 3),
+            Err(DatabaseError::Open { path, .. }) if path == blocked
+        ));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn verify_after_write_detects_corrupted_frames() {
+        let path = temp_path("verify-after-write");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new()
+            .verify_after_write(true)
+            .open(&path)
+            .unwrap();
+        client.write(&test_messages[0]).unwrap();
+        client.write_many(&test_messages[1..]).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        let len = client.scan_frames().unwrap()[2].offset;
+        let mut bytes = std::fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        std::fs::write(&path, &bytes).unwrap();
+        let e = super::verify_written(&mut client.file, &client.layout, len).unwrap_err();
+        assert!(matches!(e, DatabaseError::WriteVerifyFailed { offset } if offset == len));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn compact_to_latest_keeps_last_snapshot() {
+        let path = temp_path("latest-snapshot");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(client.load_latest_snapshot().unwrap(), None);
+        assert_eq!(client.compact_to_latest().unwrap(), 0);
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(
+            client.load_latest_snapshot().unwrap().unwrap(),
+            test_messages[2]
+        );
+        assert_eq!(client.compact_to_latest().unwrap(), 2);
+        assert_eq!(client.load().unwrap().unwrap(), test_messages[2..]);
+        assert_eq!(client.compact_to_latest().unwrap(), 0);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn app_id_guards_files_of_other_applications() {
+        let path = temp_path("app-id");
+        let mut client: Client<Test> = ClientBuilder::new()
+            .app_id(b"MYAPP001")
+            .open(&path)
+            .unwrap();
+        client.write_many(&generate_test_data()).unwrap();
+        assert_eq!(
+            crate::inspect_header(&path).unwrap().app_id,
+            Some(*b"MYAPP001")
+        );
+        let client: Client<Test> = ClientBuilder::new()
+            .app_id(b"MYAPP001")
+            .append(true)
+            .open(&path)
+            .unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), generate_test_data());
+        let mut other = ClientBuilder::new();
+        other.app_id(b"OTHERAPP");
+        for mode in [crate::OpenMode::Update, crate::OpenMode::Overwrite] {
+            let e = other
+                .open_mode(mode)
+                .open::<Test, Bincode, _>(&path)
+                .err()
+                .unwrap();
+            assert!(matches!(
+                e,
+                DatabaseError::WrongAppId { expected, found: Some(found) }
+                    if expected == *b"OTHERAPP" && found == *b"MYAPP001"
+            ));
+        }
+        let client: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), generate_test_data());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn iter_continues_after_corrupted_document() {
+        let path = temp_path("iter-corrupted");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        let second = client.scan_frames().unwrap()[1].offset as usize;
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[second + crate::FRAME_HEADER_LEN] ^= 1;
+        std::fs::write(&path, &bytes).unwrap();
+        let results: Vec<_> = client.iter().collect();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap(), &test_messages[0]);
+        assert!(matches!(
+            results[1],
+            Err(DatabaseError::MismatchedChecksum { .. })
+        ));
+        assert_eq!(results[2].as_ref().unwrap(), &test_messages[2]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn exclusive_clients_exclude_each_other() {
+        let path = temp_path("exclusive");
+        let mut builder = ClientBuilder::new();
+        builder.append(true).exclusive(true);
+        let mut client: Client<Test> = builder.open(&path).unwrap();
+        let Err(e) = builder.open::<Test, Bincode, _>(&path) else {
+            panic!("expected the file to be locked");
+        };
+        assert!(matches!(e, DatabaseError::Locked(ref locked) if *locked == path));
+        assert_eq!(e.kind(), crate::DatabaseErrorKind::Io);
+        client
+            .lock()
+            .unwrap()
+            .write(&generate_test_data()[0])
+            .unwrap();
+        drop(client);
+        let mut client: Client<Test> = builder.open(&path).unwrap();
+        assert_eq!(client.len().unwrap(), 1);
+        drop(client);
+        std::fs::remove_file(&path).unwrap();
+        let mut lock_path = path.into_os_string();
+        lock_path.push(".lock");
+        std::fs::remove_file(lock_path).unwrap();
+    }
+
+    #[test]
+    fn overwrite_replaces_file_only_once_written() {
+        let path = temp_path("atomic-overwrite");
+        let mut temp_path = path.clone().into_os_string();
+        temp_path.push(".tmp");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        drop(client);
+        let old = std::fs::read(&path).unwrap();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        assert_eq!(client.load().unwrap(), None);
+        assert_eq!(std::fs::read(&path).unwrap(), old);
+        client.write(&test_messages[2]).unwrap();
+        assert!(!std::path::Path::new(&temp_path).exists());
+        let reopened: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(reopened.load().unwrap().unwrap(), test_messages[2..]);
+        drop((client, reopened));
+        let client: Client<Test> = Client::new(&path, false).unwrap();
+        drop(client);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn len_counts_documents_without_decoding() {
+        let path = temp_path("len");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new().tombstones(true).open(&path).unwrap();
+        assert_eq!(client.len().unwrap(), 0);
+        assert!(client.is_empty().unwrap());
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(client.len().unwrap(), 3);
+        client.remove_at(0).unwrap();
+        assert_eq!(client.len().unwrap(), 2);
+        assert!(!client.is_empty().unwrap());
+        client.remove_at(0).unwrap();
+        client.remove_at(0).unwrap();
+        assert!(client.is_empty().unwrap());
+        client.write(&test_messages[0]).unwrap();
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(file.metadata().unwrap().len() - 1).unwrap();
+        let e = client.len().unwrap_err();
+        assert_eq!(e.kind(), crate::DatabaseErrorKind::Truncated);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_recovers_documents_before_corruption() {
+        let path = temp_path("data-poison");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        client.write_many(&test_messages).unwrap();
+        let mut raw = std::fs::read(&path).unwrap();
+        *raw.last_mut().unwrap() ^= 0xff;
+        std::fs::write(&path, &raw).unwrap();
+        let client: Client<Test> = Client::new(&path, true).unwrap();
+        let Err(LoadError::Poisoned(e)) = client.load() else {
+            panic!("expected a poisoned load");
+        };
+        assert_ne!(e.saved(), e.expected());
+        assert_eq!(e.into_inner(), test_messages[..2]);
+        assert!(matches!(
+            DatabaseError::from(client.load().unwrap_err()),
+            DatabaseError::CorruptedFrame { .. }
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_errors_know_the_offset_of_the_frame() {
+        let path = temp_path("error-offset");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages).unwrap();
+        let frames = client.scan_frames().unwrap();
+        let mut raw = std::fs::read(&path).unwrap();
+        raw[frames[1].offset as usize + crate::FRAME_HEADER_LEN] ^= 1;
+        std::fs::write(&path, &raw).unwrap();
+        let Err(LoadError::Poisoned(e)) = client.load() else {
+            panic!("expected a poisoned load");
+        };
+        assert_eq!(e.offset(), frames[1].offset);
+        raw.truncate(raw.len() - 2);
+        std::fs::write(&path, &raw).unwrap();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        let Err(DatabaseError::TruncatedFrame {
+            offset,
+            expected_len,
+            available,
+        }) = client.load_unchecked()
+        else {
+            panic!("expected a truncated frame");
+        };
+        assert_eq!(offset, frames[2].offset);
+        assert_eq!(u64::from(expected_len), available + 2);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn short_file_is_a_truncated_header() {
+        let path = temp_path("truncated-header");
+        std::fs::write(&path, [1, 2, 3, 4, 5]).unwrap();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        let e = client.load().unwrap_err();
+        assert!(matches!(
+            e,
+            LoadError::Database(DatabaseError::TruncatedHeader(5))
+        ));
+        assert_eq!(e.kind(), crate::DatabaseErrorKind::Truncated);
+        assert!(matches!(
+            client.iter().next(),
+            Some(Err(DatabaseError::TruncatedHeader(5)))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn create_with_writes_documents() {
+        let path = temp_path("create-with");
+        let test_messages = generate_test_data();
+        std::fs::write(&path, b"stale").unwrap();
+        let client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn migrate_converts_every_document() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Tagged {
+            id: usize,
+            message: String,
+            tag: Option<String>,
+        }
+
+        let path = temp_path("migrate-type");
+        assert!(matches!(
+            Client::<Test>::migrate(&path, |_| 0),
+            Err(DatabaseError::Open { .. })
+        ));
+        assert!(!path.exists());
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        client.remove(0).unwrap();
+        drop(client);
+        let migrated = Client::<Test>::migrate(&path, |test| Tagged {
+            id: test.id,
+            message: test.message,
+            tag: None,
+        })
+        .unwrap();
+        assert_eq!(migrated, 2);
+        let client: Client<Tagged> = Client::new(&path, true).unwrap();
+        let loaded = client.load().unwrap().unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].id, test_messages[1].id);
+        assert_eq!(loaded[1].tag, None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// Fails every other call with `ErrorKind::Interrupted`, like a syscall hit by signals.
+    struct Interrupting<I> {
+        inner: I,
+        interrupt: bool,
+    }
+
+    impl<I> Interrupting<I> {
+        fn new(inner: I) -> Self {
+            Self {
+                inner,
+                interrupt: true,
+            }
+        }
+
+        fn interrupted(&mut self) -> bool {
+            self.interrupt = !self.interrupt;
+            !self.interrupt
+        }
+    }
+
+    impl<R: std::io::Read> std::io::Read for Interrupting<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.interrupted() {
+                return Err(std::io::ErrorKind::Interrupted.into());
+            }
+            self.inner.read(buf)
+        }
+    }
+
+    impl<W: Write> Write for Interrupting<W> {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.interrupted() {
+                return Err(std::io::ErrorKind::Interrupted.into());
+            }
+            self.inner.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    #[test]
+    fn interrupted_io_is_retried() {
+        let test_messages = generate_test_data();
+        let layout = Layout::default();
+        let mut writer = Interrupting::new(Vec::new());
+        write_encoded::<Bincode, _, Test, _>(&mut writer, &test_messages, &layout, &mut Vec::new())
+            .unwrap();
+        let expected = vec_to_binary::<Bincode, _>(&test_messages, &layout).unwrap();
+        assert_eq!(writer.inner, expected);
+        let mut reader = Interrupting::new(expected.as_slice());
+        for message in &test_messages {
+            let body = read_frame(&mut reader, &layout)
+                .unwrap()
+                .verify(&layout)
+                .unwrap();
+            assert_eq!(&bincode::deserialize::<Test>(&body).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn record_iter_estimates_remaining_documents() {
+        let path = temp_path("record-iter");
+        let test_messages = vec![generate_test_data()[1].clone(); 10];
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        let mut iter = client.iter();
+        assert_eq!(iter.size_hint().0, 0);
+        assert_eq!(iter.next().unwrap().unwrap(), test_messages[0]);
+        assert_eq!(iter.size_hint().0, 9);
+        assert!(iter.size_hint().1.unwrap() >= 9);
+        assert_eq!(iter.collect::<Result<Vec<_>, _>>().unwrap().len(), 9);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn custom_checksum_algorithm() {
+        const BZIP2: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_BZIP2);
+        let path = temp_path("checksum-algorithm");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new()
+            .checksum_algorithm(&crc::CRC_32_BZIP2)
+            .open(&path)
+            .unwrap();
+        client.write_many(&test_messages).unwrap();
+        let frames = client.scan_frames().unwrap();
+        let encoded = bincode::serialize(&test_messages[0]).unwrap();
+        assert_eq!(frames[0].checksum, BZIP2.checksum(&encoded));
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        assert!(matches!(
+            Client::<Test>::new(&path, true),
+            Err(DatabaseError::ChecksumAlgorithmMismatch { .. })
+        ));
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        assert!(matches!(
+            ClientBuilder::new()
+                .append(true)
+                .checksum_algorithm(&crc::CRC_32_BZIP2)
+                .open::<Test, Bincode, _>(&path),
+            Err(DatabaseError::ChecksumAlgorithmMismatch { .. })
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn crc64_catches_corruption_behind_frame_checksum() {
+        let path = temp_path("checksum-crc64");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new()
+            .checksum(crate::Checksum::Crc64)
+            .open(&path)
+            .unwrap();
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        assert_eq!(
+            crate::inspect_header(&path).unwrap().checksum,
+            crate::Checksum::Crc64
+        );
+        let frames = client.scan_frames().unwrap();
+        let mut raw = std::fs::read(&path).unwrap();
+        raw[frames[2].offset as usize - 1] ^= 0xff;
+        std::fs::write(&path, &raw).unwrap();
+        client.refresh_checksum_at(1).unwrap();
+        assert!(matches!(
+            client.load(),
+            Err(LoadError::Database(
+                DatabaseError::MismatchedChecksum64 { .. }
+            ))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn no_checksum_leaves_frame_checksums_unset() {
+        let path = temp_path("checksum-none");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new()
+            .checksum(crate::Checksum::None)
+            .open(&path)
+            .unwrap();
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(
+            crate::inspect_header(&path).unwrap().checksum,
+            crate::Checksum::None
+        );
+        let frames = client.scan_frames().unwrap();
+        assert!(frames.iter().all(|frame| frame.checksum == 0));
+        let mut raw = std::fs::read(&path).unwrap();
+        *raw.last_mut().unwrap() ^= 1;
+        std::fs::write(&path, &raw).unwrap();
+        let reopened: Client<Test> = Client::new(&path, true).unwrap();
+        let documents = reopened.load().unwrap().unwrap();
+        assert_eq!(documents[..2], test_messages[..2]);
+        assert_ne!(documents[2], test_messages[2]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn write_header_marks_default_layout_files() {
+        let path = temp_path("write-header");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new().write_header(true).open(&path).unwrap();
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(&std::fs::read(&path).unwrap()[..4], &crate::MAGIC);
+        assert_eq!(
+            crate::inspect_header(&path).unwrap().version,
+            Some((crate::FORMAT_VERSION, 0))
+        );
+        drop(client);
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        client.vacuum().unwrap();
+        assert_eq!(&std::fs::read(&path).unwrap()[..4], &crate::MAGIC);
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn writing_and_reading_need_one_half_of_serde_each() {
+        #[derive(Serialize)]
+        struct Event<'a> {
+            id: u64,
+            message: &'a str,
+        }
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Seen {
+            id: u64,
+            message: String,
+        }
+        let path = temp_path("split-bounds");
+        let mut producer: Client<Event<'_>> = Client::new(&path, true).unwrap();
+        producer
+            .write(&Event {
+                id: 1,
+                message: "hello",
+            })
+            .unwrap();
+        let mut consumer: Client<Seen> = Client::new(&path, true).unwrap();
+        let expected = Seen {
+            id: 1,
+            message: "hello".to_owned(),
+        };
+        assert_eq!(consumer.load().unwrap().unwrap(), vec![expected]);
+        assert_eq!(consumer.len().unwrap(), 1);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn encode_many_matches_written_bytes() {
+        let path = temp_path("encode-many");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        let encoded = client.encode_many(&test_messages).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), encoded);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn vacuum_replaces_file_with_open_reader() {
+        let path = temp_path("vacuum-open-reader");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new().tombstones(true).open(&path).unwrap();
+        client.write_many(&test_messages).unwrap();
+        let mut reader = client.reader().unwrap();
+        client.remove_at(0).unwrap();
+        assert!(client.vacuum().unwrap() > 0);
+        assert_eq!(client.load().unwrap().unwrap(), test_messages[1..]);
+        client.write(&test_messages[0]).unwrap();
+        assert_eq!(client.load().unwrap().unwrap().len(), 3);
+        assert_eq!(reader.load().unwrap().unwrap(), test_messages[1..]);
+        drop(reader);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn trim_prefix_drops_oldest_documents() {
+        let path = temp_path("trim-prefix");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        assert_eq!(client.trim_prefix(0).unwrap(), 0);
+        assert_eq!(client.trim_prefix(2).unwrap(), 2);
+        assert_eq!(client.load().unwrap().unwrap(), test_messages[2..]);
+        assert_eq!(client.trim_prefix(5).unwrap(), 1);
+        assert_eq!(client.load().unwrap(), None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn trim_prefix_drops_footer_of_finalized_file() {
+        let path = temp_path("trim-prefix-footer");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        client.finalize().unwrap();
+        assert_eq!(client.trim_prefix(1).unwrap(), 1);
+        assert_eq!(client.load().unwrap().unwrap(), test_messages[1..]);
+        client.finalize().unwrap();
+        assert_eq!(client.compact_to_latest().unwrap(), 1);
+        assert_eq!(client.load().unwrap().unwrap(), test_messages[2..]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn probe_detects_wrong_type() {
+        #[derive(Serialize, Deserialize)]
+        struct Wide {
+            a: u64,
+            b: u64,
+            c: u64,
+            d: u64,
+            e: u64,
+        }
+
+        let path = temp_path("probe");
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        assert!(client.probe().unwrap());
+        client.write_many(&generate_test_data()).unwrap();
+        assert!(client.probe().unwrap());
+        let mut wide: Client<Wide> = Client::new(&path, true).unwrap();
+        assert!(!wide.probe().unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn aligned_frames_start_on_boundaries() {
+        let path = temp_path("aligned");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new()
+            .align(16)
+            .tombstones(true)
+            .open(&path)
+            .unwrap();
+        client.write_many(&test_messages).unwrap();
+        let frames = client.scan_frames().unwrap();
+        assert_eq!(frames.len(), test_messages.len());
+        assert!(frames.iter().all(|frame| (frame.offset + 9) % 16 == 0));
+        assert!(client.verify().unwrap().is_ok());
+        client.remove_at(0).unwrap();
+        client.vacuum().unwrap();
+        let client: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages[1..]);
+        assert!(ClientBuilder::new()
+            .align(12)
+            .open::<Test, Bincode, _>(&path)
+            .is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn replace_all_iter_rewrites_file() {
+        let path = temp_path("replace-all-iter");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        let replaced = client
+            .replace_all_iter(test_messages.iter().rev().cloned())
+            .unwrap();
+        assert_eq!(replaced, 3);
+        let mut expected = test_messages.clone();
+        expected.reverse();
+        assert_eq!(client.load().unwrap().unwrap(), expected);
+        client.write(&test_messages[0]).unwrap();
+        expected.push(test_messages[0].clone());
+        let client: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), expected);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn position_is_end_after_write() {
+        let path = temp_path("position");
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&generate_test_data()).unwrap();
+        let len = std::fs::metadata(&path).unwrap().len();
+        assert_eq!(client.position().unwrap(), len);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn for_each_stops_early() {
+        let path = temp_path("for-each");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        let mut seen = Vec::new();
+        client
+            .for_each(|document| {
+                seen.push(document.id);
+                if seen.len() == 2 {
+                    std::ops::ControlFlow::Break(())
+                } else {
+                    std::ops::ControlFlow::Continue(())
+                }
+            })
+            .unwrap();
+        assert_eq!(seen, vec![1, 2]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn verify_on_open_reports_corruption() {
+        let path = temp_path("verify-on-open");
+        let test_messages = generate_test_data();
+        Client::<Test>::create_with(&path, test_messages).unwrap();
+        let open = || {
+            ClientBuilder::new()
+                .append(true)
+                .verify_on_open(true)
+                .open::<Test, Bincode, _>(&path)
+        };
+        assert!(open().is_ok());
+        let mut raw = std::fs::read(&path).unwrap();
+        let last = raw.len() - 1;
+        raw[last] ^= 0xff;
+        std::fs::write(&path, &raw).unwrap();
+        let offset = Client::<Test>::new(&path, true)
+            .unwrap()
+            .scan_frames()
+            .unwrap()[2]
+            .offset;
+        assert!(matches!(
+            open(),
+            Err(DatabaseError::CorruptedFrame { offset: o, .. }) if o == offset
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn new_verified_rejects_corrupt_file_in_append_mode() {
+        let path = temp_path("new-verified");
+        let test_messages = generate_test_data();
+        Client::<Test>::create_with(&path, test_messages).unwrap();
+        assert!(Client::<Test>::new_verified(&path, true).is_ok());
+        let mut raw = std::fs::read(&path).unwrap();
+        let last = raw.len() - 1;
+        raw[last] ^= 0xff;
+        std::fs::write(&path, &raw).unwrap();
+        let offset = Client::<Test>::new(&path, true)
+            .unwrap()
+            .scan_frames()
+            .unwrap()[2]
+            .offset;
+        assert!(matches!(
+            Client::<Test>::new_verified(&path, true),
+            Err(DatabaseError::CorruptedFrame { offset: o, .. }) if o == offset
+        ));
+        let client = Client::<Test>::new_verified(&path, false).unwrap();
+        drop(client);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn append_try_iter_stops_at_first_error() {
+        let path = temp_path("append-try-iter");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        let documents = vec![
+            Ok(test_messages[0].clone()),
+            Ok(test_messages[1].clone()),
+            Err(std::fmt::Error),
+            Ok(test_messages[2].clone()),
+        ];
+        let result = client.append_try_iter(documents);
+        assert!(matches!(result, Err(MixedError::Source { written: 2, .. })));
+        assert_eq!(client.load().unwrap().unwrap(), test_messages[..2]);
+        let documents = test_messages.iter().cloned().map(Ok::<_, std::fmt::Error>);
+        assert_eq!(client.append_try_iter(documents).unwrap(), 3);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn write_iter_streams_owned_or_borrowed_documents() {
+        let path = temp_path("write-iter");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        assert_eq!(client.write_iter(test_messages.iter()).unwrap(), 3);
+        let generated = (0..2).map(|id| Test {
+            id,
+            message: id.to_string(),
+        });
+        assert_eq!(client.write_iter(generated.clone()).unwrap(), 2);
+        let mut expected = test_messages;
+        expected.extend(generated);
+        assert_eq!(client.load().unwrap().unwrap(), expected);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_chunks_yields_batches() {
+        let path = temp_path("load-chunks");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        let chunks: Vec<Vec<Test>> = client.load_chunks(2).collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            chunks,
+            vec![test_messages[..2].to_vec(), test_messages[2..].to_vec()]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn client_into_iterator() {
+        let path = temp_path("into-iterator");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        let mut ids = Vec::new();
+        for document in &mut client {
+            ids.push(document.unwrap().id);
+        }
+        assert_eq!(ids, vec![1, 2, 3]);
+        let documents: Vec<Test> = client.into_iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(documents, test_messages);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn overwrite_refuses_append_log() {
+        let path = temp_path("mode-conflict");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        client.write_many(&test_messages).unwrap();
+        drop(client);
+        assert!(matches!(
+            Client::<Test>::new(&path, false),
+            Err(DatabaseError::ModeConflict(_))
+        ));
+        assert_eq!(
+            Client::<Test>::new(&path, true)
+                .unwrap()
+                .load()
+                .unwrap()
+                .unwrap(),
+            test_messages
+        );
+        let client = ClientBuilder::new()
+            .force(true)
+            .open::<Test, Bincode, _>(&path)
+            .unwrap();
+        assert!(client.load().unwrap().is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn get_many_keeps_index_order() {
+        let path = temp_path("get-many");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        let documents = client.get_many(&[2, 7, 0, 2]).unwrap();
+        assert_eq!(
+            documents,
+            vec![
+                Some(test_messages[2].clone()),
+                None,
+                Some(test_messages[0].clone()),
+                Some(test_messages[2].clone()),
+            ]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_range_returns_a_page() {
+        let path = temp_path("load-range");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new().tombstones(true).open(&path).unwrap();
+        client.write_many(&test_messages).unwrap();
+        client.write_many(&test_messages).unwrap();
+        client.remove_at(0).unwrap();
+        assert_eq!(
+            client.load_range(1, 2).unwrap(),
+            vec![test_messages[2].clone(), test_messages[0].clone()]
+        );
+        assert_eq!(client.load_range(3, 10).unwrap(), test_messages[1..]);
+        assert!(client.load_range(5, 1).unwrap().is_empty());
+        assert!(client.load_range(0, 0).unwrap().is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn first_and_last_skip_removed_documents() {
+        let path = temp_path("first-last");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = ClientBuilder::new().tombstones(true).open(&path).unwrap();
+        assert_eq!(client.first().unwrap(), None);
+        assert_eq!(client.last().unwrap(), None);
+        client.write_many(&test_messages).unwrap();
+        assert_eq!(client.first().unwrap().as_ref(), Some(&test_messages[0]));
+        assert_eq!(client.last().unwrap().as_ref(), Some(&test_messages[2]));
+        client.remove_at(0).unwrap();
+        client.remove_at(1).unwrap();
+        assert_eq!(client.first().unwrap().as_ref(), Some(&test_messages[1]));
+        assert_eq!(client.last().unwrap().as_ref(), Some(&test_messages[1]));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn write_through_round_trips() {
+        let path = temp_path("write-through");
+        let test_messages = generate_test_data();
+        let mut client = ClientBuilder::new()
+            .append(true)
+            .tombstones(true)
+            .write_through(true)
+            .open::<Test, Bincode, _>(&path)
+            .unwrap();
+        client.write_many(&test_messages).unwrap();
+        assert!(client.remove_at(0).unwrap());
+        client.vacuum().unwrap();
+        client.write(&test_messages[0]).unwrap();
+        let mut expected = test_messages[1..].to_vec();
+        expected.push(test_messages[0].clone());
+        assert_eq!(client.load().unwrap().unwrap(), expected);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn migrate_adds_header_once() {
+        let path = temp_path("migrate");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        client.write_many(&test_messages).unwrap();
+        drop(client);
+        assert!(matches!(
+            crate::migrate::<Test, _>(&path).unwrap(),
+            crate::MigrationOutcome::Upgraded {
+                from: 0,
+                documents: 3,
+                ..
+            }
+        ));
+        assert!(std::fs::read(&path).unwrap().starts_with(b"CRIO"));
+        assert!(matches!(
+            crate::migrate::<Test, _>(&path).unwrap(),
+            crate::MigrationOutcome::AlreadyCurrent { .. }
+        ));
+        let client: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn cached_client_invalidates_on_remove() {
+        let path = temp_path("cached-client");
+        let test_messages = generate_test_data();
+        let client = ClientBuilder::new()
+            .tombstones(true)
+            .open::<Test, Bincode, _>(&path)
+            .unwrap();
+        let mut cached = crate::CachedClient::new(client, 2);
+        cached.write_many(&test_messages).unwrap();
+        assert_eq!(cached.get(1).unwrap(), Some(test_messages[1].clone()));
+        assert_eq!(cached.get(1).unwrap(), Some(test_messages[1].clone()));
+        assert_eq!(cached.get(2).unwrap(), Some(test_messages[2].clone()));
+        assert_eq!(cached.get(0).unwrap(), Some(test_messages[0].clone()));
+        let stats = cached.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 3));
+        assert!(cached.remove_at(0).unwrap());
+        assert_eq!(cached.get(0).unwrap(), Some(test_messages[1].clone()));
+        assert_eq!(cached.get(2).unwrap(), None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn filter_into_copies_matches() {
+        let path = temp_path("filter-into-source");
+        let dest = temp_path("filter-into-dest");
+        let test_messages = generate_test_data();
+        let mut client = ClientBuilder::new()
+            .timestamps(true)
+            .open::<Test, Bincode, _>(&path)
+            .unwrap();
+        client.write_many(&test_messages).unwrap();
+        client.finalize().unwrap();
+        let copied = client
+            .filter_into(&dest, |document| document.id != 2)
+            .unwrap();
+        assert_eq!(copied, 2);
+        let mut filtered: Client<Test> = Client::new(&dest, true).unwrap();
+        let source = client.load_with_timestamps().unwrap().unwrap();
+        assert_eq!(
+            filtered.load_with_timestamps().unwrap().unwrap(),
+            [source[0].clone(), source[2].clone()]
+        );
+        assert!(matches!(
+            client.filter_into(&path, |_| true),
+            Err(DatabaseError::Unsupported(_))
+        ));
+        let missing = temp_path("filter-into-missing").join("dest");
+        assert!(matches!(
+            client.filter_into(&missing, |_| true),
+            Err(DatabaseError::Open { path, .. }) if path == missing
+        ));
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(dest).unwrap();
+    }
+
+    #[test]
+    fn open_error_names_path() {
+        let path = temp_path(&"x".repeat(1024));
+        let Err(error) = Client::<Test>::new(&path, true) else {
+            panic!("opened a file with an overlong name");
+        };
+        assert!(matches!(&error, DatabaseError::Open { path: p, .. } if *p == path));
+        assert!(error.to_string().starts_with("failed to open"));
+    }
+
+    #[test]
+    fn throttled_client_coalesces_states() {
+        let path = temp_path("throttled");
+        let test_messages = generate_test_data();
+        let client: Client<Test> = Client::new(&path, false).unwrap();
+        let mut throttled =
+            crate::ThrottledClient::new(client, std::time::Duration::from_secs(3600));
+        assert!(throttled.set(&test_messages[..1]).unwrap());
+        assert!(!throttled.set(&test_messages[..2]).unwrap());
+        assert!(!throttled.set(&test_messages).unwrap());
+        assert!(throttled.is_pending());
+        let reader: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(reader.load().unwrap().unwrap(), test_messages[..1]);
+        drop(throttled);
+        let reader: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(reader.load().unwrap().unwrap(), test_messages);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn buffered_client_coalesces_writes() {
+        let path = temp_path("buffered");
+        let test_messages = generate_test_data();
+        let client: Client<Test> = Client::new(&path, true).unwrap();
+        let header_len = client.layout.data_start;
+        let mut buffered = crate::BufferedClient::new(client, 4096);
+        buffered.write(&test_messages[0]).unwrap();
+        buffered.write_many(&test_messages[1..]).unwrap();
+        assert!(buffered.buffered_len() > 0);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), header_len);
+        assert_eq!(
+            buffered.get_mut().unwrap().load().unwrap().unwrap(),
+            test_messages
+        );
+        assert_eq!(buffered.buffered_len(), 0);
+        buffered.write(&test_messages[0]).unwrap();
+        assert_eq!(buffered.load().unwrap().unwrap().len(), 4);
+        buffered.write(&test_messages[0]).unwrap();
+        buffered.sync().unwrap();
+        assert_eq!(buffered.buffered_len(), 0);
+        buffered.write(&test_messages[0]).unwrap();
+        drop(buffered);
+        let reader: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(reader.load().unwrap().unwrap().len(), 6);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "notify")]
+    #[test]
+    fn follow_yields_appended_documents() {
+        let path = temp_path("follow");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        let follow = client.follow().unwrap();
+        let writer = std::thread::spawn(move || {
+            client.write(&test_messages[1]).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            client.write(&test_messages[2]).unwrap();
+        });
+        let ids: Vec<usize> = follow
+            .take(2)
+            .map(|document| document.unwrap().id)
+            .collect();
+        writer.join().unwrap();
+        assert_eq!(ids, [2, 3]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn write_reports_first_write() {
+        let path = temp_path("write-report");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::new(&path, false).unwrap();
+        let report = client.write_many(&test_messages).unwrap();
+        assert!(report.created);
+        assert_eq!(report.bytes, std::fs::metadata(&path).unwrap().len());
+        assert!(!client.write(&test_messages[0]).unwrap().created);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn custom_flags_are_passed_to_open() {
+        let path = temp_path("custom-flags");
+        let link = temp_path("custom-flags-link");
+        std::fs::write(&path, []).unwrap();
+        std::os::unix::fs::symlink(&path, &link).unwrap();
+        let mut builder = ClientBuilder::new();
+        builder.append(true);
+        assert!(builder.open::<Test, Bincode, _>(&link).is_ok());
+        let Err(e) = builder
+            .custom_flags(libc::O_NOFOLLOW)
+            .open::<Test, Bincode, _>(&link)
+        else {
+            panic!("expected the symbolic link to be refused");
+        };
+        assert!(matches!(e, DatabaseError::Open { .. }));
+        std::fs::remove_file(link).unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restricted_permissions_survive_vacuum() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temp_path("restrict-permissions");
+        let test_messages = generate_test_data();
+        let mut client = ClientBuilder::new()
+            .restrict_permissions(true)
+            .open::<Test, Bincode, _>(&path)
+            .unwrap();
+        client.write_many(&test_messages).unwrap();
+        let mode = || std::fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(), 0o600);
+        client.trim_prefix(1).unwrap();
+        assert_eq!(mode(), 0o600);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn diff_reports_changes_and_additions() {
+        let path_a = temp_path("diff-a");
+        let path_b = temp_path("diff-b");
+        let test_messages = generate_test_data();
+        let mut a: Client<Test> = Client::create_with(&path_a, test_messages.clone()).unwrap();
+        let mut changed = test_messages[1].clone();
+        changed.id = 42;
+        let mut b: Client<Test> =
+            Client::create_with(&path_b, vec![test_messages[0].clone(), changed.clone()]).unwrap();
+        assert_eq!(
+            crate::diff(&mut a, &mut b).unwrap(),
+            [
+                crate::Diff::Changed {
+                    index: 1,
+                    a: test_messages[1].clone(),
+                    b: changed,
+                },
+                crate::Diff::OnlyInA {
+                    index: 2,
+                    document: test_messages[2].clone(),
+                },
+            ]
+        );
+        std::fs::remove_file(path_a).unwrap();
+        std::fs::remove_file(path_b).unwrap();
+    }
+
+    #[test]
+    fn reserve_keeps_length() {
+        let path = temp_path("reserve");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        let len = std::fs::metadata(&path).unwrap().len();
+        client.reserve(1 << 20).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), len);
+        client.write(&test_messages[0]).unwrap();
+        assert_eq!(client.load().unwrap().unwrap().len(), 4);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn encoding_matches_golden_bytes() {
+        let document = Test {
+            id: 1,
+            message: "a".to_string(),
+        };
+        let payload = [
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // id
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // length of message
+            0x61, // message
+        ];
+        let little =
+            vec_to_binary::<Bincode, _>(std::array::from_ref(&document), &Layout::default())
+                .unwrap();
+        assert_eq!(
+            little[..crate::FRAME_HEADER_LEN],
+            [0x73, 0x7f, 0x87, 0x63, 0x11, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(little[crate::FRAME_HEADER_LEN..], payload);
+
+        let path = temp_path("golden");
+        let mut client = ClientBuilder::new()
+            .endianness(Endianness::Big)
+            .open::<Test, Bincode, _>(&path)
+            .unwrap();
+        client.write(&document).unwrap();
+        let big = std::fs::read(&path).unwrap();
+        let (header, frame) = big.split_at(crate::FILE_HEADER_LEN);
+        assert_eq!(header[..4], crate::MAGIC);
+        assert_eq!(header[4], crate::FORMAT_VERSION);
+        assert_eq!(
+            header[4..],
+            [0x01, 0x00, 0x0c, 0x00, 0x01, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(
+            frame[..crate::FRAME_HEADER_LEN],
+            [0x63, 0x87, 0x7f, 0x73, 0x00, 0x00, 0x00, 0x11]
+        );
+        assert_eq!(frame[crate::FRAME_HEADER_LEN..], payload);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_into_reuses_vec() {
+        let path = temp_path("load-into");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        let mut out = Vec::with_capacity(16);
+        assert_eq!(client.load_into(&mut out).unwrap(), 3);
+        assert_eq!(client.load_into(&mut out).unwrap(), 3);
+        assert_eq!(out, test_messages);
+        assert_eq!(out.capacity(), 16);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn lock_guard_serializes_updates() {
+        let path = temp_path("lock-guard");
+        Client::<Test>::create_with(&path, generate_test_data()).unwrap();
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let mut client: Client<Test> = Client::new(&path, true).unwrap();
+                    let mut guard = client.lock().unwrap();
+                    let mut documents = guard.load().unwrap().unwrap();
+                    documents[0].id += 1;
+                    guard.replace_all_iter(documents).unwrap();
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let client: Client<Test> = Client::new(&path, true).unwrap();
+        assert_eq!(client.load().unwrap().unwrap()[0].id, 5);
+        std::fs::remove_file(&path).unwrap();
+        let mut lock_path = path.into_os_string();
+        lock_path.push(".lock");
+        std::fs::remove_file(lock_path).unwrap();
+    }
+
+    #[test]
+    fn positions_of_matches() {
+        let path = temp_path("positions");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages).unwrap();
+        assert_eq!(
+            client.positions(|document| document.id != 2).unwrap(),
+            [0, 2]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn append_only_client_keeps_history() {
+        let path = temp_path("append-only");
+        let test_messages = generate_test_data();
+        let mut client: crate::AppendOnlyClient<Test> =
+            crate::AppendOnlyClient::new(&path).unwrap();
+        assert_eq!(client.append_many(&test_messages[..2]).unwrap(), 2);
+        drop(client);
+        let mut client: crate::AppendOnlyClient<Test> =
+            crate::AppendOnlyClient::new(&path).unwrap();
+        assert_eq!(client.append_one(&test_messages[2]).unwrap(), 3);
+        assert_eq!(client.len().unwrap(), 3);
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        assert!(client.verify().unwrap().is_ok());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn verify_path_checks_files_of_any_type() {
+        let path = temp_path("verify-path");
+        let mut client: Client<Test> = Client::new(&path, true).unwrap();
+        client.write_many(&generate_test_data()).unwrap();
+        assert_eq!(crate::verify_path(&path).unwrap(), client.verify().unwrap());
+        let offset = client.scan_frames().unwrap()[1].offset;
+        let mut raw = std::fs::read(&path).unwrap();
+        raw[offset as usize + 12] ^= 0xff;
+        std::fs::write(&path, &raw).unwrap();
+        let report = crate::verify_path(&path).unwrap();
+        assert_eq!((report.valid, report.corrupted()), (2, 1));
+        assert_eq!(report.mismatches[0].0, offset);
+        assert!(matches!(
+            crate::verify_path(temp_path("verify-path-missing")),
+            Err(DatabaseError::Open { .. })
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn verify_checks_crc64_of_every_frame() {
+        let path = temp_path("verify-crc64");
+        let mut client: Client<Test> = ClientBuilder::new()
+            .append(true)
+            .checksum(crate::Checksum::Crc64)
+            .open(&path)
+            .unwrap();
+        client.write_many(&generate_test_data()).unwrap();
+        let frame = &client.scan_frames().unwrap()[1];
+        let (offset, start) = (
+            frame.offset as usize,
+            frame.offset as usize + crate::FRAME_HEADER_LEN,
+        );
+        let end = start + frame.len as usize;
+        let mut raw = std::fs::read(&path).unwrap();
+        raw[end - 1] ^= 0xff;
+        let checksum = client.layout.checksum(&raw[start..end]);
+        raw[offset..offset + 4].copy_from_slice(&checksum.to_le_bytes());
+        std::fs::write(&path, &raw).unwrap();
+        let report = client.verify().unwrap();
+        assert_eq!((report.valid, report.corrupted()), (2, 1));
+        assert!(report.mismatches.is_empty());
+        assert_eq!(report.mismatches64[0].0, offset as u64);
+        assert!(!report.is_ok());
+        assert_eq!(crate::verify_path(&path).unwrap(), report);
+        assert!(matches!(
+            client.append_raw_frames(&raw[offset..end], true),
+            Err(DatabaseError::MismatchedChecksum64 { .. })
+        ));
+        assert_eq!(std::fs::read(&path).unwrap(), raw);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn refresh_checksum_repairs_frame() {
+        let path = temp_path("refresh-checksum");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> = Client::create_with(&path, test_messages.clone()).unwrap();
+        let offset = client.scan_frames().unwrap()[1].offset;
+        let mut raw = std::fs::read(&path).unwrap();
+        raw[offset as usize] ^= 0xff;
+        std::fs::write(&path, &raw).unwrap();
+        assert!(client.load().is_err());
+        client.refresh_checksum_at(1).unwrap();
+        assert_eq!(client.load().unwrap().unwrap(), test_messages);
+        assert!(matches!(
+            client.refresh_checksum_at(3),
+            Err(DatabaseError::IndexOutOfRange { index: 3, len: 3 })
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn checksums_match_frames() {
+        let path = temp_path("checksums");
+        let test_messages = generate_test_data();
+        let mut client = ClientBuilder::new()
+            .tombstones(true)
+            .open::<Test, Bincode, _>(&path)
+            .unwrap();
+        client.write_many(&test_messages).unwrap();
+        let frames = client.scan_frames().unwrap();
+        assert!(client.remove_at(1).unwrap());
+        assert_eq!(
+            client.checksums().unwrap(),
+            [frames[0].checksum, frames[2].checksum]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn insert_at_splices_document() {
+        let path = temp_path("insert-at");
+        let test_messages = generate_test_data();
+        let mut client: Client<Test> =
+            Client::create_with(&path, test_messages[1..].to_vec()).unwrap();
+        client.insert_at(0, &test_messages[0]).unwrap();
+        client.insert_at(3, &test_messages[0]).unwrap();
+        let mut expected = test_messages.clone();
+        expected.push(test_messages[0].clone());
+        assert_eq!(client.load().unwrap().unwrap(), expected);
+        assert!(matches!(
+            client.insert_at(5, &test_messages[0]),
+            Err(DatabaseError::IndexOutOfRange { index: 5, len: 4 })
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    struct Nested {
+        name: String,
+        tags: Vec<String>,
+        inner: Option<Test>,
+    }
+
+    fn nested_strategy() -> impl Strategy<Value = Nested> {
+        let test = (any::<usize>(), any::<String>()).prop_map(|(id, message)| Test { id, message });
+        (
+            any::<String>(),
+            prop::collection::vec(any::<String>(), 0..8),
+            prop::option::of(test),
+        )
+            .prop_map(|(name, tags, inner)| Nested { name, tags, inner })
+    }
+
+    fn layout_strategy() -> impl Strategy<Value = Layout> {
+        (
+            prop_oneof![Just(Endianness::Little), Just(Endianness::Big)],
+            prop_oneof![Just(0), Just(8)],
+        )
+            .prop_map(|(endianness, alignment)| Layout {
+                endianness,
+                alignment,
+                ..Layout::default()
+            })
+    }
+
+    fn round_trip<T>(data: &[T], layout: &Layout) -> Vec<T>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let binary = vec_to_binary::<Bincode, _>(data, layout).unwrap();
+        binary_to_vec::<Bincode, _>(&binary, layout, true).unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip_integers(data in prop::collection::vec(any::<i64>(), 0..64), layout in layout_strategy()) {
+            prop_assert_eq!(round_trip(&data, &layout), data);
+        }
+
+        #[test]
+        fn round_trip_strings(data in prop::collection::vec(".*", 0..64), layout in layout_strategy()) {
+            prop_assert_eq!(round_trip(&data, &layout), data);
+        }
+
+        #[test]
+        fn round_trip_nested(data in prop::collection::vec(nested_strategy(), 0..16), layout in layout_strategy()) {
+            prop_assert_eq!(round_trip(&data, &layout), data);
+        }
+
+        #[test]
+        fn round_trip_large_payloads(data in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..16384), 0..4)) {
+            prop_assert_eq!(round_trip(&data, &Layout::default()), data);
+        }
+
+        #[test]
+        fn decoding_garbage_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..512), layout in layout_strategy()) {
+            let _ = binary_to_vec::<Bincode, Nested>(&bytes, &layout, true);
+            let _ = binary_to_vec::<Bincode, Nested>(&bytes, &layout, false);
+            let _ = verify_binary(&bytes, 0, &layout);
+        }
     }
 }
@@ -0,0 +1,105 @@
+use crate::{open_failed, Client, DatabaseError, Format};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::{File, OpenOptions, TryLockError},
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+};
+
+/// A client holding an exclusive lock on its file, obtained via [`Client::lock`]. Every
+/// method of the client is available through the guard, and the lock is released when
+/// the guard is dropped, including during a panic.
+///
+/// The lock is taken on a separate `.lock` file next to the file, so that it is kept when
+/// the file is replaced, e.g. by [`Client::vacuum`]. Locks are advisory: they only exclude
+/// other clients that lock the same file, not processes that write to it regardless.
+pub struct LockGuard<'a, T: Serialize + DeserializeOwned, F: Format> {
+    client: &'a mut Client<T, F>,
+    /// The lock taken by the guard, or `None` if the client already holds the lock for
+    /// its whole lifetime.
+    lock: Option<File>,
+}
+
+impl<'a, T, F> LockGuard<'a, T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+{
+    pub(crate) fn new(client: &'a mut Client<T, F>) -> Result<Self, DatabaseError> {
+        if client.lock.is_some() {
+            return Ok(Self { client, lock: None });
+        }
+        let lock = lock_file(&client.path, true)?;
+        // Another client may have replaced the file while this one was waiting, leaving
+        // this one with a handle to the old file.
+        client.reopen()?;
+        Ok(Self {
+            client,
+            lock: Some(lock),
+        })
+    }
+}
+
+impl<T, F> Deref for LockGuard<'_, T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+{
+    type Target = Client<T, F>;
+
+    fn deref(&self) -> &Self::Target {
+        self.client
+    }
+}
+
+impl<T, F> DerefMut for LockGuard<'_, T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.client
+    }
+}
+
+impl<T, F> Drop for LockGuard<'_, T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+{
+    fn drop(&mut self) {
+        if let Some(lock) = &self.lock {
+            let _ = lock.unlock();
+        }
+    }
+}
+
+/// Opens the lock file of the file at `path` and takes an exclusive lock on it, waiting
+/// for other clients to release it if `wait` is `true`. The lock is released when the
+/// returned handle is closed.
+pub(crate) fn lock_file(path: &Path, wait: bool) -> Result<File, DatabaseError> {
+    let lock_path = lock_path(path);
+    let lock = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&lock_path)
+        .map_err(open_failed(&lock_path))?;
+    if wait {
+        lock.lock()?;
+    } else {
+        match lock.try_lock() {
+            Ok(()) => (),
+            Err(TryLockError::WouldBlock) => return Err(DatabaseError::Locked(path.to_path_buf())),
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+    }
+    Ok(lock)
+}
+
+/// Returns the path of the file whose lock guards the file at `path`.
+fn lock_path(path: &Path) -> PathBuf {
+    let mut lock_path = path.to_path_buf().into_os_string();
+    lock_path.push(".lock");
+    lock_path.into()
+}
@@ -0,0 +1,78 @@
+use crate::{
+    binary_to_vec,
+    header::{Layout, FORMAT_VERSION},
+    open_failed, replace_file, Bincode, DatabaseError,
+};
+use serde::de::DeserializeOwned;
+use std::{
+    fs::OpenOptions,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+/// What [`migrate`] did to a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationOutcome {
+    /// The file already uses the latest version of the file format and was left as is.
+    AlreadyCurrent { version: u8 },
+    /// The file was rewritten from version `from` of the file format to version `to`.
+    /// Files without a header are version 0.
+    Upgraded { from: u8, to: u8, documents: usize },
+}
+
+/// Upgrades the file at `path` to the latest version of the file format, so that files
+/// written by older versions of this crate keep working as the format evolves.
+///
+/// Files without a header, which is how older versions of this crate write every file,
+/// are given a header that describes their layout. Every document is checked and
+/// deserialized as `T` first, so a file that is corrupted or holds other documents is
+/// left untouched. The file is rewritten into a temporary file which is then renamed
+/// over the original, so it is never left half-rewritten. Running this on a file that
+/// is already current does nothing.
+///
+/// # Errors
+///
+/// - `DatabaseError::Open` if it fails to open the file.
+///
+/// - `std::io::Error`, of kind `UnexpectedEof` if the file ends in the middle of
+///   a document.
+///
+/// - `DatabaseError::BadHeader` or `DatabaseError::UnsupportedVersion` if the file has a
+///   header this version of the crate cannot read.
+///
+/// - If a checksum mismatch occurs, a `DatabaseError::CorruptedFrame` error is
+///   returned.
+///
+/// - `bincode::Error` if a document fails to deserialize as `T`.
+pub fn migrate<T, P>(path: P) -> Result<MigrationOutcome, DatabaseError>
+where
+    T: DeserializeOwned,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(open_failed(path))?;
+    let mut layout = Layout::read(&mut file, None)?;
+    if layout.data_start > 0 {
+        return Ok(MigrationOutcome::AlreadyCurrent {
+            version: FORMAT_VERSION,
+        });
+    }
+    let mut data = Vec::new();
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut data)?;
+    let documents = binary_to_vec::<Bincode, T>(&data, &layout, true)?.len();
+    replace_file(&mut file, path, |temp| {
+        temp.write_all(&layout.encode_header())?;
+        temp.write_all(&data)?;
+        Ok(())
+    })?;
+    Ok(MigrationOutcome::Upgraded {
+        from: 0,
+        to: FORMAT_VERSION,
+        documents,
+    })
+}
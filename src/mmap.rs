@@ -0,0 +1,87 @@
+use crate::DatabaseError;
+use std::{fs::File, ops::Deref};
+
+/// The first `len` bytes of a file mapped into memory read-only, unmapped when dropped.
+/// Requires the `mmap` feature.
+///
+/// The mapping is shared with the file, so a write to the mapped bytes in the meantime,
+/// by this process or another one, changes them under the reader, and truncating the
+/// file below `len` makes reading past its new end fail with `SIGBUS`.
+#[cfg(unix)]
+pub(crate) struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+#[cfg(unix)]
+impl Mmap {
+    /// Maps the first `len` bytes of `file`, which must be more than zero.
+    pub(crate) fn map(file: &File, len: u64) -> Result<Self, DatabaseError> {
+        use std::os::unix::io::AsRawFd;
+
+        let len = usize::try_from(len)?;
+        // SAFETY: the mapping is created read-only from a descriptor that `file` keeps
+        // open for the duration of the call, and is only unmapped by `drop`.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Self { ptr, len })
+    }
+}
+
+#[cfg(unix)]
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: `ptr` points to `len` readable bytes until the mapping is dropped.
+        unsafe { std::slice::from_raw_parts(self.ptr.cast(), self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` and `len` describe a mapping created by `map` that is no longer
+        // borrowed.
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+/// Without memory mapping, the bytes are read into a buffer instead.
+#[cfg(not(unix))]
+pub(crate) struct Mmap(Vec<u8>);
+
+#[cfg(not(unix))]
+impl Mmap {
+    pub(crate) fn map(file: &File, len: u64) -> Result<Self, DatabaseError> {
+        use std::io::Read;
+
+        let mut buf = Vec::new();
+        crate::ReadAt { file, offset: 0 }
+            .take(len)
+            .read_to_end(&mut buf)?;
+        Ok(Self(buf))
+    }
+}
+
+#[cfg(not(unix))]
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
@@ -0,0 +1,296 @@
+use crate::{
+    count_documents, Bincode, Client, ClientBuilder, DatabaseError, Format, RecordIter, WriteReport,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    path::{Path, PathBuf},
+};
+
+/// Reads several files as a single collection, e.g. a log sharded into `data.0.crio`,
+/// `data.1.crio` and so on, and appends to the last one.
+///
+/// Documents are read file by file in the order in which the files were given, so an
+/// index into the collection is an index into the concatenation of every file. Writes
+/// always go to the last file, the active one. Once a size cap is set with
+/// [`rollover_at`](MultiClient::rollover_at), a new file is started whenever the active
+/// one has reached it.
+pub struct MultiClient<T: Serialize + DeserializeOwned, F: Format = Bincode> {
+    shards: Vec<Client<T, F>>,
+    builder: ClientBuilder,
+    rollover: Option<Rollover>,
+}
+
+/// When and where a [`MultiClient`] starts a new file.
+struct Rollover {
+    max_size: u64,
+    next_path: Box<dyn FnMut(usize) -> PathBuf + Send>,
+}
+
+impl<T, F> MultiClient<T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+{
+    /// Opens every file in `paths` in append mode, creating the ones that don't exist.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` if `paths` is empty.
+    ///
+    /// - See [`ClientBuilder::open`].
+    pub fn open<P: AsRef<Path>>(paths: &[P]) -> Result<Self, DatabaseError> {
+        Self::with_builder(paths, ClientBuilder::new())
+    }
+
+    /// Opens every file in `paths` with the options configured on `builder`, which is
+    /// always switched to append mode. Files started by a rollover are opened with the
+    /// same options.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Unsupported` if `paths` is empty.
+    ///
+    /// - See [`ClientBuilder::open`].
+    pub fn with_builder<P: AsRef<Path>>(
+        paths: &[P],
+        mut builder: ClientBuilder,
+    ) -> Result<Self, DatabaseError> {
+        if paths.is_empty() {
+            return Err(DatabaseError::Unsupported("no files to open"));
+        }
+        builder.append(true);
+        let shards = paths
+            .iter()
+            .map(|path| builder.open(path))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            shards,
+            builder,
+            rollover: None,
+        })
+    }
+
+    /// Starts a new file whenever the active file holds documents and has grown to
+    /// `max_size` bytes or more before a write. The new file is created at the path
+    /// returned by `next_path`, which is called with the position of the new file in the
+    /// list of files, e.g. `|n| format!("data.{n}.crio").into()`.
+    ///
+    /// A single write is never split across files, so a file can exceed `max_size` by
+    /// the size of the last write.
+    pub fn rollover_at<G>(&mut self, max_size: u64, next_path: G) -> &mut Self
+    where
+        G: FnMut(usize) -> PathBuf + Send + 'static,
+    {
+        self.rollover = Some(Rollover {
+            max_size,
+            next_path: Box::new(next_path),
+        });
+        self
+    }
+
+    /// Returns the paths of the files, in order. The last one is the active file.
+    pub fn paths(&self) -> Vec<&Path> {
+        self.shards
+            .iter()
+            .map(|shard| shard.path.as_path())
+            .collect()
+    }
+
+    /// Returns the number of documents in every file combined, by reading only the frame
+    /// headers unless the files have blocks. Removed documents are not counted.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`, of kind `UnexpectedEof` if a file ends in the middle of a
+    ///   document.
+    pub fn len(&mut self) -> Result<usize, DatabaseError> {
+        let mut len = 0;
+        for shard in &mut self.shards {
+            len += count_documents(&mut shard.file, &shard.layout)?;
+        }
+        Ok(len)
+    }
+
+    /// Returns `true` if no file holds any documents. See [`len`](MultiClient::len).
+    ///
+    /// # Errors
+    ///
+    /// See [`len`](MultiClient::len).
+    pub fn is_empty(&mut self) -> Result<bool, DatabaseError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Returns the documents of every file, in order. If every file is empty, this
+    /// method returns `Ok(None)`. See [`Client::load`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::load`].
+    pub fn load(&mut self) -> Result<Option<Vec<T>>, DatabaseError> {
+        let mut documents = None;
+        for shard in &mut self.shards {
+            if let Some(loaded) = shard.load()? {
+                documents.get_or_insert_with(Vec::new).extend(loaded);
+            }
+        }
+        Ok(documents)
+    }
+
+    /// Returns an iterator over the documents of every file, in order. Like
+    /// [`Client::iter`], documents are read one at a time and a document that fails its
+    /// checksum or fails to decode is yielded as an error without stopping the iterator.
+    pub fn iter(&mut self) -> MultiIter<'_, T, F> {
+        MultiIter {
+            shards: self.shards.iter_mut(),
+            current: None,
+        }
+    }
+
+    /// Returns an iterator over the documents of every file in the order of their keys, as
+    /// returned by `key`, assuming that every file is sorted by key, e.g. because its
+    /// documents were sorted before they were written. The files are merged as they are
+    /// read, holding a single document per file in memory, so the whole collection never
+    /// has to be sorted. Documents with equal keys come in the order of their files.
+    ///
+    /// If a file is not sorted, every document is still returned, but not in order. The
+    /// iterator stops after the first error in any file.
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::iter`].
+    pub fn iter_sorted<'a, K, G>(
+        &'a mut self,
+        key: G,
+    ) -> impl Iterator<Item = Result<T, DatabaseError>> + 'a
+    where
+        K: Ord + 'a,
+        G: Fn(&T) -> K + 'a,
+    {
+        let mut shards: Vec<_> = self.shards.iter_mut().map(Client::iter).collect();
+        let mut heads = BinaryHeap::with_capacity(shards.len());
+        let mut failed = None;
+        for (shard, documents) in shards.iter_mut().enumerate() {
+            match documents.next() {
+                Some(Ok(document)) => heads.push(Reverse(Head::new(&key, shard, document))),
+                Some(Err(e)) => {
+                    failed = Some(e);
+                    break;
+                }
+                None => (),
+            }
+        }
+        std::iter::from_fn(move || {
+            if let Some(e) = failed.take() {
+                heads.clear();
+                return Some(Err(e));
+            }
+            let Reverse(head) = heads.pop()?;
+            match shards[head.shard].next() {
+                Some(Ok(document)) => heads.push(Reverse(Head::new(&key, head.shard, document))),
+                Some(Err(e)) => failed = Some(e),
+                None => (),
+            }
+            Some(Ok(head.document))
+        })
+    }
+
+    /// Appends a document to the active file, rolling over first if needed. See
+    /// [`Client::write`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::write`] and [`ClientBuilder::open`].
+    pub fn write(&mut self, document: &T) -> Result<WriteReport, DatabaseError> {
+        self.active()?.write(document)
+    }
+
+    /// Appends documents to the active file, rolling over first if needed. See
+    /// [`Client::write_many`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::write_many`] and [`ClientBuilder::open`].
+    pub fn write_many(&mut self, documents: &[T]) -> Result<WriteReport, DatabaseError> {
+        self.active()?.write_many(documents)
+    }
+
+    /// Returns the client of the file to write to, starting a new file if the active one
+    /// has reached the size cap.
+    fn active(&mut self) -> Result<&mut Client<T, F>, DatabaseError> {
+        if let Some(rollover) = &mut self.rollover {
+            let active = &self.shards[self.shards.len() - 1];
+            let len = active.file.metadata()?.len();
+            if len > active.layout.data_start && len >= rollover.max_size {
+                let path = (rollover.next_path)(self.shards.len());
+                let shard = self.builder.open(path)?;
+                self.shards.push(shard);
+            }
+        }
+        let last = self.shards.len() - 1;
+        Ok(&mut self.shards[last])
+    }
+}
+
+/// The next document of a file during [`MultiClient::iter_sorted`], ordered by its key
+/// and then by the position of its file.
+struct Head<K, T> {
+    key: K,
+    shard: usize,
+    document: T,
+}
+
+impl<K, T> Head<K, T> {
+    fn new<G: Fn(&T) -> K>(key: &G, shard: usize, document: T) -> Self {
+        Self {
+            key: key(&document),
+            shard,
+            document,
+        }
+    }
+}
+
+impl<K: Ord, T> Ord for Head<K, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.key, self.shard).cmp(&(&other.key, other.shard))
+    }
+}
+
+impl<K: Ord, T> PartialOrd for Head<K, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, T> PartialEq for Head<K, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<K: Ord, T> Eq for Head<K, T> {}
+
+/// An iterator over the documents of every file of a [`MultiClient`], returned by
+/// [`MultiClient::iter`].
+pub struct MultiIter<'a, T: Serialize + DeserializeOwned, F: Format> {
+    shards: std::slice::IterMut<'a, Client<T, F>>,
+    current: Option<RecordIter<'a, T, F>>,
+}
+
+impl<T, F> Iterator for MultiIter<'_, T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+{
+    type Item = Result<T, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(document) = self.current.as_mut().and_then(Iterator::next) {
+                return Some(document);
+            }
+            self.current = Some(self.shards.next()?.iter());
+        }
+    }
+}
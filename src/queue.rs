@@ -0,0 +1,158 @@
+use crate::{
+    append_or_rollback, header::Layout, open_failed, process_document, replace_file, vec_to_binary,
+    Bincode, DatabaseError,
+};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// Size of the read cursor stored at the start of a queue file.
+const CURSOR_LEN: u64 = 8;
+
+/// Popped documents are only reclaimed once they take up at least this many bytes.
+const COMPACT_THRESHOLD: u64 = 4096;
+
+/// A durable first-in, first-out queue of documents.
+///
+/// A queue file starts with the offset of the oldest document that has not been popped
+/// yet, followed by documents stored in the same format as a [`Client`](crate::Client).
+/// Pushing a document appends it to the end of the file while popping a document only
+/// moves the stored offset forward, so neither operation rewrites the file.
+///
+/// The space taken up by popped documents is reclaimed once it takes up at least 4 KiB
+/// and more space than the documents still in the queue. Compaction copies the remaining
+/// documents into a temporary file which is then renamed over the queue file, so the
+/// amortized cost of popping stays proportional to the size of a document.
+pub struct CrioQueue<T: Serialize + DeserializeOwned> {
+    file: File,
+    path: PathBuf,
+    cursor: u64,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+impl<T> CrioQueue<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Opens the queue stored at `path`, creating an empty queue if the file doesn't
+    /// exist.
+    ///
+    /// # Errors
+    ///
+    /// - `DatabaseError::Open` if it fails to open or create a new file.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, DatabaseError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path.as_ref())
+            .map_err(open_failed(path.as_ref()))?;
+        let cursor = if file.metadata()?.len() == 0 {
+            file.write_u64::<LittleEndian>(CURSOR_LEN)?;
+            CURSOR_LEN
+        } else {
+            file.read_u64::<LittleEndian>()?
+        };
+        Ok(Self {
+            file,
+            path: path.as_ref().to_path_buf(),
+            cursor,
+            _phantom: std::marker::PhantomData,
+        })
+    }
+
+    /// Adds a document to the back of the queue.
+    ///
+    /// # Errors
+    ///
+    /// - `std::num::TryFromIntError` occurs when the document takes up more space
+    ///   than `u32::MAX` bytes.
+    ///
+    /// - `std::io::Error`
+    ///
+    /// - `bincode::Error` when the document provided fails to serialize for some reason.
+    pub fn push(&mut self, document: &T) -> Result<(), DatabaseError> {
+        let buf = vec_to_binary::<Bincode, _>(std::array::from_ref(document), &Layout::default())?;
+        let len = self.file.seek(SeekFrom::End(0))?;
+        append_or_rollback(&mut self.file, len, |file| Ok(file.write_all(&buf)?))
+    }
+
+    /// Returns the document at the front of the queue without removing it, or `Ok(None)`
+    /// if the queue is empty.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned.
+    ///
+    /// - `bincode::Error` if the document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    pub fn peek(&mut self) -> Result<Option<T>, DatabaseError> {
+        Ok(self.read_front()?.map(|(document, _)| document))
+    }
+
+    /// Removes the document at the front of the queue and returns it, or returns
+    /// `Ok(None)` if the queue is empty.
+    ///
+    /// # Errors
+    ///
+    /// - If a checksum mismatch occurs, a `DatabaseError::MismatchedChecksum` error
+    ///   is returned and the document is not removed.
+    ///
+    /// - `bincode::Error` if the document fails to deserialize.
+    ///
+    /// - `std::io::Error`
+    pub fn pop(&mut self) -> Result<Option<T>, DatabaseError> {
+        let Some((document, next)) = self.read_front()? else {
+            return Ok(None);
+        };
+        self.cursor = next;
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_u64::<LittleEndian>(self.cursor)?;
+        let consumed = self.cursor - CURSOR_LEN;
+        let remaining = self.file.metadata()?.len() - self.cursor;
+        if consumed >= COMPACT_THRESHOLD && consumed > remaining {
+            self.compact()?;
+        }
+        Ok(Some(document))
+    }
+
+    /// Returns `true` if there are no documents left in the queue.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn is_empty(&mut self) -> Result<bool, DatabaseError> {
+        Ok(self.cursor >= self.file.metadata()?.len())
+    }
+
+    fn read_front(&mut self) -> Result<Option<(T, u64)>, DatabaseError> {
+        if self.is_empty()? {
+            return Ok(None);
+        }
+        self.file.seek(SeekFrom::Start(self.cursor))?;
+        let raw_doc = process_document(&mut self.file, &Layout::default())?;
+        let next = self.file.stream_position()?;
+        let document = bincode::deserialize(&raw_doc)?;
+        Ok(Some((document, next)))
+    }
+
+    fn compact(&mut self) -> Result<(), DatabaseError> {
+        let mut remaining = Vec::new();
+        self.file.seek(SeekFrom::Start(self.cursor))?;
+        self.file.read_to_end(&mut remaining)?;
+        replace_file(&mut self.file, &self.path, |temp| {
+            temp.write_u64::<LittleEndian>(CURSOR_LEN)?;
+            temp.write_all(&remaining)?;
+            Ok(())
+        })?;
+        self.cursor = CURSOR_LEN;
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{vec_to_binary, Bincode, Client, DatabaseError, Format};
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::{Duration, Instant};
+
+/// Wraps a [`Client`] to persist the latest state of a collection at most once per
+/// interval, e.g. for an application that updates its state often but should rarely
+/// write it to disk.
+///
+/// [`set`](ThrottledClient::set) only records the desired contents of the file, which
+/// replace the whole file the next time the client is ticked after the interval has
+/// elapsed. States set in between are coalesced, so only the latest one is written. The
+/// pending state is also written when the client is flushed or dropped.
+pub struct ThrottledClient<T: Serialize + DeserializeOwned, F: Format = Bincode> {
+    client: Client<T, F>,
+    interval: Duration,
+    last_persist: Option<Instant>,
+    pending: Option<Vec<u8>>,
+}
+
+impl<T, F> ThrottledClient<T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+{
+    /// Wraps `client`, writing to it at most once per `interval`.
+    pub fn new(client: Client<T, F>, interval: Duration) -> Self {
+        Self {
+            client,
+            interval,
+            last_persist: None,
+            pending: None,
+        }
+    }
+
+    /// Sets the documents the file should contain, replacing any pending state, and
+    /// writes them right away if the interval has elapsed since the last write. Returns
+    /// whether they were written.
+    ///
+    /// The documents are encoded immediately, so they do not need to be kept around.
+    ///
+    /// # Errors
+    ///
+    /// - `std::num::TryFromIntError` occurs when a document takes up more space than
+    ///   `u32::MAX` bytes.
+    ///
+    /// - `bincode::Error` when a document fails to serialize for some reason.
+    ///
+    /// - `std::io::Error`
+    pub fn set(&mut self, documents: &[T]) -> Result<bool, DatabaseError> {
+        self.pending = Some(vec_to_binary::<F, _>(documents, &self.client.layout)?);
+        self.tick()
+    }
+
+    /// Writes the pending state if there is one and the interval has elapsed since the
+    /// last write, returning whether it was written. Call this periodically, e.g. from
+    /// the event loop of the application, so that the last state set is written even if
+    /// no further state is set.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn tick(&mut self) -> Result<bool, DatabaseError> {
+        if self
+            .last_persist
+            .is_some_and(|last_persist| last_persist.elapsed() < self.interval)
+        {
+            return Ok(false);
+        }
+        self.flush()
+    }
+
+    /// Writes the pending state right away if there is one, returning whether it was
+    /// written. The file is replaced atomically, the same way as in
+    /// [`Client::vacuum`].
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn flush(&mut self) -> Result<bool, DatabaseError> {
+        let Some(pending) = self.pending.take() else {
+            return Ok(false);
+        };
+        if let Err(e) = self.client.replace_data(&pending) {
+            self.pending = Some(pending);
+            return Err(e);
+        }
+        self.last_persist = Some(Instant::now());
+        Ok(true)
+    }
+
+    /// Returns `true` if a state was set that has not been written yet.
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+impl<T, F> Drop for ThrottledClient<T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+{
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{append_frames, strip_footer, vec_to_binary, Backend, Client, DatabaseError, Format};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fs::File;
+
+/// A batch of documents that is buffered in memory and written to disk all at once,
+/// obtained via [`Client::begin`].
+///
+/// Nothing is written until [`commit`](Transaction::commit) is called. Dropping a
+/// transaction without committing it discards the buffered documents.
+pub struct Transaction<'a, T: Serialize + DeserializeOwned, F: Format, B: Backend = File> {
+    client: &'a mut Client<T, F, B>,
+    buf: Vec<u8>,
+}
+
+impl<'a, T, F, B> Transaction<'a, T, F, B>
+where
+    T: Serialize + DeserializeOwned,
+    F: Format,
+    B: Backend,
+{
+    pub(crate) fn new(client: &'a mut Client<T, F, B>) -> Self {
+        Self {
+            client,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Adds a document to the transaction.
+    ///
+    /// # Errors
+    ///
+    /// - `std::num::TryFromIntError` occurs when the document takes up more space
+    ///   than `u32::MAX` bytes.
+    ///
+    /// - `bincode::Error` when the document provided fails to serialize for some reason.
+    pub fn write(&mut self, document: &T) -> Result<(), DatabaseError> {
+        let buf = vec_to_binary::<F, _>(std::array::from_ref(document), &self.client.layout)?;
+        self.buf.extend_from_slice(&buf);
+        Ok(())
+    }
+
+    /// Adds the provided documents to the transaction.
+    ///
+    /// # Errors
+    ///
+    /// - `std::num::TryFromIntError` occurs when a document takes up more space
+    ///   than `u32::MAX` bytes.
+    ///
+    /// - `bincode::Error` when the documents provided fails to serialize for some reason.
+    pub fn write_many(&mut self, documents: &[T]) -> Result<(), DatabaseError> {
+        let buf = vec_to_binary::<F, _>(documents, &self.client.layout)?;
+        self.buf.extend_from_slice(&buf);
+        Ok(())
+    }
+
+    /// Writes every buffered document to the end of the file in a single write. If the
+    /// write fails, the file is truncated back to its previous length so that no partial
+    /// document is left behind.
+    ///
+    /// # Errors
+    ///
+    /// - `std::io::Error`
+    pub fn commit(self) -> Result<(), DatabaseError> {
+        let file = &mut self.client.file;
+        let len = strip_footer(file, &self.client.layout)?;
+        append_frames(file, &self.client.layout, len, |file| {
+            Ok(file.write_all(&self.buf)?)
+        })?;
+        self.client.commit_overwrite()
+    }
+
+    /// Discards every buffered document. This is equivalent to dropping the transaction.
+    pub fn rollback(self) {}
+}